@@ -0,0 +1,80 @@
+// Compares collecting the checked cells of 100k values, in 10 batches of 10k, with and without
+// assign_batch_into:
+//     cargo bench --bench batch
+// Each batch collected by assign_batch_parallel gets its own Vec, which is then moved into the combined one,
+// while assign_batch_into appends every batch to the one buffer. The bytes each way allocates are printed
+// before the timings.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::bench::{synthesize_batches, Batches};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// Counts the bytes allocated, and the most that were live at once
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(live, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const NUM_VALUES: u64 = 100_000;
+const NUM_BATCHES: u64 = 10;
+
+fn bench_batches(c: &mut Criterion) {
+    let batch_len = NUM_VALUES / NUM_BATCHES;
+    let values: Vec<Vec<u64>> = (0..NUM_BATCHES)
+        .map(|batch| {
+            (0..batch_len)
+                .map(|i| (batch * batch_len + i) % 16)
+                .collect()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("batch range check, 100k values");
+    group.sample_size(10);
+    for (name, reuse_buffer) in [
+        ("assign_batch_parallel", false),
+        ("assign_batch_into", true),
+    ] {
+        let batches = Batches::new(&values, reuse_buffer);
+
+        ALLOCATED.store(0, Ordering::Relaxed);
+        PEAK.store(LIVE.load(Ordering::Relaxed), Ordering::Relaxed);
+        let live = LIVE.load(Ordering::Relaxed);
+        synthesize_batches(&batches).unwrap();
+        println!(
+            "{}: {} bytes allocated, peak {} bytes over the {} live before",
+            name,
+            ALLOCATED.load(Ordering::Relaxed),
+            PEAK.load(Ordering::Relaxed) - live,
+            live
+        );
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), &batches, |b, batches| {
+            b.iter(|| synthesize_batches(batches).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_batches);
+criterion_main!(benches);
@@ -0,0 +1,29 @@
+// Measures keygen_vk + keygen_pk for the brute-force range check as RANGE grows:
+//     cargo bench --bench keygen
+// The product gate has degree RANGE + 1, so the extended domain keygen evaluates over doubles with each step,
+// which is where the lookup range check pulls ahead.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::bench::{brute_force_shape, keygen_brute_force};
+use halo2_proofs::{pasta::EqAffine, poly::commitment::Params};
+
+fn bench_range<const RANGE: usize>(c: &mut Criterion) {
+    let (k, degree) = brute_force_shape::<RANGE>();
+    println!("RANGE = {}: k = {}, degree = {}", RANGE, k, degree);
+    let params: Params<EqAffine> = Params::new(k);
+    let mut group = c.benchmark_group("keygen brute-force range check");
+    group.sample_size(10);
+    group.bench_with_input(BenchmarkId::from_parameter(RANGE), &params, |b, params| {
+        b.iter(|| keygen_brute_force::<RANGE>(params).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_range::<8>,
+    bench_range::<16>,
+    bench_range::<32>,
+    bench_range::<64>
+);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+// Runs every example circuit through the MockProver and prints pass/fail for each:
+//     cargo run --example demo
+// Exits with a nonzero status if any of them fails.
+
+fn main() {
+    if !halo2_examples::demo::run_all() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,244 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig,
+    less_than::{field_cmp, LessThanChip, LessThanConfig},
+};
+
+/// Computes `out = |a - b|` for `a` and `b` in `0..2^N_BITS`. The sign $s = (a < b)$ comes from the less-than
+/// chip, and selects which way round to subtract:
+///     $$out = (a - b) + s \cdot 2 (b - a)$$
+/// `out` is then range-checked to however many bits the caller asks for, which also bounds the distance.
+///
+/// As with the less-than gadget, the inputs themselves are not range-checked here.
+#[derive(Debug, Clone)]
+struct AbsDiffConfig<F: PrimeField, const N_BITS: usize> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    sign: Column<Advice>,
+    out: Column<Advice>,
+    q_abs_diff: Selector,
+    less_than: LessThanConfig<F, N_BITS>,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> AbsDiffConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let sign = meta.advice_column();
+        let out = meta.advice_column();
+        let q_abs_diff = meta.selector();
+        let less_than = LessThanChip::configure(meta);
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [a, b, sign, out] {
+            meta.enable_equality(column);
+        }
+
+        //     a    |    b    |  sign  |         out          | q_abs_diff
+        //    ---------------------------------------------------------------
+        //     a    |    b    | a < b  | a - b + s * 2(b - a) |     1
+        meta.create_gate("abs diff", |meta| {
+            let q = meta.query_selector(q_abs_diff);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let sign = meta.query_advice(sign, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let two = Expression::Constant(F::from(2));
+            Constraints::with_selector(
+                q,
+                [(
+                    "out = a - b + s * 2(b - a)",
+                    out - (a.clone() - b.clone() + sign * two * (b - a)),
+                )],
+            )
+        });
+
+        Self {
+            a,
+            b,
+            sign,
+            out,
+            q_abs_diff,
+            less_than,
+            range,
+        }
+    }
+}
+
+// The absolute difference, and the sign bit
+type AbsDiff<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+#[derive(Debug, Clone)]
+struct AbsDiffChip<F: PrimeField, const N_BITS: usize> {
+    config: AbsDiffConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> AbsDiffChip<F, N_BITS> {
+    fn construct(config: AbsDiffConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> AbsDiffConfig<F, N_BITS> {
+        AbsDiffConfig::configure(meta)
+    }
+
+    /// Returns `|a - b|`, range-checked to `bits` bits, and the sign bit `a < b`.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        bits: usize,
+    ) -> Result<AbsDiff<F>, Error> {
+        let out = a.value().zip(b.value()).map(|(a, b)| {
+            if field_cmp(a, b).is_lt() {
+                *b - *a
+            } else {
+                *a - *b
+            }
+        });
+        self.assign_out(layouter, a, b, bits, out)
+    }
+
+    // Like assign, but with the given witness for out
+    fn assign_out(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        bits: usize,
+        out: Value<F>,
+    ) -> Result<AbsDiff<F>, Error> {
+        let config = &self.config;
+        let sign = LessThanChip::construct(config.less_than.clone()).assign(
+            layouter.namespace(|| "a < b"),
+            a,
+            b,
+        )?;
+        let (out, sign) = layouter.assign_region(
+            || "abs diff",
+            |mut region| {
+                let offset = 0;
+                config.q_abs_diff.enable(&mut region, offset)?;
+                a.copy_advice(|| "a", &mut region, config.a, offset)?;
+                b.copy_advice(|| "b", &mut region, config.b, offset)?;
+                let sign = sign.copy_advice(|| "sign", &mut region, config.sign, offset)?;
+                let out = region.assign_advice(|| "out", config.out, offset, || out)?;
+                Ok((out, sign))
+            },
+        )?;
+        let out = config
+            .range
+            .assign(layouter.namespace(|| "range check out"), &out, bits)?;
+        Ok((out, sign))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 8;
+
+    // Takes |a - b| of the private inputs to BITS bits, exposing it at instance row 0 and the sign at row 1.
+    // The witness for `out` can be forged.
+    #[derive(Default)]
+    struct AbsDiffCircuit<F: PrimeField, const BITS: usize> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField, const BITS: usize> ForgeableCircuit<F> for AbsDiffCircuit<F, BITS> {
+        type Config = (AbsDiffConfig<F, N_BITS>, Column<Advice>, Column<Instance>);
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (AbsDiffChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            out: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "Load a and b",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            let chip = AbsDiffChip::construct(config);
+            let (out, sign) = match out {
+                Some(out) => chip.assign_out(layouter.namespace(|| "|a - b|"), &a, &b, BITS, *out),
+                None => chip.assign(layouter.namespace(|| "|a - b|"), &a, &b, BITS),
+            }?;
+            layouter.constrain_instance(out.cell(), instance, 0)?;
+            layouter.constrain_instance(sign.cell(), instance, 1)
+        }
+    }
+
+    fn circuit<const BITS: usize>(a: u64, b: u64) -> Forged<Fp, AbsDiffCircuit<Fp, BITS>> {
+        Forged::honest(AbsDiffCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        })
+    }
+
+    #[test]
+    fn test_abs_diff() {
+        // a > b, a < b and a == b, up to the extremes of N_BITS
+        for (a, b) in [(12, 10), (10, 12), (7, 7), (0, 0), (255, 0), (0, 255)] {
+            let (out, sign) = (Fp::from(a.max(b) - a.min(b)), (a < b) as u64);
+            let public = |out, sign| [out, Fp::from(sign)];
+            assert!(run(&circuit::<8>(a, b), &public(out, sign)), "{} {}", a, b);
+            // The wrong sign, or the difference the other way round
+            assert!(!run(&circuit::<8>(a, b), &public(out, 1 - sign)));
+            if a != b {
+                assert!(!run(&circuit::<8>(a, b), &public(-out, sign)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_abs_diff_bits() {
+        // |a - b| = 16 doesn't fit in 4 bits, though 15 does
+        assert!(run(&circuit::<4>(20, 5), &[Fp::from(15), Fp::from(0)]));
+        assert!(!run(&circuit::<4>(21, 5), &[Fp::from(16), Fp::from(0)]));
+    }
+
+    #[test]
+    fn test_abs_diff_forged_out() {
+        for (a, b, forged) in [
+            // The signed difference, which wraps around the field
+            (10, 12, -Fp::from(2)),
+            // Off by one, and a difference for a == b
+            (12, 10, Fp::from(3)),
+            (7, 7, Fp::from(1)),
+        ] {
+            let circuit = circuit::<8>(a, b).forge(Value::known(forged));
+            let sign = Fp::from((a < b) as u64);
+            assert!(!run(&circuit, &[forged, sign]), "{} {}", a, b);
+        }
+    }
+}
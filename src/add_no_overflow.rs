@@ -0,0 +1,208 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+/// Adds two cells, $c = a + b$, where $a$, $b$ and $c$ are all range-checked to `num_bits` bits with the bits
+/// range check. The sum of two `num_bits`-bit values can carry into one more bit, so the check on $c$ is what
+/// catches an overflow: a sum past the bound fails verification rather than silently needing a wider type.
+#[derive(Debug, Clone)]
+struct AddNoOverflowConfig<F: PrimeField> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    q_add: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> AddNoOverflowConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let q_add = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [a, b, c] {
+            meta.enable_equality(column);
+        }
+
+        //     a    |    b    |    c    | q_add
+        //    -------------------------------------
+        //     a    |    b    |  a + b  |   1
+        meta.create_gate("add", |meta| {
+            let q = meta.query_selector(q_add);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            Constraints::with_selector(q, [("c = a + b", c - (a + b))])
+        });
+
+        Self {
+            a,
+            b,
+            c,
+            q_add,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AddNoOverflowChip<F: PrimeField> {
+    config: AddNoOverflowConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> AddNoOverflowChip<F> {
+    fn construct(config: AddNoOverflowConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> AddNoOverflowConfig<F> {
+        AddNoOverflowConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to `a + b`, where the addends and the sum must all fit in `num_bits` bits.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let c = a.value().zip(b.value()).map(|(a, b)| *a + *b);
+        self.assign_sum(layouter, a, b, c, num_bits)
+    }
+
+    fn assign_sum(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        c: Value<F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        for (name, cell) in [("a", a), ("b", b)] {
+            config.range.assign(
+                layouter.namespace(|| format!("range check {}", name)),
+                cell,
+                num_bits,
+            )?;
+        }
+        let c = layouter.assign_region(
+            || "add",
+            |mut region| {
+                let offset = 0;
+                config.q_add.enable(&mut region, offset)?;
+                a.copy_advice(|| "a", &mut region, config.a, offset)?;
+                b.copy_advice(|| "b", &mut region, config.b, offset)?;
+                region.assign_advice(|| "c", config.c, offset, || c)
+            },
+        )?;
+        config
+            .range
+            .assign(layouter.namespace(|| "range check c"), &c, num_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const NUM_BITS: usize = 8;
+
+    // Adds `a` and `b`, exposing the sum at instance row 0. The witness for the sum `c` can be forged.
+    #[derive(Default)]
+    struct AddNoOverflowCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for AddNoOverflowCircuit<F> {
+        type Config = (AddNoOverflowConfig<F>, Column<Advice>, Column<Instance>);
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (AddNoOverflowChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            c: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "Load a and b",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            let chip = AddNoOverflowChip::construct(config);
+            let c = match c {
+                Some(c) => chip.assign_sum(layouter.namespace(|| "a + b"), &a, &b, *c, NUM_BITS),
+                None => chip.assign(layouter.namespace(|| "a + b"), &a, &b, NUM_BITS),
+            }?;
+            layouter.constrain_instance(c.cell(), instance, 0)
+        }
+    }
+
+    fn circuit(a: Fp, b: Fp) -> Forged<Fp, AddNoOverflowCircuit<Fp>> {
+        Forged::honest(AddNoOverflowCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        })
+    }
+
+    fn add(a: u64, b: u64) -> Forged<Fp, AddNoOverflowCircuit<Fp>> {
+        circuit(Fp::from(a), Fp::from(b))
+    }
+
+    #[test]
+    fn test_add_no_overflow() {
+        for (a, b) in [(0, 0), (1, 2), (100, 155), (255, 0), (128, 127)] {
+            assert!(run(&add(a, b), &[Fp::from(a + b)]));
+            assert!(!run(&add(a, b), &[Fp::from(a + b + 1)]));
+        }
+    }
+
+    #[test]
+    fn test_add_overflow() {
+        // Sums of 256 and up overflow 8 bits, even though both addends fit
+        for (a, b) in [(128, 128), (255, 1), (255, 255)] {
+            assert!(!run(&add(a, b), &[Fp::from(a + b)]));
+        }
+        // An addend past the bound is rejected too, even when the sum would wrap back into range
+        assert!(!run(&circuit(Fp::from(5), -Fp::ONE), &[Fp::from(4)]));
+        assert!(!run(&add(256, 0), &[Fp::from(256)]));
+    }
+
+    #[test]
+    fn test_add_malicious_sum() {
+        // 12 is in range, but isn't 3 + 5
+        let malicious = add(3, 5).forge(Value::known(Fp::from(12)));
+        assert!(!run(&malicious, &[Fp::from(12)]));
+
+        let honest = add(3, 5).forge(Value::known(Fp::from(8)));
+        assert!(run(&honest, &[Fp::from(8)]));
+    }
+}
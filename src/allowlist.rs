@@ -0,0 +1,190 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::table::RangeTableConfig;
+
+/// Proves a value is one of a fixed allowlist, by looking it up in a table holding exactly the allowlist. Rows
+/// without the selector look up the first entry instead of 0, so 0 doesn't have to be allowed:
+///     $$q \cdot value + (1 - q) \cdot a_0 \in \{a_0, \dots, a_{n-1}\}$$
+/// Tables are a power of two long, so the allowlist is padded out with repeats of its first entry.
+///
+/// Every table row takes a row of the circuit, so an allowlist of $n$ entries needs $2^k$ to be above $n$ plus
+/// the blinding rows: each doubling of the allowlist can cost a doubling of every column. Below a few entries,
+/// a product of $(value - a_i)$ in a gate is cheaper, as in the brute-force range check.
+///
+/// A lookup can only show membership, so this asserts it rather than returning a boolean: a prover claiming
+/// "not a member" could never be held to it.
+#[derive(Debug, Clone)]
+struct AllowlistConfig<F: PrimeField> {
+    value: Column<Advice>,
+    q_lookup: Selector,
+    table: RangeTableConfig<F, 0>,
+}
+
+impl<F: PrimeField> AllowlistConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, allowlist: &[F]) -> Self {
+        assert!(!allowlist.is_empty(), "The allowlist is empty");
+        let value = meta.advice_column();
+        let q_lookup = meta.complex_selector();
+        let mut entries = allowlist.to_vec();
+        entries.resize(allowlist.len().next_power_of_two(), allowlist[0]);
+        let table = RangeTableConfig::with_entries(meta, entries);
+        meta.enable_equality(value);
+
+        //     value   | q_lookup
+        //    --------------------
+        //       v     |    1
+        let first = allowlist[0];
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let value = meta.query_advice(value, Rotation::cur());
+            let not_q = Expression::Constant(F::ONE) - q.clone();
+            vec![(q * value + not_q * Expression::Constant(first), table.value)]
+        });
+
+        Self {
+            value,
+            q_lookup,
+            table,
+        }
+    }
+
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.table.load(layouter)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AllowlistChip<F: PrimeField> {
+    config: AllowlistConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> AllowlistChip<F> {
+    fn construct(config: AllowlistConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, allowlist: &[F]) -> AllowlistConfig<F> {
+        AllowlistConfig::configure(meta, allowlist)
+    }
+
+    /// Copies `value` into a new region and constrains it to be on the allowlist. Returns the copied cell.
+    fn assert_member(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "allowlist",
+            |mut region| {
+                config.q_lookup.enable(&mut region, 0)?;
+                value.copy_advice(|| "value", &mut region, config.value, 0)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    // The first 64 primes, so 0 and 1 aren't on it and the gaps vary
+    fn primes() -> Vec<u64> {
+        (2u64..)
+            .filter(|n| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+            .take(64)
+            .collect()
+    }
+
+    // Checks each of the private values is one of the first 64 primes, or of the first ALLOWED of them
+    #[derive(Default)]
+    struct AllowlistCircuit<F: PrimeField, const ALLOWED: usize> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: PrimeField, const ALLOWED: usize> Circuit<F> for AllowlistCircuit<F, ALLOWED> {
+        type Config = (AllowlistConfig<F>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            let allowlist: Vec<F> = primes().into_iter().take(ALLOWED).map(F::from).collect();
+            (AllowlistChip::configure(meta, &allowlist), input)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            let chip = AllowlistChip::construct(config);
+            for (i, value) in self.values.iter().enumerate() {
+                let value = layouter.assign_region(
+                    || format!("Load value {}", i),
+                    |mut region| region.assign_advice(|| "value", input, 0, || *value),
+                )?;
+                chip.assert_member(layouter.namespace(|| format!("member {}", i)), &value)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn run<const ALLOWED: usize>(values: &[u64]) -> bool {
+        let circuit = AllowlistCircuit::<Fp, ALLOWED> {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        };
+        run_mock_auto(&circuit, vec![]).verify().is_ok()
+    }
+
+    #[test]
+    fn test_allowlist_members() {
+        let primes = primes();
+        assert_eq!(primes[63], 311);
+        assert!(run::<64>(&primes));
+    }
+
+    #[test]
+    fn test_allowlist_non_members() {
+        for value in [0, 1, 4, 9, 312, 313, 1_000] {
+            assert!(!run::<64>(&[value]), "{}", value);
+        }
+        // One non-member among members is enough to fail
+        assert!(!run::<64>(&[2, 3, 100, 5]));
+    }
+
+    #[test]
+    fn test_allowlist_padded() {
+        // 48 entries are padded out to a 64 row table with repeats of 2, which doesn't let anything else in
+        let primes = primes();
+        assert!(run::<48>(&primes[..48]));
+        assert!(run::<48>(&[2, 2]));
+        for value in [0, primes[48], primes[63]] {
+            assert!(!run::<48>(&[value]), "{}", value);
+        }
+    }
+}
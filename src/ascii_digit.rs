@@ -0,0 +1,207 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+// ASCII '0'
+const ZERO_CHAR: u64 = 48;
+// The digits fit in 4 bits, and 16 - 10 = 6 shifts the last digit up to the top of the range
+const DIGIT_BITS: usize = 4;
+const DIGIT_SHIFT: u64 = 6;
+
+/// Checks that a byte is an ASCII digit '0'..='9', i.e. in `48..58`, and returns its numeric value
+/// $d = byte - 48$. The interval isn't a power of two wide, so $d$ is range-checked twice with the bits range
+/// check, as is and shifted up:
+///     $$0 \le d < 2^4, \quad 0 \le d + 6 < 2^4$$
+/// which together give $0 \le d < 10$. A byte below 48 wraps $d$ around the field, and fails the first check.
+#[derive(Debug, Clone)]
+struct AsciiDigitConfig<F: PrimeField> {
+    byte: Column<Advice>,
+    digit: Column<Advice>,
+    shifted: Column<Advice>,
+    q_digit: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> AsciiDigitConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let byte = meta.advice_column();
+        let digit = meta.advice_column();
+        let shifted = meta.advice_column();
+        let q_digit = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [byte, digit, shifted] {
+            meta.enable_equality(column);
+        }
+
+        //     byte   |    digit    |   shifted   | q_digit
+        //    -----------------------------------------------
+        //     byte   |  byte - 48  |  digit + 6  |    1
+        meta.create_gate("ascii digit", |meta| {
+            let q = meta.query_selector(q_digit);
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let digit = meta.query_advice(digit, Rotation::cur());
+            let shifted = meta.query_advice(shifted, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "digit = byte - 48",
+                        digit.clone() - (byte - Expression::Constant(F::from(ZERO_CHAR))),
+                    ),
+                    (
+                        "shifted = digit + 6",
+                        shifted - (digit + Expression::Constant(F::from(DIGIT_SHIFT))),
+                    ),
+                ],
+            )
+        });
+
+        Self {
+            byte,
+            digit,
+            shifted,
+            q_digit,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AsciiDigitChip<F: PrimeField> {
+    config: AsciiDigitConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> AsciiDigitChip<F> {
+    fn construct(config: AsciiDigitConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> AsciiDigitConfig<F> {
+        AsciiDigitConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to the numeric value of `byte`, which must be an ASCII digit.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        byte: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let (digit, shifted) = layouter.assign_region(
+            || "ascii digit",
+            |mut region| {
+                let offset = 0;
+                config.q_digit.enable(&mut region, offset)?;
+                let byte = byte.copy_advice(|| "byte", &mut region, config.byte, offset)?;
+                let digit = byte.value().map(|byte| *byte - F::from(ZERO_CHAR));
+                let digit = region.assign_advice(|| "digit", config.digit, offset, || digit)?;
+                let shifted = digit.value().map(|digit| *digit + F::from(DIGIT_SHIFT));
+                let shifted =
+                    region.assign_advice(|| "shifted", config.shifted, offset, || shifted)?;
+                Ok((digit, shifted))
+            },
+        )?;
+        config.range.assign(
+            layouter.namespace(|| "range check shifted"),
+            &shifted,
+            DIGIT_BITS,
+        )?;
+        config.range.assign(
+            layouter.namespace(|| "range check digit"),
+            &digit,
+            DIGIT_BITS,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    // Parses the private byte, and exposes its numeric value at instance row 0
+    #[derive(Default)]
+    struct AsciiDigitCircuit<F: PrimeField> {
+        byte: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for AsciiDigitCircuit<F> {
+        type Config = (AsciiDigitConfig<F>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (AsciiDigitChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let byte = layouter.assign_region(
+                || "Load byte",
+                |mut region| region.assign_advice(|| "byte", input, 0, || self.byte),
+            )?;
+            let chip = AsciiDigitChip::construct(config);
+            let digit = chip.assign(layouter.namespace(|| "parse digit"), &byte)?;
+            layouter.constrain_instance(digit.cell(), instance, 0)
+        }
+    }
+
+    fn circuit(byte: u8) -> AsciiDigitCircuit<Fp> {
+        AsciiDigitCircuit {
+            byte: Value::known(Fp::from(byte as u64)),
+        }
+    }
+
+    #[test]
+    fn test_ascii_digits() {
+        for (byte, digit) in (b'0'..=b'9').zip(0..) {
+            run_mock_auto(&circuit(byte), vec![vec![Fp::from(digit)]]).assert_satisfied();
+            assert!(
+                run_mock_auto(&circuit(byte), vec![vec![Fp::from(digit + 1)]])
+                    .verify()
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_ascii_non_digits() {
+        // Either side of the digits, '?' whose value of 15 would still fit in 4 bits, a letter, and the extremes
+        for byte in [b':', b'/', b'?', b'A', 0, 255] {
+            let digit = Fp::from(byte as u64) - Fp::from(ZERO_CHAR);
+            assert!(
+                run_mock_auto(&circuit(byte), vec![vec![digit]])
+                    .verify()
+                    .is_err(),
+                "{:?}",
+                byte as char
+            );
+        }
+    }
+}
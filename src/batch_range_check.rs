@@ -0,0 +1,343 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::table::RangeTableConfig;
+
+/// Range-checks a batch of independent values to `0..RANGE` by looking each one up in the range table.
+/// The values can be spread over `width` advice columns, so that a batch of `n` values only takes
+/// `ceil(n / width)` rows instead of `n`, which in turn lowers the `k` the circuit needs.
+#[derive(Debug, Clone)]
+pub(crate) struct BatchRangeCheckConfig<F: PrimeField, const RANGE: usize> {
+    values: Vec<Column<Advice>>,
+    q_lookups: Vec<Selector>,
+    // The index of each column's lookup in the constraint system, as VerifyFailure::Lookup reports it
+    lookup_indices: Vec<usize>,
+    table: RangeTableConfig<F, RANGE>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const RANGE: usize> BatchRangeCheckConfig<F, RANGE> {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self::configure_parallel(meta, 1)
+    }
+
+    pub(crate) fn configure_parallel(meta: &mut ConstraintSystem<F>, width: usize) -> Self {
+        assert!(width > 0, "Need at least one column");
+        let table = RangeTableConfig::configure(meta);
+
+        //     value_0  |  value_1  |  ...  |  value_{w-1}  |  q_lookup_0  |  ...  |  q_lookup_{w-1}
+        //    -----------------------------------------------------------------------------------------
+        //       v_0    |    v_1    |  ...  |    v_{w-1}    |      1       |  ...  |        1
+        //       v_w    |  v_{w+1}  |  ...  |   v_{2w-1}    |      1       |  ...  |        1
+        //
+        // Each column has its own selector and lookup into the same table, so the last row can be partially filled.
+        let mut values = Vec::with_capacity(width);
+        let mut q_lookups = Vec::with_capacity(width);
+        let mut lookup_indices = Vec::with_capacity(width);
+        for _ in 0..width {
+            let value = meta.advice_column();
+            let q_lookup = meta.complex_selector();
+            // So the checked cells can be copied out into other chips
+            meta.enable_equality(value);
+            lookup_indices.push(meta.lookup(|meta| {
+                let q = meta.query_selector(q_lookup);
+                let value = meta.query_advice(value, Rotation::cur());
+                vec![(q * value, table.value)]
+            }));
+            values.push(value);
+            q_lookups.push(q_lookup);
+        }
+
+        Self {
+            values,
+            q_lookups,
+            lookup_indices,
+            table,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Loads the range table.
+    pub(crate) fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.table.load(layouter)
+    }
+
+    /// The number of rows a batch of `num_values` takes up.
+    fn rows_for(&self, num_values: usize) -> usize {
+        num_values.div_ceil(self.values.len())
+    }
+
+    /// Maps the lookup failures of a batch back to the indices of the values that failed them, in order.
+    /// MockProver::verify doesn't stop at the first failing row, so one run of a large batch reports every
+    /// out-of-range value at once, rather than one per fix. Assumes the circuit checks a single batch, since the
+    /// failures only carry offsets within their region.
+    #[cfg(test)]
+    fn out_of_range_indices(&self, failures: &[halo2_proofs::dev::VerifyFailure]) -> Vec<usize> {
+        use halo2_proofs::dev::{FailureLocation, VerifyFailure};
+
+        let width = self.values.len();
+        let mut indices: Vec<usize> = failures
+            .iter()
+            .filter_map(|failure| match failure {
+                VerifyFailure::Lookup {
+                    lookup_index,
+                    location: FailureLocation::InRegion { offset, .. },
+                } => {
+                    let column = self.lookup_indices.iter().position(|i| i == lookup_index)?;
+                    Some(offset * width + column)
+                }
+                _ => None,
+            })
+            .collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Assigns value `i` to row `i / width` of column `i % width`, and range-checks it.
+    pub(crate) fn assign_batch_parallel(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[Value<F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut cells = Vec::with_capacity(values.len());
+        self.assign_batch_into(layouter, values, &mut cells)?;
+        Ok(cells)
+    }
+
+    /// Same as assign_batch_parallel, but appends the checked cells to `out`, so that a caller checking several
+    /// large batches can keep reusing one buffer. The floor planner may run the region more than once, so `out`
+    /// is truncated back to its original length before each run rather than collecting a fresh Vec each time.
+    pub(crate) fn assign_batch_into(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Value<F>],
+        out: &mut Vec<AssignedCell<F, F>>,
+    ) -> Result<(), Error> {
+        let width = self.values.len();
+        let start = out.len();
+        out.reserve(values.len());
+        layouter.assign_region(
+            || "Assign batch",
+            |mut region| {
+                out.truncate(start);
+                for (i, value) in values.iter().enumerate() {
+                    let (offset, column) = (i / width, i % width);
+                    self.q_lookups[column].enable(&mut region, offset)?;
+                    out.push(region.assign_advice(
+                        || format!("value {}", i),
+                        self.values[column],
+                        offset,
+                        || *value,
+                    )?);
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const RANGE: usize = 16;
+
+    #[derive(Default)]
+    struct BatchRangeCheckCircuit<F: PrimeField, const WIDTH: usize> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: PrimeField, const WIDTH: usize> Circuit<F> for BatchRangeCheckCircuit<F, WIDTH> {
+        type Config = BatchRangeCheckConfig<F, RANGE>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            BatchRangeCheckConfig::configure_parallel(meta, WIDTH)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            config.assign_batch_parallel(layouter.namespace(|| "batch"), &self.values)?;
+            Ok(())
+        }
+    }
+
+    fn circuit<const WIDTH: usize>(values: &[u64]) -> BatchRangeCheckCircuit<Fp, WIDTH> {
+        BatchRangeCheckCircuit {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_batch_parallel() {
+        let k = 6;
+        let values: Vec<u64> = (0..50).map(|i| i % RANGE as u64).collect();
+        let prover = MockProver::run(k, &circuit::<4>(&values), vec![]).unwrap();
+        prover.assert_satisfied();
+
+        // Value 22 lands on row 5 of column 2, which is checked by lookup 2
+        let mut values = values;
+        values[22] = RANGE as u64;
+        let prover = MockProver::run(k, &circuit::<4>(&values), vec![]).unwrap();
+        assert_eq!(
+            prover.verify(),
+            Err(vec![VerifyFailure::Lookup {
+                lookup_index: 2,
+                location: FailureLocation::InRegion {
+                    region: (1, "Assign batch").into(),
+                    offset: 5,
+                },
+            }])
+        );
+    }
+
+    #[test]
+    fn test_batch_all_failures() {
+        let out_of_range = [0, 3, 22, 23, 37, 49];
+        let values: Vec<u64> = (0..50)
+            .map(|i| {
+                let value = i as u64 % RANGE as u64;
+                if out_of_range.contains(&i) {
+                    value + RANGE as u64
+                } else {
+                    value
+                }
+            })
+            .collect();
+
+        // Every out-of-range value is reported, whether the batch is in one column or spread over four
+        let failures = run_mock_auto(&circuit::<1>(&values), vec![])
+            .verify()
+            .unwrap_err();
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let config = BatchRangeCheckConfig::<Fp, RANGE>::configure(&mut meta);
+        assert_eq!(
+            config.out_of_range_indices(&failures),
+            out_of_range.to_vec()
+        );
+
+        let failures = run_mock_auto(&circuit::<4>(&values), vec![])
+            .verify()
+            .unwrap_err();
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let config = BatchRangeCheckConfig::<Fp, RANGE>::configure_parallel(&mut meta, 4);
+        assert_eq!(
+            config.out_of_range_indices(&failures),
+            out_of_range.to_vec()
+        );
+    }
+
+    #[test]
+    fn test_batch_parallel_rows() {
+        // 300 values take 300 rows in a single column but only 75 across 4 columns,
+        // so the parallel layout fits in k = 7 where the single column needs k = 9
+        let values: Vec<u64> = (0..300).map(|i| i % RANGE as u64).collect();
+
+        let mut meta = ConstraintSystem::<Fp>::default();
+        assert_eq!(
+            BatchRangeCheckConfig::<Fp, RANGE>::configure(&mut meta).rows_for(300),
+            300
+        );
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let config = BatchRangeCheckConfig::<Fp, RANGE>::configure_parallel(&mut meta, 4);
+        assert_eq!(config.rows_for(300), 75);
+
+        assert!(MockProver::run(7, &circuit::<1>(&values), vec![]).is_err());
+        MockProver::run(9, &circuit::<1>(&values), vec![])
+            .unwrap()
+            .assert_satisfied();
+        MockProver::run(7, &circuit::<4>(&values), vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    // Checks two batches into one buffer, which ends up holding both in order
+    #[derive(Default)]
+    struct BatchIntoCircuit<F: PrimeField> {
+        batches: [Vec<Value<F>>; 2],
+    }
+
+    impl<F: PrimeField> Circuit<F> for BatchIntoCircuit<F> {
+        type Config = BatchRangeCheckConfig<F, RANGE>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                batches: self
+                    .batches
+                    .clone()
+                    .map(|batch| vec![Value::unknown(); batch.len()]),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            BatchRangeCheckConfig::configure_parallel(meta, 4)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let mut cells = vec![];
+            for (i, batch) in self.batches.iter().enumerate() {
+                config.assign_batch_into(
+                    layouter.namespace(|| format!("batch {}", i)),
+                    batch,
+                    &mut cells,
+                )?;
+            }
+            let num_values = self.batches.iter().map(Vec::len).sum::<usize>();
+            assert_eq!(cells.len(), num_values);
+            for (cell, value) in cells.iter().zip(self.batches.iter().flatten()) {
+                cell.value()
+                    .zip(*value)
+                    .assert_if_known(|(cell, value)| *cell == value);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_batch_into() {
+        let batch = |values: &[u64]| values.iter().map(|v| Value::known(Fp::from(*v))).collect();
+        let circuit = BatchIntoCircuit {
+            batches: [batch(&[1, 2, 3, 4, 5]), batch(&[15, 14, 13])],
+        };
+        MockProver::run(6, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+
+        let circuit = BatchIntoCircuit {
+            batches: [batch(&[1, 2, 3, 4, 5]), batch(&[15, RANGE as u64, 13])],
+        };
+        assert!(MockProver::run(6, &circuit, vec![])
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+}
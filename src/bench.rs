@@ -0,0 +1,210 @@
+use ff::Field;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::{EqAffine, Fp},
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+    poly::commitment::Params,
+};
+
+use crate::{
+    batch_range_check::BatchRangeCheckConfig,
+    range_check::{keygen_range_check, range_check_degree, range_check_keygen_k},
+};
+
+/// The smallest `k` keygen accepts for the brute-force range check to 0..RANGE, and the degree of its
+/// constraint system. `cargo bench --bench keygen` reports these next to the keygen times.
+pub fn brute_force_shape<const RANGE: usize>() -> (u32, usize) {
+    (
+        range_check_keygen_k::<RANGE>(),
+        range_check_degree::<RANGE>(),
+    )
+}
+
+/// Runs `keygen_vk` and `keygen_pk` for the brute-force range check to 0..RANGE.
+pub fn keygen_brute_force<const RANGE: usize>(params: &Params<EqAffine>) -> Result<(), Error> {
+    keygen_range_check::<RANGE>(params).map(|_| ())
+}
+
+// The batches are checked to 0..16, four values to a row
+const BATCH_RANGE: usize = 16;
+const BATCH_WIDTH: usize = 4;
+
+struct BatchesCircuit {
+    batches: Vec<Vec<Value<Fp>>>,
+    reuse_buffer: bool,
+}
+
+impl Circuit<Fp> for BatchesCircuit {
+    type Config = BatchRangeCheckConfig<Fp, BATCH_RANGE>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            batches: self
+                .batches
+                .iter()
+                .map(|batch| vec![Value::unknown(); batch.len()])
+                .collect(),
+            reuse_buffer: self.reuse_buffer,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        BatchRangeCheckConfig::configure_parallel(meta, BATCH_WIDTH)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        config.load_table(&mut layouter)?;
+        let mut cells = vec![];
+        for (i, batch) in self.batches.iter().enumerate() {
+            let layouter = layouter.namespace(|| format!("batch {}", i));
+            if self.reuse_buffer {
+                config.assign_batch_into(layouter, batch, &mut cells)?;
+            } else {
+                cells.extend(config.assign_batch_parallel(layouter, batch)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Range checks of several batches of values, whose checked cells all end up in one Vec, for
+/// synthesize_batches.
+pub struct Batches(BatchesCircuit);
+
+impl Batches {
+    /// With `reuse_buffer`, each batch is appended to the Vec by assign_batch_into. Otherwise each batch is
+    /// collected into its own Vec by assign_batch_parallel, and then moved over.
+    pub fn new(batches: &[Vec<u64>], reuse_buffer: bool) -> Self {
+        Self(BatchesCircuit {
+            batches: batches
+                .iter()
+                .map(|batch| batch.iter().map(|v| Value::known(Fp::from(*v))).collect())
+                .collect(),
+            reuse_buffer,
+        })
+    }
+}
+
+/// Synthesizes `batches` with SimpleFloorPlanner, throwing the assigned cells away, so that what's measured is
+/// the batch path rather than a prover storing the cells. `cargo bench --bench batch` compares the two ways of
+/// collecting the cells.
+pub fn synthesize_batches(batches: &Batches) -> Result<(), Error> {
+    let mut meta = ConstraintSystem::default();
+    let config = BatchesCircuit::configure(&mut meta);
+    SimpleFloorPlanner::synthesize(&mut Discard, &batches.0, config, meta.constants().clone())
+}
+
+// An Assignment that computes each cell's value and then drops it
+struct Discard;
+
+impl<F: Field> Assignment<F> for Discard {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        _: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        to();
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        _: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        to();
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brute_force_shape() {
+        // The product gate over 0..RANGE grows a degree with each value, but the rows it needs don't grow
+        let (k_8, degree_8) = brute_force_shape::<8>();
+        let (k_16, degree_16) = brute_force_shape::<16>();
+        assert_eq!(degree_16 - degree_8, 8);
+        assert_eq!(k_8, k_16);
+        let params: Params<EqAffine> = Params::new(k_8);
+        assert!(keygen_brute_force::<8>(&params).is_ok());
+    }
+
+    #[test]
+    fn test_synthesize_batches() {
+        // Out-of-range values still synthesize, since nothing here checks the lookups
+        let batches = [vec![1, 2, 3, 4, 5], vec![15, 16, 13]];
+        for reuse_buffer in [false, true] {
+            assert!(synthesize_batches(&Batches::new(&batches, reuse_buffer)).is_ok());
+        }
+    }
+}
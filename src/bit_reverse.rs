@@ -0,0 +1,171 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{ConstraintSystem, Error},
+};
+use std::marker::PhantomData;
+
+use crate::decompose_range_check::{plan_decomposition, DecomposeRangeCheckConfig};
+
+/// Reverses the order of the bits of an N_BITS-bit value, as FFT index permutations do. The decompose chip splits
+/// the value into 1-bit windows $b_i$, which also range-checks it, and repack then copies the windows back in
+/// reverse order into
+///     $$out = \sum_i b_{N-1-i} \cdot 2^i$$
+/// so the output is tied to the input's bits by copy constraints alone, with no gate of its own.
+#[derive(Debug, Clone)]
+struct BitReverseConfig<F: PrimeField, const N_BITS: usize> {
+    decompose: DecomposeRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> BitReverseConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            decompose: DecomposeRangeCheckConfig::configure_with_plan(
+                meta,
+                plan_decomposition(N_BITS, 1),
+            ),
+        }
+    }
+
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.decompose.load_table(layouter)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BitReverseChip<F: PrimeField, const N_BITS: usize> {
+    config: BitReverseConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> BitReverseChip<F, N_BITS> {
+    fn construct(config: BitReverseConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> BitReverseConfig<F, N_BITS> {
+        BitReverseConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to the bit-reversal of `value`, which must fit in N_BITS bits.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let decompose = &self.config.decompose;
+        let decomposed = decompose.assign_cell(layouter.namespace(|| "bits"), None, value)?;
+        // Repack weights the i-th window it's given by 2^i, so handing it the bits top first reverses them
+        let mut reversed = decomposed.windows;
+        reversed.reverse();
+        decompose.repack(layouter.namespace(|| "reverse bits"), &reversed, 0, N_BITS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const N_BITS: usize = 8;
+
+    // Reverses the bits of the private value, exposing the result at instance row 0
+    #[derive(Default)]
+    struct BitReverseCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for BitReverseCircuit<F> {
+        type Config = (
+            BitReverseConfig<F, N_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (BitReverseChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let reversed = BitReverseChip::construct(config)
+                .assign(layouter.namespace(|| "bit reverse"), &value)?;
+            layouter.constrain_instance(reversed.cell(), instance, 0)
+        }
+    }
+
+    fn run(value: u64, reversed: u64) -> bool {
+        let circuit = BitReverseCircuit {
+            value: Value::known(Fp::from(value)),
+        };
+        run_mock_auto(&circuit, vec![vec![Fp::from(reversed)]])
+            .verify()
+            .is_ok()
+    }
+
+    // The host reference
+    fn reverse_bits(value: u64) -> u64 {
+        (value as u8).reverse_bits() as u64
+    }
+
+    #[test]
+    fn test_bit_reverse() {
+        for value in [
+            0,
+            1,
+            0b1000_0000,
+            0b1101_0010,
+            0b1010_1010,
+            0b1111_1111,
+            0x0f,
+            37,
+        ] {
+            let reversed = reverse_bits(value);
+            assert!(run(value, reversed), "{:#010b}", value);
+            // Reversing twice gives the value back
+            assert!(run(reversed, value), "{:#010b}", reversed);
+        }
+        assert_eq!(reverse_bits(0b1101_0010), 0b0100_1011);
+    }
+
+    #[test]
+    fn test_bit_reverse_wrong_output() {
+        for value in [0b1101_0010, 1, 0] {
+            let reversed = reverse_bits(value);
+            // The value itself, the reversal off by one, and the reversal of the wrong width
+            for wrong in [value, reversed ^ 1, reversed >> 1] {
+                if wrong != reversed {
+                    assert!(!run(value, wrong), "{:#010b} to {:#010b}", value, wrong);
+                }
+            }
+        }
+        // 9 bits don't fit, even claiming the reversal of the low 8
+        assert!(!run(0x1_01, reverse_bits(0x01)));
+    }
+}
@@ -0,0 +1,196 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// Range-checks a cell to $n$ bits, where $n$ is chosen at assignment time rather than as a const.
+/// The value $\alpha$ is decomposed into bits with a running sum $z$ held in a single column:
+///     $$z_0 = \alpha, \quad z_{i+1} = (z_i - b_i) / 2, \quad z_n = 0$$
+/// where each bit $b_i = z_i - 2 z_{i+1}$ is constrained to be boolean.
+///
+/// This costs $n + 1$ rows per check but needs no lookup table, so it is a convenient
+/// building block for gadgets which need to bound an intermediate value to a runtime width.
+#[derive(Debug, Clone)]
+pub(crate) struct BitsRangeCheckConfig<F: PrimeField> {
+    pub(crate) z: Column<Advice>,
+    q_bit: Selector,
+    q_zero: Selector,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> BitsRangeCheckConfig<F> {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let z = meta.advice_column();
+        let q_bit = meta.selector();
+        let q_zero = meta.selector();
+        // Needed to copy the checked cell into z_0
+        meta.enable_equality(z);
+
+        //        z       |    q_bit    |   q_zero
+        //       ------------------------------------
+        //       z_0      |      1      |     0
+        //       z_1      |      1      |     0
+        //       ...      |     ...     |    ...
+        //       z_n      |      0      |     1
+        meta.create_gate("bit", |meta| {
+            let q = meta.query_selector(q_bit);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let bit = z_cur - z_next * Expression::Constant(F::from(2));
+            Constraints::with_selector(
+                q,
+                [(
+                    "bit is boolean",
+                    bit.clone() * (Expression::Constant(F::ONE) - bit),
+                )],
+            )
+        });
+
+        meta.create_gate("z_n = 0", |meta| {
+            let q = meta.query_selector(q_zero);
+            let z = meta.query_advice(z, Rotation::cur());
+            Constraints::with_selector(q, [("z_n = 0", z)])
+        });
+
+        Self {
+            z,
+            q_bit,
+            q_zero,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Copies `value` into a new region and constrains it to `0..2^num_bits`.
+    /// Returns the copied cell, so callers can keep chaining from the range-checked copy.
+    pub(crate) fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || format!("range check {} bits", num_bits),
+            |mut region| self.assign_in_region(&mut region, 0, value, num_bits),
+        )
+    }
+
+    /// The number of rows a check of `num_bits` bits takes up.
+    pub(crate) fn rows_used(num_bits: usize) -> usize {
+        num_bits + 1
+    }
+
+    /// Like assign, but lays the check out in an existing region from `offset`, taking `rows_used` rows.
+    pub(crate) fn assign_in_region(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let z_0 = value.copy_advice(|| "z_0", region, self.z, offset)?;
+
+        // Shift one bit out of the running sum per row. An out-of-range value never reaches zero,
+        // so the honest witness still fails the z_n = 0 gate.
+        let mut z = z_0.value().copied();
+        for i in 0..num_bits {
+            self.q_bit.enable(region, offset + i)?;
+            z = z.map(|z| (z - F::from(bool::from(z.is_odd()) as u64)) * F::TWO_INV);
+            region.assign_advice(|| format!("z_{}", i + 1), self.z, offset + i + 1, || z)?;
+        }
+        self.q_zero.enable(region, offset + num_bits)?;
+
+        Ok(z_0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+    use crate::testing::assert_selector_rows;
+
+    #[derive(Default)]
+    struct BitsRangeCheckCircuit<F: PrimeField> {
+        value: Value<F>,
+        num_bits: usize,
+    }
+
+    impl<F: PrimeField> Circuit<F> for BitsRangeCheckCircuit<F> {
+        type Config = BitsRangeCheckConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: Value::unknown(),
+                num_bits: self.num_bits,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            BitsRangeCheckConfig::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", config.z, 0, || self.value),
+            )?;
+            config.assign(layouter.namespace(|| "range check"), &value, self.num_bits)?;
+            Ok(())
+        }
+    }
+
+    fn circuit(value: Fp, num_bits: usize) -> BitsRangeCheckCircuit<Fp> {
+        BitsRangeCheckCircuit {
+            value: Value::known(value),
+            num_bits,
+        }
+    }
+
+    #[test]
+    fn test_bits_range_check_pass() {
+        let k = 8;
+        for (value, num_bits) in [(0, 0), (0, 4), (15, 4), (1, 1), (200, 8), (u64::MAX, 64)] {
+            let prover = MockProver::run(k, &circuit(Fp::from(value), num_bits), vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_bits_range_check_fail() {
+        let k = 8;
+        for (value, num_bits) in [(1, 0), (16, 4), (256, 8)] {
+            let prover = MockProver::run(k, &circuit(Fp::from(value), num_bits), vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+        // -1 wraps around the field and is far out of range
+        let prover = MockProver::run(k, &circuit(-Fp::ONE, 8), vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_bits_range_check_rows_used() {
+        // The check starts below the loaded value on row 0, and its last row is the z_n = 0 row
+        for num_bits in [0, 4, 8] {
+            let circuit = circuit(Fp::from(1), num_bits);
+            let rows = BitsRangeCheckConfig::<Fp>::rows_used(num_bits);
+            assert_eq!(rows, num_bits + 1);
+            assert_selector_rows(&circuit, &[], |config| config.q_bit, 1..rows);
+            assert_selector_rows(&circuit, &[], |config| config.q_zero, [rows]);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::decompose_range_check::field_to_u128;
+
+/// Computes `out = a & b` for `a` and `b` in `0..2^N_BITS`.
+/// All three values are decomposed into single bits with running sums side by side, as in the bits range check:
+///     $$z_0 = \alpha, \quad z_{i+1} = (z_i - b_i) / 2, \quad z_N = 0$$
+/// and on each row the output bit is constrained to be the product of the input bits,
+///     $$out_i = a_i \cdot b_i$$
+/// Since every running sum must reach zero after N_BITS bits, the inputs and output are all range-checked too.
+#[derive(Debug, Clone)]
+struct BitwiseAndConfig<F: PrimeField, const N_BITS: usize> {
+    z_a: Column<Advice>,
+    z_b: Column<Advice>,
+    z_out: Column<Advice>,
+    q_bit: Selector,
+    q_zero: Selector,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> BitwiseAndConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let z_a = meta.advice_column();
+        let z_b = meta.advice_column();
+        let z_out = meta.advice_column();
+        let q_bit = meta.selector();
+        let q_zero = meta.selector();
+        for column in [z_a, z_b, z_out] {
+            meta.enable_equality(column);
+        }
+
+        //       z_a      |      z_b      |     z_out     |  q_bit  |  q_zero
+        //     ---------------------------------------------------------------
+        //      a = z_0   |    b = z_0    |   out = z_0   |    1    |    0
+        //       z_1      |      z_1      |      z_1      |    1    |    0
+        //       ...      |      ...      |      ...      |   ...   |   ...
+        //       z_N      |      z_N      |      z_N      |    0    |    1
+        meta.create_gate("bitwise and", |meta| {
+            let q = meta.query_selector(q_bit);
+            let one = Expression::Constant(F::ONE);
+            let bit = |meta: &mut halo2_proofs::plonk::VirtualCells<F>, z| {
+                let z_cur = meta.query_advice(z, Rotation::cur());
+                let z_next = meta.query_advice(z, Rotation::next());
+                z_cur - z_next * Expression::Constant(F::from(2))
+            };
+            let a = bit(meta, z_a);
+            let b = bit(meta, z_b);
+            let out = bit(meta, z_out);
+            Constraints::with_selector(
+                q,
+                [
+                    ("a_i is boolean", a.clone() * (one.clone() - a.clone())),
+                    ("b_i is boolean", b.clone() * (one - b.clone())),
+                    ("out_i = a_i * b_i", out - a * b),
+                ],
+            )
+        });
+
+        meta.create_gate("z_N = 0", |meta| {
+            let q = meta.query_selector(q_zero);
+            Constraints::with_selector(
+                q,
+                [z_a, z_b, z_out].map(|z| ("z_N = 0", meta.query_advice(z, Rotation::cur()))),
+            )
+        });
+
+        Self {
+            z_a,
+            z_b,
+            z_out,
+            q_bit,
+            q_zero,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BitwiseAndChip<F: PrimeField, const N_BITS: usize> {
+    config: BitwiseAndConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> BitwiseAndChip<F, N_BITS> {
+    fn construct(config: BitwiseAndConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> BitwiseAndConfig<F, N_BITS> {
+        BitwiseAndConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to `a & b`. The host side reads the low 128 bits, so N_BITS must be at most 128.
+    fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let out = a
+            .value()
+            .zip(b.value())
+            .map(|(a, b)| F::from_u128(field_to_u128(*a) & field_to_u128(*b)));
+        self.assign_out(layouter, a, b, out)
+    }
+
+    fn assign_out(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        out: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "bitwise and",
+            |mut region| {
+                let a = a.copy_advice(|| "a", &mut region, config.z_a, 0)?;
+                let b = b.copy_advice(|| "b", &mut region, config.z_b, 0)?;
+                let out = region.assign_advice(|| "out", config.z_out, 0, || out)?;
+
+                // Shift one bit out of each running sum per row
+                let shift = |z: F| (z - F::from(bool::from(z.is_odd()) as u64)) * F::TWO_INV;
+                let mut z = [a.value().copied(), b.value().copied(), out.value().copied()];
+                for i in 0..N_BITS {
+                    config.q_bit.enable(&mut region, i)?;
+                    for (z, column) in z.iter_mut().zip([config.z_a, config.z_b, config.z_out]) {
+                        *z = z.map(shift);
+                        region.assign_advice(|| format!("z_{}", i + 1), column, i + 1, || *z)?;
+                    }
+                }
+                config.q_zero.enable(&mut region, N_BITS)?;
+
+                Ok(out)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::*;
+    use crate::testing::{run_mock_auto, ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 4;
+
+    // Loads `a` and `b`, then ANDs them. The witness for `out` can be forged.
+    #[derive(Default)]
+    struct BitwiseAndCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for BitwiseAndCircuit<F> {
+        type Config = (BitwiseAndConfig<F, N_BITS>, Column<Advice>);
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (BitwiseAndChip::configure(meta), input)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input): Self::Config,
+            mut layouter: impl Layouter<F>,
+            out: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "Load a and b",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            let chip = BitwiseAndChip::construct(config);
+            let layouter = layouter.namespace(|| "a & b");
+            match out {
+                Some(out) => chip.assign_out(layouter, &a, &b, *out),
+                None => chip.and(layouter, &a, &b),
+            }?;
+            Ok(())
+        }
+    }
+
+    fn circuit(a: u64, b: u64) -> Forged<Fp, BitwiseAndCircuit<Fp>> {
+        Forged::honest(BitwiseAndCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        })
+    }
+
+    #[test]
+    fn test_bitwise_and() {
+        for (a, b) in [
+            (0b1100, 0b1010),
+            (0, 0b1111),
+            (0b1111, 0b1111),
+            (0b0101, 0b1010),
+        ] {
+            run_mock_auto(&circuit(a, b), vec![]).assert_satisfied();
+        }
+
+        // 0b1100 & 0b1010 is 0b1000, not 0b1100 or 0b1110
+        for out in [0b1100, 0b1110] {
+            let malicious = circuit(0b1100, 0b1010).forge(Value::known(Fp::from(out)));
+            assert!(run_mock_auto(&malicious, vec![]).verify().is_err());
+        }
+        let honest = circuit(0b1100, 0b1010).forge(Value::known(Fp::from(0b1000)));
+        run_mock_auto(&honest, vec![]).assert_satisfied();
+    }
+
+    #[test]
+    fn test_bitwise_and_out_of_range() {
+        // 0b10000 doesn't fit in 4 bits, even though its AND with 0b1010 does
+        for (a, b) in [(0b10000, 0b1010), (0b1010, 0b10000)] {
+            assert!(run_mock_auto(&circuit(a, b), vec![]).verify().is_err());
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// The canonical booleanity constraint $b \cdot (1 - b)$, which is zero exactly when $b$ is 0 or 1.
+pub(crate) fn bool_check<F: PrimeField>(value: Expression<F>) -> Expression<F> {
+    value.clone() * (Expression::Constant(F::ONE) - value)
+}
+
+/// Constrains a cell to be 0 or 1. This is the range check to `0..2`, but as the single degree-2 gate
+///     $$b \cdot (1 - b) = 0$$
+/// with no table or decomposition, so it's the primitive for the flags that selects and other gadgets switch on.
+#[derive(Debug, Clone)]
+pub(crate) struct BooleanConfig<F: PrimeField> {
+    value: Column<Advice>,
+    q_bool: Selector,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> BooleanConfig<F> {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let q_bool = meta.selector();
+        meta.enable_equality(value);
+
+        //     value   | q_bool
+        //    ------------------
+        //       b     |   1
+        meta.create_gate("boolean", |meta| {
+            let q = meta.query_selector(q_bool);
+            let value = meta.query_advice(value, Rotation::cur());
+            Constraints::with_selector(q, [("b is boolean", bool_check(value))])
+        });
+
+        Self {
+            value,
+            q_bool,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BooleanChip<F: PrimeField> {
+    config: BooleanConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> BooleanChip<F> {
+    pub(crate) fn construct(config: BooleanConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> BooleanConfig<F> {
+        BooleanConfig::configure(meta)
+    }
+
+    /// Witnesses `value` in a cell constrained to be 0 or 1.
+    pub(crate) fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "boolean",
+            |mut region| {
+                config.q_bool.enable(&mut region, 0)?;
+                region.assign_advice(|| "b", config.value, 0, || value)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{FailureLocation, VerifyFailure},
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    // Witnesses the private value as a boolean, exposing it at instance row 0
+    #[derive(Default)]
+    struct BooleanCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for BooleanCircuit<F> {
+        type Config = (BooleanConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (BooleanChip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let b = BooleanChip::construct(config)
+                .assign(layouter.namespace(|| "boolean"), self.value)?;
+            layouter.constrain_instance(b.cell(), instance, 0)
+        }
+    }
+
+    fn run(value: Fp) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = BooleanCircuit {
+            value: Value::known(value),
+        };
+        run_mock_auto(&circuit, vec![vec![value]]).verify()
+    }
+
+    #[test]
+    fn test_boolean() {
+        for value in [0, 1] {
+            assert!(run(Fp::from(value)).is_ok(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_not_boolean() {
+        // The public input matches the cell, so only the booleanity constraint fails
+        for value in [Fp::from(2), Fp::from(3), -Fp::from(1)] {
+            match &run(value).unwrap_err()[..] {
+                [VerifyFailure::ConstraintNotSatisfied {
+                    constraint,
+                    location,
+                    ..
+                }] => {
+                    assert_eq!(
+                        *constraint,
+                        ((0, "boolean").into(), 0, "b is boolean").into()
+                    );
+                    assert_eq!(
+                        *location,
+                        FailureLocation::InRegion {
+                            region: (0, "boolean").into(),
+                            offset: 0,
+                        }
+                    );
+                }
+                failures => panic!("Unexpected failures {:?}", failures),
+            }
+        }
+    }
+}
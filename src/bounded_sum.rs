@@ -0,0 +1,184 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Constraints, Error, Instance, Selector},
+    poly::Rotation,
+};
+
+use crate::batch_range_check::BatchRangeCheckConfig;
+
+/// Sums cells with a running sum, returning the total.
+#[derive(Debug, Clone)]
+struct SumConfig {
+    value: Column<Advice>,
+    sum: Column<Advice>,
+    q_sum: Selector,
+    q_sum_start: Selector,
+}
+
+impl SumConfig {
+    fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let sum = meta.advice_column();
+        let q_sum = meta.selector();
+        let q_sum_start = meta.selector();
+        meta.enable_equality(value);
+        meta.enable_equality(sum);
+
+        //     value   |    sum    |  q_sum  | q_sum_start
+        //    -----------------------------------------------
+        //      v_0    |     0     |    1    |      1
+        //      v_1    |    v_0    |    1    |      0
+        //      ...    |    ...    |   ...   |     ...
+        //             | v_0 + ... |    0    |      0
+        meta.create_gate("sum start", |meta| {
+            let q = meta.query_selector(q_sum_start);
+            let sum = meta.query_advice(sum, Rotation::cur());
+            Constraints::with_selector(q, [("sum_0 = 0", sum)])
+        });
+
+        meta.create_gate("running sum", |meta| {
+            let q = meta.query_selector(q_sum);
+            let value = meta.query_advice(value, Rotation::cur());
+            let sum_cur = meta.query_advice(sum, Rotation::cur());
+            let sum_next = meta.query_advice(sum, Rotation::next());
+            Constraints::with_selector(
+                q,
+                [("sum_next = sum_cur + value", sum_next - (sum_cur + value))],
+            )
+        });
+
+        Self {
+            value,
+            sum,
+            q_sum,
+            q_sum_start,
+        }
+    }
+
+    fn assign<F: PrimeField>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "Sum",
+            |mut region| {
+                self.q_sum_start.enable(&mut region, 0)?;
+                let mut sum =
+                    region.assign_advice(|| "sum_0", self.sum, 0, || Value::known(F::ZERO))?;
+                for (offset, cell) in cells.iter().enumerate() {
+                    self.q_sum.enable(&mut region, offset)?;
+                    let value = cell.copy_advice(|| "value", &mut region, self.value, offset)?;
+                    let next = sum.value().copied() + value.value();
+                    sum = region.assign_advice(
+                        || format!("sum_{}", offset + 1),
+                        self.sum,
+                        offset + 1,
+                        || next,
+                    )?;
+                }
+                Ok(sum)
+            },
+        )
+    }
+}
+
+/// A complete statement built from the smaller chips: the prover knows private values, each in `0..RANGE`
+/// (so `B`-bit values for `RANGE = 2^B`), whose sum is the public total at instance row 0.
+/// The values are range-checked as one batch, then copied into a running sum whose last row is exposed.
+#[derive(Debug, Clone)]
+struct BoundedSumConfig<F: PrimeField, const RANGE: usize> {
+    batch: BatchRangeCheckConfig<F, RANGE>,
+    sum: SumConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Default)]
+struct BoundedSumCircuit<F: PrimeField, const RANGE: usize> {
+    values: Vec<Value<F>>,
+}
+
+impl<F: PrimeField, const RANGE: usize> Circuit<F> for BoundedSumCircuit<F, RANGE> {
+    type Config = BoundedSumConfig<F, RANGE>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        BoundedSumConfig {
+            batch: BatchRangeCheckConfig::configure(meta),
+            sum: SumConfig::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.batch.load_table(&mut layouter)?;
+        let cells = config
+            .batch
+            .assign_batch_parallel(layouter.namespace(|| "range check values"), &self.values)?;
+        let total = config
+            .sum
+            .assign(layouter.namespace(|| "sum values"), &cells)?;
+        layouter.constrain_instance(total.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    // 4-bit values
+    const RANGE: usize = 16;
+
+    fn run(values: &[u64], total: u64) -> bool {
+        let circuit = BoundedSumCircuit::<Fp, RANGE> {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        };
+        run_mock_auto(&circuit, vec![vec![Fp::from(total)]])
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_bounded_sum() {
+        let values = [3, 15, 0, 7, 9, 15, 1];
+        assert!(run(&values, values.iter().sum()));
+        assert!(run(&[], 0));
+    }
+
+    #[test]
+    fn test_bounded_sum_out_of_range() {
+        // 16 is out of range, even with the total it's honestly summed to
+        assert!(!run(&[3, 16, 0], 19));
+        // Splitting 20 as 21 + (-1) keeps the sum but puts both terms out of range
+        let circuit = BoundedSumCircuit::<Fp, RANGE> {
+            values: vec![Value::known(Fp::from(21)), Value::known(-Fp::from(1))],
+        };
+        assert!(run_mock_auto(&circuit, vec![vec![Fp::from(20)]])
+            .verify()
+            .is_err());
+    }
+
+    #[test]
+    fn test_bounded_sum_wrong_total() {
+        let values = [3, 15, 0, 7];
+        assert!(!run(&values, 24));
+        assert!(!run(&values, 26));
+        assert!(!run(&values, 0));
+    }
+}
@@ -0,0 +1,215 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    decompose_range_check::{DecomposedValue, RangeConstrained},
+    less_than::pow2,
+    table::RangeTableConfig,
+};
+
+/// Range-checks a value given as NUM_BYTES little-endian bytes, for hashing and serialization inputs that
+/// don't fit in a u128. Each byte is looked up in a 256-entry table, and a single gate reconstructs
+///     $$value = \sum_i b_i \cdot 2^{8i}$$
+/// Like the decompose chip, the gate queries one rotation per byte.
+///
+/// The reconstruction is modulo the field. Up to `F::CAPACITY / 8` bytes (31 for the Pasta fields) it is
+/// exact, but with more, distinct byte strings can reconstruct to the same cell.
+#[derive(Debug, Clone)]
+struct ByteDecomposeConfig<F: PrimeField, const NUM_BYTES: usize> {
+    value: Column<Advice>,
+    byte: Column<Advice>,
+    q_bytes: Selector,
+    q_byte: Selector,
+    table: RangeTableConfig<F, 256>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const NUM_BYTES: usize> ByteDecomposeConfig<F, NUM_BYTES> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        assert!(NUM_BYTES > 0, "Empty value!");
+        let value = meta.advice_column();
+        let byte = meta.advice_column();
+        let q_bytes = meta.selector();
+        let q_byte = meta.complex_selector();
+        let table = RangeTableConfig::configure(meta);
+        meta.enable_equality(value);
+        meta.enable_equality(byte);
+
+        //        value     |    byte    |   q_bytes   |   q_byte   | range_check_table
+        //       ---------------------------------------------------------------------------
+        //          v       |    b_0     |      1      |     1      |        0
+        //          -       |    b_1     |      0      |     1      |        1
+        //          -       |    ...     |     ...     |    ...     |       ...
+        //          -       |  b_{n-1}   |      0      |     1      |       255
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_byte);
+            let byte = meta.query_advice(byte, Rotation::cur());
+            vec![(q * byte, table.value)]
+        });
+
+        meta.create_gate("bytes", |meta| {
+            let q = meta.query_selector(q_bytes);
+            let value = meta.query_advice(value, Rotation::cur());
+            let sum = (0..NUM_BYTES).fold(Expression::Constant(F::ZERO), |sum, i| {
+                sum + meta.query_advice(byte, Rotation(i as i32))
+                    * Expression::Constant(pow2::<F>(8 * i))
+            });
+            Constraints::with_selector(q, [("value = sum of bytes", sum - value)])
+        });
+
+        Self {
+            value,
+            byte,
+            q_bytes,
+            q_byte,
+            table,
+            _marker: PhantomData,
+        }
+    }
+
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.table.load(layouter)
+    }
+
+    /// Range-checks each of `bytes`, and returns them along with a cell holding the little-endian integer
+    /// they make up. Returns `Error::Synthesis` unless there are exactly NUM_BYTES bytes.
+    fn assign_from_bytes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bytes: &[u8],
+    ) -> Result<DecomposedValue<F>, Error> {
+        if bytes.len() != NUM_BYTES {
+            return Err(Error::Synthesis);
+        }
+        let value = bytes.iter().enumerate().fold(F::ZERO, |sum, (i, byte)| {
+            sum + F::from(*byte as u64) * pow2::<F>(8 * i)
+        });
+
+        layouter.assign_region(
+            || "Assign bytes",
+            |mut region| {
+                let offset = 0;
+                self.q_bytes.enable(&mut region, offset)?;
+                let value =
+                    region.assign_advice(|| "value", self.value, offset, || Value::known(value))?;
+                let windows = bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, byte)| {
+                        self.q_byte.enable(&mut region, offset + i)?;
+                        region
+                            .assign_advice(
+                                || format!("byte {}", i),
+                                self.byte,
+                                offset + i,
+                                || Value::known(F::from(*byte as u64)),
+                            )
+                            .map(RangeConstrained)
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(DecomposedValue { value, windows })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+    use ff::Field;
+
+    // Exposes the reconstructed value, followed by each byte, as public inputs
+    #[derive(Default)]
+    struct BytesCircuit<F: PrimeField, const NUM_BYTES: usize> {
+        bytes: Vec<u8>,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField, const NUM_BYTES: usize> Circuit<F> for BytesCircuit<F, NUM_BYTES> {
+        type Config = (ByteDecomposeConfig<F, NUM_BYTES>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                bytes: vec![0; NUM_BYTES],
+                _marker: PhantomData,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (ByteDecomposeConfig::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            let decomposed =
+                config.assign_from_bytes(layouter.namespace(|| "bytes"), &self.bytes)?;
+            layouter.constrain_instance(decomposed.value.cell(), instance, 0)?;
+            for (i, byte) in decomposed.windows.iter().enumerate() {
+                layouter.constrain_instance(byte.0.cell(), instance, i + 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn public_inputs(value: Fp, bytes: &[u8]) -> Vec<Vec<Fp>> {
+        let bytes = bytes.iter().map(|byte| Fp::from(*byte as u64));
+        vec![std::iter::once(value).chain(bytes).collect()]
+    }
+
+    #[test]
+    fn test_four_bytes() {
+        let bytes = 0xdeadbeefu32.to_le_bytes();
+        let circuit = BytesCircuit::<Fp, 4> {
+            bytes: bytes.to_vec(),
+            _marker: PhantomData,
+        };
+        run_mock_auto(&circuit, public_inputs(Fp::from(0xdeadbeef), &bytes)).assert_satisfied();
+
+        // Big-endian isn't the same value
+        let prover = run_mock_auto(&circuit, public_inputs(Fp::from(0xefbeadde), &bytes));
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_thirty_two_bytes() {
+        // The repr of a field element is its little-endian integer, so the bytes of -1 reconstruct to -1
+        let value = -Fp::ONE;
+        let bytes = value.to_repr();
+        let circuit = BytesCircuit::<Fp, 32> {
+            bytes: bytes.to_vec(),
+            _marker: PhantomData,
+        };
+        run_mock_auto(&circuit, public_inputs(value, &bytes)).assert_satisfied();
+
+        let prover = run_mock_auto(&circuit, public_inputs(value - Fp::ONE, &bytes));
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_wrong_number_of_bytes() {
+        let circuit = BytesCircuit::<Fp, 4> {
+            bytes: vec![1, 2, 3],
+            _marker: PhantomData,
+        };
+        assert!(halo2_proofs::dev::MockProver::run(10, &circuit, vec![vec![]]).is_err());
+    }
+}
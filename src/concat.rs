@@ -0,0 +1,212 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{bits_range_check::BitsRangeCheckConfig, less_than::pow2};
+
+/// Concatenates two range-checked values, `low` of `w_1` bits and `high` of `w_2` bits, into
+///     $$out = low + high \cdot 2^{w_1}$$
+/// and range-checks `out` to $w_1 + w_2$ bits. The widths are chosen at assignment time,
+/// so $2^{w_1}$ is held in a fixed column rather than baked into the gate.
+///
+/// The inputs themselves are not range-checked here: the caller must already know they fit their widths,
+/// otherwise e.g. a 4-bit `low` would overlap the bottom bit of `high`.
+#[derive(Debug, Clone)]
+struct ConcatConfig<F: PrimeField> {
+    low: Column<Advice>,
+    high: Column<Advice>,
+    out: Column<Advice>,
+    shift: Column<Fixed>,
+    q_concat: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> ConcatConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let low = meta.advice_column();
+        let high = meta.advice_column();
+        let out = meta.advice_column();
+        let shift = meta.fixed_column();
+        let q_concat = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [low, high, out] {
+            meta.enable_equality(column);
+        }
+
+        //     low   |  high  |   out   |  shift  | q_concat
+        //    -------------------------------------------------
+        //     low   |  high  |   out   |  2^w_1  |    1
+        meta.create_gate("concat", |meta| {
+            let q = meta.query_selector(q_concat);
+            let low = meta.query_advice(low, Rotation::cur());
+            let high = meta.query_advice(high, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [("out = low + high * 2^w_1", out - (low + high * shift))],
+            )
+        });
+
+        Self {
+            low,
+            high,
+            out,
+            shift,
+            q_concat,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ConcatChip<F: PrimeField> {
+    config: ConcatConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ConcatChip<F> {
+    fn construct(config: ConcatConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ConcatConfig<F> {
+        ConcatConfig::configure(meta)
+    }
+
+    /// Returns `low + high * 2^low_bits`, range-checked to `low_bits + high_bits` bits.
+    fn concat(
+        &self,
+        layouter: impl Layouter<F>,
+        low: &AssignedCell<F, F>,
+        high: &AssignedCell<F, F>,
+        low_bits: usize,
+        high_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let out = low
+            .value()
+            .zip(high.value())
+            .map(|(low, high)| *low + *high * pow2::<F>(low_bits));
+        self.assign_out(layouter, low, high, low_bits, high_bits, out)
+    }
+
+    fn assign_out(
+        &self,
+        mut layouter: impl Layouter<F>,
+        low: &AssignedCell<F, F>,
+        high: &AssignedCell<F, F>,
+        low_bits: usize,
+        high_bits: usize,
+        out: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let out = layouter.assign_region(
+            || "concat",
+            |mut region| {
+                let offset = 0;
+                config.q_concat.enable(&mut region, offset)?;
+                low.copy_advice(|| "low", &mut region, config.low, offset)?;
+                high.copy_advice(|| "high", &mut region, config.high, offset)?;
+                region.assign_fixed(
+                    || "2^w_1",
+                    config.shift,
+                    offset,
+                    || Value::known(pow2::<F>(low_bits)),
+                )?;
+                region.assign_advice(|| "out", config.out, offset, || out)
+            },
+        )?;
+
+        config.range.assign(
+            layouter.namespace(|| "range check out"),
+            &out,
+            low_bits + high_bits,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::*;
+    use crate::testing::{run_mock_auto, ForgeableCircuit, Forged};
+
+    const LOW_BITS: usize = 3;
+    const HIGH_BITS: usize = 5;
+
+    // Loads `low` and `high`, then concatenates them. The witness for the recombined `out` can be forged.
+    #[derive(Default)]
+    struct ConcatCircuit<F: PrimeField> {
+        low: Value<F>,
+        high: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for ConcatCircuit<F> {
+        type Config = ConcatConfig<F>;
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            ConcatChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+            out: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            let (low, high) = layouter.assign_region(
+                || "Load low and high",
+                |mut region| {
+                    let low = region.assign_advice(|| "low", config.low, 0, || self.low)?;
+                    let high = region.assign_advice(|| "high", config.high, 0, || self.high)?;
+                    Ok((low, high))
+                },
+            )?;
+            let chip = ConcatChip::construct(config);
+            let layouter = layouter.namespace(|| "concat");
+            match out {
+                Some(out) => chip.assign_out(layouter, &low, &high, LOW_BITS, HIGH_BITS, *out),
+                None => chip.concat(layouter, &low, &high, LOW_BITS, HIGH_BITS),
+            }?;
+            Ok(())
+        }
+    }
+
+    fn circuit(low: u64, high: u64) -> Forged<Fp, ConcatCircuit<Fp>> {
+        Forged::honest(ConcatCircuit {
+            low: Value::known(Fp::from(low)),
+            high: Value::known(Fp::from(high)),
+        })
+    }
+
+    #[test]
+    fn test_concat_pass() {
+        for (low, high) in [(0, 0), (5, 0), (0, 17), (5, 17), (7, 31)] {
+            run_mock_auto(&circuit(low, high), vec![]).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_concat_fail() {
+        // high = 32 doesn't fit in 5 bits, so the honest out = 7 + 32 * 8 = 263 doesn't fit in 8 bits
+        let prover = run_mock_auto(&circuit(7, 32), vec![]);
+        assert!(prover.verify().is_err());
+
+        // A recombination that fits in 8 bits but isn't low + high * 2^3
+        let malicious = circuit(5, 17).forge(Value::known(Fp::from(5 * 8 + 5)));
+        assert!(run_mock_auto(&malicious, vec![]).verify().is_err());
+
+        // The correct recombination, pushed outside 8 bits by the field modulus
+        let malicious = circuit(5, 17).forge(Value::known(Fp::from(5 + 17 * 8) - Fp::from(256)));
+        assert!(run_mock_auto(&malicious, vec![]).verify().is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+/// Proves `a` and `b` are coprime from Bezout coefficients the prover supplies. One of a pair of Bezout
+/// coefficients is never positive, so the gate takes the second one negated:
+///     $$a \cdot x - b \cdot y = 1$$
+/// with $x$ and $y$ range-checked to N_BITS bits. As long as `2 * N_BITS + 1 < F::CAPACITY`, which configure
+/// asserts, neither product can wrap around the field, so this holds over the integers too, and any common
+/// divisor of $a$ and $b$ divides 1. Without the range checks it would
+/// prove nothing, since $x = a^{-1}$, $y = 0$ satisfies the gate for any nonzero `a`.
+///
+/// The prover must supply correct coefficients, e.g. $x = a^{-1} \bmod b$ taken in `1..=b` and
+/// $y = (a x - 1) / b$, and no coefficients exist for a pair that isn't coprime. `a` and `b` must already be
+/// known to fit in N_BITS bits, and `a` must be nonzero, since no $x$ scales 0 up to $1 + b y$.
+#[derive(Debug, Clone)]
+struct CoprimeConfig<F: PrimeField, const N_BITS: usize> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    x: Column<Advice>,
+    y: Column<Advice>,
+    q_coprime: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> CoprimeConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        assert!(
+            2 * N_BITS + 1 < F::CAPACITY as usize,
+            "Products of {}-bit values can wrap around the field",
+            N_BITS
+        );
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let x = meta.advice_column();
+        let y = meta.advice_column();
+        let q_coprime = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [a, b, x, y] {
+            meta.enable_equality(column);
+        }
+
+        //     a    |    b    |    x    |    y    | q_coprime
+        //    ------------------------------------------------
+        //     a    |    b    |    x    |    y    |     1
+        meta.create_gate("coprime", |meta| {
+            let q = meta.query_selector(q_coprime);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [(
+                    "a * x - b * y = 1",
+                    a * x - b * y - Expression::Constant(F::ONE),
+                )],
+            )
+        });
+
+        Self {
+            a,
+            b,
+            x,
+            y,
+            q_coprime,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CoprimeChip<F: PrimeField, const N_BITS: usize> {
+    config: CoprimeConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> CoprimeChip<F, N_BITS> {
+    fn construct(config: CoprimeConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> CoprimeConfig<F, N_BITS> {
+        CoprimeConfig::configure(meta)
+    }
+
+    /// Constrains `a * x - b * y = 1` for the witnessed Bezout coefficients `x` and `y`, each range-checked to
+    /// N_BITS bits, so verification fails unless `a` and `b` are coprime.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        x: Value<F>,
+        y: Value<F>,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let (x, y) = layouter.assign_region(
+            || "coprime",
+            |mut region| {
+                let offset = 0;
+                config.q_coprime.enable(&mut region, offset)?;
+                a.copy_advice(|| "a", &mut region, config.a, offset)?;
+                b.copy_advice(|| "b", &mut region, config.b, offset)?;
+                let x = region.assign_advice(|| "x", config.x, offset, || x)?;
+                let y = region.assign_advice(|| "y", config.y, offset, || y)?;
+                Ok((x, y))
+            },
+        )?;
+        for (name, cell) in [("x", &x), ("y", &y)] {
+            config.range.assign(
+                layouter.namespace(|| format!("range check {}", name)),
+                cell,
+                N_BITS,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, pasta::Fp, plonk::Circuit};
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const N_BITS: usize = 8;
+
+    // Proves the private a and b are coprime with the given coefficients
+    #[derive(Default)]
+    struct CoprimeCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+        x: Value<F>,
+        y: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for CoprimeCircuit<F> {
+        type Config = (CoprimeConfig<F, N_BITS>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (CoprimeChip::configure(meta), input)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "Load a and b",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            CoprimeChip::construct(config).assign(
+                layouter.namespace(|| "gcd(a, b) = 1"),
+                &a,
+                &b,
+                self.x,
+                self.y,
+            )
+        }
+    }
+
+    fn run(a: u64, b: u64, x: Fp, y: Fp) -> bool {
+        let circuit = CoprimeCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            x: Value::known(x),
+            y: Value::known(y),
+        };
+        run_mock_auto(&circuit, vec![]).verify().is_ok()
+    }
+
+    // The host reference: x = a^-1 mod b in 1..=b and y = (a x - 1) / b, if a and b are coprime
+    fn bezout(a: u64, b: u64) -> Option<(u64, u64)> {
+        let x = (1..=b).find(|x| (a * x) % b == 1 % b)?;
+        Some((x, (a * x - 1) / b))
+    }
+
+    #[test]
+    fn test_coprime() {
+        for (a, b) in [
+            (3, 7),
+            (7, 3),
+            (1, 1),
+            (1, 200),
+            (200, 1),
+            (35, 64),
+            (255, 254),
+        ] {
+            let (x, y) = bezout(a, b).unwrap();
+            assert_eq!(a * x - b * y, 1);
+            assert!(run(a, b, Fp::from(x), Fp::from(y)), "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_not_coprime() {
+        for (a, b) in [(6, 9), (4, 2), (7, 7), (200, 250)] {
+            assert_eq!(bezout(a, b), None);
+            // The field solutions for small y all need an x far out of range
+            for y in 0..4 {
+                let x = Fp::from(1 + b * y) * Fp::from(a).invert().unwrap();
+                assert!(!run(a, b, x, Fp::from(y)), "{} {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_coprime_wrong_coefficients() {
+        // 3 * 5 - 7 * 2 = 1, but off by one either way it isn't
+        assert!(run(3, 7, Fp::from(5), Fp::from(2)));
+        assert!(!run(3, 7, Fp::from(5), Fp::from(1)));
+        assert!(!run(3, 7, Fp::from(4), Fp::from(2)));
+        // -2 * 3 + 1 * 7 = 1 with the signs the other way round, which wraps y out of range
+        assert!(!run(3, 7, -Fp::from(2), -Fp::from(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Products of 127-bit values can wrap around the field")]
+    fn test_coprime_too_wide() {
+        // 2 * 127 + 1 = 255 bits isn't below the 254 bits of capacity, and 126 bits would be
+        let mut meta = ConstraintSystem::<Fp>::default();
+        CoprimeChip::<Fp, 126>::configure(&mut meta);
+        CoprimeChip::<Fp, 127>::configure(&mut meta);
+    }
+}
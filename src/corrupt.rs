@@ -0,0 +1,182 @@
+use ff::Field;
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+use std::marker::PhantomData;
+
+/// Wraps `C` so that as it's synthesized, `DELTA` is added to the one advice cell in column `COLUMN` at row
+/// `ROW`, counting from the top of the circuit. Everything else is assigned just as `C` assigns it, and the
+/// cells `C` copies the tampered one to keep the honest value. This way a negative test can assert exactly which
+/// `VerifyFailure`s one bad cell causes, without writing a faulty version of the chip under test.
+///
+/// The corruption is part of the type, since the floor planner only sees the circuit as some `Circuit`, e.g.
+/// `Corrupted::<_, 4, 0, -1>::new(circuit)` takes 1 from the cell in advice column 4 at row 0.
+#[derive(Debug, Clone)]
+pub(crate) struct Corrupted<C, const COLUMN: usize, const ROW: usize, const DELTA: i64> {
+    circuit: C,
+}
+
+impl<C, const COLUMN: usize, const ROW: usize, const DELTA: i64> Corrupted<C, COLUMN, ROW, DELTA> {
+    pub(crate) fn new(circuit: C) -> Self {
+        Self { circuit }
+    }
+}
+
+impl<F: Field, C: Circuit<F>, const COLUMN: usize, const ROW: usize, const DELTA: i64> Circuit<F>
+    for Corrupted<C, COLUMN, ROW, DELTA>
+{
+    type Config = C::Config;
+    type FloorPlanner = CorruptingPlanner<C::FloorPlanner, COLUMN, ROW, DELTA>;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.circuit.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit.synthesize(config, layouter)
+    }
+}
+
+/// Lays the circuit out with `P`, handing it an Assignment that tampers with the one cell on the way through.
+#[derive(Debug)]
+pub(crate) struct CorruptingPlanner<P, const COLUMN: usize, const ROW: usize, const DELTA: i64> {
+    _marker: PhantomData<P>,
+}
+
+impl<P: FloorPlanner, const COLUMN: usize, const ROW: usize, const DELTA: i64> FloorPlanner
+    for CorruptingPlanner<P, COLUMN, ROW, DELTA>
+{
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut corrupting = Corrupting::<_, COLUMN, ROW, DELTA> { cs };
+        P::synthesize(&mut corrupting, circuit, config, constants)
+    }
+}
+
+// Passes every call through to `cs`, but for the assignment of the tampered cell
+struct Corrupting<'a, CS, const COLUMN: usize, const ROW: usize, const DELTA: i64> {
+    cs: &'a mut CS,
+}
+
+impl<F: Field, CS: Assignment<F>, const COLUMN: usize, const ROW: usize, const DELTA: i64>
+    Assignment<F> for Corrupting<'_, CS, COLUMN, ROW, DELTA>
+{
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.enter_region(name)
+    }
+
+    fn exit_region(&mut self) {
+        self.cs.exit_region()
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        if (column.index(), row) != (COLUMN, ROW) {
+            return self.cs.assign_advice(annotation, column, row, to);
+        }
+        // Only Field is available here, not PrimeField's From<u64>, so |DELTA| is built up by double-and-add
+        let magnitude = (0..64).rev().fold(F::ZERO, |acc, bit| {
+            let acc = acc.double();
+            if DELTA.unsigned_abs() >> bit & 1 == 1 {
+                acc + F::ONE
+            } else {
+                acc
+            }
+        });
+        let delta = if DELTA < 0 { -magnitude } else { magnitude };
+        self.cs.assign_advice(annotation, column, row, || {
+            to().map(|value| Assigned::from(value.into().evaluate() + delta))
+        })
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
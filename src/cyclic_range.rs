@@ -0,0 +1,262 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig,
+    less_than::{LessThanChip, LessThanConfig},
+};
+
+/// Checks that `value` is an index into a ring buffer of `modulus` slots, $value < modulus$, and returns the
+/// next index, which wraps back to zero past the last slot. With a boolean $w$ flagging the wraparound:
+///     $$next = value + 1 - w \cdot modulus$$
+/// and both $value < modulus$ and $next < modulus$ enforced with the less-than gadget, after range-checking
+/// the three of them to N_BITS bits. Claiming $w = 1$ early makes $next$ negative, and $w = 0$ on the last
+/// slot makes $next = modulus$, so neither passes.
+#[derive(Debug, Clone)]
+struct CyclicRangeConfig<F: PrimeField, const N_BITS: usize> {
+    value: Column<Advice>,
+    modulus: Column<Advice>,
+    wrap: Column<Advice>,
+    next: Column<Advice>,
+    q_wrap: Selector,
+    less_than: LessThanConfig<F, N_BITS>,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> CyclicRangeConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let modulus = meta.advice_column();
+        let wrap = meta.advice_column();
+        let next = meta.advice_column();
+        let q_wrap = meta.selector();
+        let less_than = LessThanChip::configure(meta);
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [value, modulus, next] {
+            meta.enable_equality(column);
+        }
+
+        //     value   |  modulus  |  wrap  |           next            | q_wrap
+        //    -----------------------------------------------------------------------
+        //     value   |     m     |   w    |  value + 1 - w * m        |   1
+        meta.create_gate("wrap", |meta| {
+            let q = meta.query_selector(q_wrap);
+            let value = meta.query_advice(value, Rotation::cur());
+            let modulus = meta.query_advice(modulus, Rotation::cur());
+            let wrap = meta.query_advice(wrap, Rotation::cur());
+            let next = meta.query_advice(next, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "wrap is boolean",
+                        wrap.clone() * (one.clone() - wrap.clone()),
+                    ),
+                    (
+                        "next = value + 1 - wrap * m",
+                        next - (value + one - wrap * modulus),
+                    ),
+                ],
+            )
+        });
+
+        Self {
+            value,
+            modulus,
+            wrap,
+            next,
+            q_wrap,
+            less_than,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CyclicRangeChip<F: PrimeField, const N_BITS: usize> {
+    config: CyclicRangeConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> CyclicRangeChip<F, N_BITS> {
+    fn construct(config: CyclicRangeConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> CyclicRangeConfig<F, N_BITS> {
+        CyclicRangeConfig::configure(meta)
+    }
+
+    /// Constrains `value < modulus`, returning a cell constrained to `(value + 1) mod modulus`.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        modulus: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let wrap = value
+            .value()
+            .zip(modulus.value())
+            .map(|(value, modulus)| *value + F::ONE == *modulus);
+        self.assign_wrap(layouter, value, modulus, wrap)
+    }
+
+    // Like assign, but with the given witness for whether the value wraps around
+    fn assign_wrap(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        modulus: &AssignedCell<F, F>,
+        wrap: Value<bool>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let next = layouter.assign_region(
+            || "wrap",
+            |mut region| {
+                let offset = 0;
+                config.q_wrap.enable(&mut region, offset)?;
+                let value = value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                let modulus =
+                    modulus.copy_advice(|| "modulus", &mut region, config.modulus, offset)?;
+                let wrap = wrap.map(|wrap| F::from(wrap as u64));
+                region.assign_advice(|| "wrap", config.wrap, offset, || wrap)?;
+                let next = value
+                    .value()
+                    .zip(modulus.value())
+                    .zip(wrap)
+                    .map(|((value, modulus), wrap)| *value + F::ONE - wrap * *modulus);
+                region.assign_advice(|| "next", config.next, offset, || next)
+            },
+        )?;
+
+        for (name, cell) in [("value", value), ("modulus", modulus), ("next", &next)] {
+            config.range.assign(
+                layouter.namespace(|| format!("range check {}", name)),
+                cell,
+                N_BITS,
+            )?;
+        }
+        let less_than = LessThanChip::construct(config.less_than.clone());
+        less_than.assert_less_than(layouter.namespace(|| "value < modulus"), value, modulus)?;
+        less_than.assert_less_than(layouter.namespace(|| "next < modulus"), &next, modulus)?;
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 8;
+
+    // Reads the modulus from instance row 0, and exposes the index after the private value at row 1. The
+    // witness for whether the value wraps around can be forged.
+    #[derive(Default)]
+    struct CyclicRangeCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for CyclicRangeCircuit<F> {
+        type Config = (
+            CyclicRangeConfig<F, N_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type Witness = Value<bool>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (CyclicRangeChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            wrap: Option<&Value<bool>>,
+        ) -> Result<(), Error> {
+            let (value, modulus) = layouter.assign_region(
+                || "Load value and modulus",
+                |mut region| {
+                    let value = region.assign_advice(|| "value", input, 0, || self.value)?;
+                    let modulus =
+                        region.assign_advice_from_instance(|| "modulus", instance, 0, input, 1)?;
+                    Ok((value, modulus))
+                },
+            )?;
+            let chip = CyclicRangeChip::construct(config);
+            let next = match wrap {
+                Some(wrap) => {
+                    chip.assign_wrap(layouter.namespace(|| "next"), &value, &modulus, *wrap)
+                }
+                None => chip.assign(layouter.namespace(|| "next"), &value, &modulus),
+            }?;
+            layouter.constrain_instance(next.cell(), instance, 1)
+        }
+    }
+
+    fn circuit(value: u64) -> Forged<Fp, CyclicRangeCircuit<Fp>> {
+        Forged::honest(CyclicRangeCircuit {
+            value: Value::known(Fp::from(value)),
+        })
+    }
+
+    fn public(modulus: u64, next: u64) -> [Fp; 2] {
+        [Fp::from(modulus), Fp::from(next)]
+    }
+
+    #[test]
+    fn test_cyclic_range() {
+        for (value, modulus, next) in [
+            (0, 10, 1),
+            (5, 10, 6),
+            (8, 10, 9),
+            // The last slot wraps back around to zero
+            (9, 10, 0),
+            (254, 255, 0),
+            // A single slot is its own successor
+            (0, 1, 0),
+        ] {
+            assert!(run(&circuit(value), &public(modulus, next)));
+            assert!(!run(&circuit(value), &public(modulus, next + 1)));
+        }
+    }
+
+    #[test]
+    fn test_cyclic_range_out_of_range() {
+        // value == modulus isn't a slot, whatever next index is claimed, nor is anything past it
+        for value in [10, 11, 200] {
+            for next in [0, 1, value + 1, value % 10] {
+                assert!(!run(&circuit(value), &public(10, next)), "{} of 10", value);
+            }
+        }
+        // An empty buffer has no slots at all
+        for next in [0, 1] {
+            assert!(!run(&circuit(0), &public(0, next)));
+        }
+    }
+
+    #[test]
+    fn test_cyclic_range_malicious_wrap() {
+        // Not wrapping on the last slot gives 10, and wrapping early gives 5 + 1 - 10
+        for (value, wrap, next) in [(9, false, Fp::from(10)), (5, true, -Fp::from(4))] {
+            let malicious = circuit(value).forge(Value::known(wrap));
+            assert!(!run(&malicious, &[Fp::from(10), next]));
+        }
+    }
+}
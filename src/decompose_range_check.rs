@@ -1,15 +1,16 @@
 use ff::{Field, PrimeField};
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
     plonk::{
-        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression,
-        Selector,
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression, Fixed,
+        Instance, Selector,
     },
     poly::Rotation,
 };
+use std::fmt;
 use std::marker::PhantomData;
 
-use crate::table::RangeTableConfig;
+use crate::{label::region_name, less_than::pow2, table::RangeTableConfig, witness::WitnessInput};
 
 /// Decomposes an $n$-bit Primefield element $\alpha$ into $W$ windows, each window
 /// being a $K$-bit word, using a running sum $z$.
@@ -35,48 +36,490 @@ use crate::table::RangeTableConfig;
 /// This means that $2^K$ has to be at most `degree_bound - 1` in order for
 /// the range check constraint to stay within the degree bound.
 ///
-/// This is a custom built version of the decompose running sum function.
+/// This is a custom built version of the decompose running sum function. Rather than a single $z$ column,
+/// it assigns the value and its windows $k_i$ in two columns; see running_sum_range_check for the $z$ layout.
 
 #[derive(Debug, Clone)]
-/// A range-constrained value in the circuit produced by the DecomposeRangeCheckConfig.
-struct RangeConstrained<F: PrimeField>(AssignedCell<F, F>);
+/// A range-constrained value in the circuit, produced by the DecomposeRangeCheckConfig and by the brute-force
+/// RangeCheckChip, so callers can take either chip's output as a value that has been range-checked.
+pub(crate) struct RangeConstrained<F: PrimeField>(pub(crate) AssignedCell<F, F>);
+
+/// A value cell together with the range-constrained windows it decomposes into, least significant first.
+#[derive(Debug, Clone)]
+pub(crate) struct DecomposedValue<F: PrimeField> {
+    pub(crate) value: AssignedCell<F, F>,
+    pub(crate) windows: Vec<RangeConstrained<F>>,
+}
 
 // RANGE is the size of the total range we want to check.
 // LOOKUP_RANGE is the size of our lookup table i.e. the max size we can lookup in one check to the table.
 // NUM_BITS is the max number of bits we want to use to represent each value in the lookup range.
 const RANGE: usize = 64;
-const NUM_BITS: usize = 3;
+pub(crate) const NUM_BITS: usize = 3;
 const LOOKUP_RANGE: usize = 8;
 // Thus, here we decompose a number into 3-bit chunks.
 
+/// How a `range_bits`-bit value is split into windows, each narrow enough to be looked up in the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DecompositionPlan {
+    /// The width in bits of each window, least significant first.
+    pub(crate) window_bits: Vec<usize>,
+}
+
+impl DecompositionPlan {
+    pub(crate) fn num_windows(&self) -> usize {
+        self.window_bits.len()
+    }
+
+    pub(crate) fn range_bits(&self) -> usize {
+        self.window_bits.iter().sum()
+    }
+
+    /// The bit offset of each window within the value, i.e. window $i$ has weight $2^{shifts_i}$.
+    pub(crate) fn shifts(&self) -> Vec<usize> {
+        self.window_bits
+            .iter()
+            .scan(0, |shift, bits| {
+                let window_shift = *shift;
+                *shift += bits;
+                Some(window_shift)
+            })
+            .collect()
+    }
+
+    /// Checks the plan can be configured over `F`: it has at least one window, no window is wider than the
+    /// NUM_BITS-bit lookup table, and the value fits in the field's capacity, so the weighted sum of the windows
+    /// can't wrap around the modulus. Returns the reason it can't otherwise.
+    pub(crate) fn validate<F: PrimeField>(&self) -> Result<(), RangeCheckError> {
+        if self.window_bits.is_empty() {
+            return Err(RangeCheckError::EmptyRange);
+        }
+        if let Some(bits) = self.window_bits.iter().find(|bits| **bits > NUM_BITS) {
+            return Err(RangeCheckError::WindowTooWide {
+                window_bits: *bits,
+                table_bits: NUM_BITS,
+            });
+        }
+        if self.range_bits() > F::CAPACITY as usize {
+            return Err(RangeCheckError::ExceedsCapacity {
+                range_bits: self.range_bits(),
+                capacity: F::CAPACITY,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Why a decompose chip can't be configured with the given sizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RangeCheckError {
+    /// There are no bits to check.
+    EmptyRange,
+    /// Windows of 0 bits never cover the range.
+    EmptyWindow,
+    /// A window is wider than the lookup table, so its lookup would reject valid values.
+    WindowTooWide {
+        window_bits: usize,
+        table_bits: usize,
+    },
+    /// The range doesn't fit in the field, so the weighted sum of the windows could wrap around the modulus.
+    ExceedsCapacity { range_bits: usize, capacity: u32 },
+}
+
+impl fmt::Display for RangeCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeCheckError::EmptyRange => write!(f, "Empty value!"),
+            RangeCheckError::EmptyWindow => write!(f, "Lookup windows must be at least 1 bit wide"),
+            RangeCheckError::WindowTooWide { table_bits, .. } => write!(
+                f,
+                "Windows can't be wider than the {}-bit lookup table",
+                table_bits
+            ),
+            RangeCheckError::ExceedsCapacity {
+                range_bits,
+                capacity,
+            } => write!(
+                f,
+                "A {}-bit value doesn't fit in the field's {}-bit capacity",
+                range_bits, capacity
+            ),
+        }
+    }
+}
+
+/// Configures a decompose chip from sizes chosen at runtime, checking them all before anything is added to the
+/// constraint system. A range that isn't a multiple of the window width gets a narrower top window.
+#[derive(Debug, Clone)]
+pub(crate) struct DecomposeRangeCheckBuilder {
+    range_bits: usize,
+    window_bits: usize,
+}
+
+impl DecomposeRangeCheckBuilder {
+    /// Check values to `range_bits` bits, i.e. `0..2^range_bits`.
+    pub(crate) fn range_bits(self, range_bits: usize) -> Self {
+        Self { range_bits, ..self }
+    }
+
+    /// Split values into windows of `window_bits` bits, each looked up in the table.
+    pub(crate) fn window_bits(self, window_bits: usize) -> Self {
+        Self {
+            window_bits,
+            ..self
+        }
+    }
+
+    pub(crate) fn build<F: PrimeField>(
+        self,
+        meta: &mut ConstraintSystem<F>,
+    ) -> Result<DecomposeRangeCheckConfig<F>, RangeCheckError> {
+        if self.window_bits == 0 {
+            return Err(RangeCheckError::EmptyWindow);
+        }
+        let plan = plan_decomposition(self.range_bits, self.window_bits);
+        DecomposeRangeCheckConfig::try_configure_with_plan(meta, plan)
+    }
+}
+
+/// Plans the fewest windows (and so rows) covering `range_bits` bits, with no window wider than `max_lookup_bits`.
+/// Every window is `max_lookup_bits` wide except for the top one, which takes whatever bits are left over.
+pub(crate) fn plan_decomposition(range_bits: usize, max_lookup_bits: usize) -> DecompositionPlan {
+    assert!(
+        max_lookup_bits > 0,
+        "Lookup windows must be at least 1 bit wide"
+    );
+    let num_windows = range_bits.div_ceil(max_lookup_bits);
+    let window_bits = (0..num_windows)
+        .map(|i| max_lookup_bits.min(range_bits - i * max_lookup_bits))
+        .collect();
+    DecompositionPlan { window_bits }
+}
+
+/// Splits `value` into the little-endian windows described by `plan`.
+/// The top window keeps any bits above the planned range, so an out-of-range value fails its lookup.
+/// Works on the bits of the field element's repr, so the range can be as wide as the field.
+pub(crate) fn decompose<F: PrimeField>(value: F, plan: &DecompositionPlan) -> Vec<F> {
+    let repr = value.to_repr();
+    let bits: Vec<bool> = repr
+        .as_ref()
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect();
+    let num_windows = plan.num_windows();
+    plan.window_bits
+        .iter()
+        .zip(plan.shifts())
+        .enumerate()
+        .map(|(i, (bits_in_window, shift))| {
+            let end = if i + 1 < num_windows {
+                shift + bits_in_window
+            } else {
+                bits.len()
+            };
+            bits[shift.min(bits.len())..end.min(bits.len())]
+                .iter()
+                .rev()
+                .fold(F::ZERO, |acc, bit| acc.double() + F::from(*bit as u64))
+        })
+        .collect()
+}
+
+/// Recombines windows with the same weights the decompose gate uses.
+fn reconstruct<F: PrimeField>(windows: &[F], plan: &DecompositionPlan) -> F {
+    windows
+        .iter()
+        .zip(plan.shifts())
+        .fold(F::ZERO, |acc, (window, shift)| {
+            acc + *window * pow2::<F>(shift)
+        })
+}
+
+// Catches a host-side decomposition which disagrees with the gate's weights while testing, instead of
+// only as an opaque constraint failure.
+fn debug_assert_reconstructs<F: PrimeField>(value: F, windows: &[F], plan: &DecompositionPlan) {
+    debug_assert_eq!(
+        reconstruct(windows, plan),
+        value,
+        "host decomposition {:?} doesn't reconstruct the value with window bits {:?}",
+        windows,
+        plan.window_bits
+    );
+}
+
+// Reads the low 128 bits of a field element, assuming a little-endian repr (true for the Pasta fields).
+pub(crate) fn field_to_u128<F: PrimeField>(value: F) -> u128 {
+    let repr = value.to_repr();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&repr.as_ref()[..16]);
+    u128::from_le_bytes(bytes)
+}
+
+/// The integer behind a field element, or None if it doesn't fit in a u128, rather than field_to_u128's low
+/// 128 bits. Assumes a little-endian repr, as field_to_u128 does.
+pub(crate) fn field_to_u128_exact<F: PrimeField>(value: F) -> Option<u128> {
+    let repr = value.to_repr();
+    let (low, high) = repr.as_ref().split_at(16);
+    high.iter()
+        .all(|byte| *byte == 0)
+        .then(|| u128::from_le_bytes(low.try_into().unwrap()))
+}
+
+/// `value` as a field element, or None if the field is too small to hold it and `F::from_u128` would reduce it.
+/// The element's whole repr is compared against `value`'s little-endian bytes, so a reduced image can't slip
+/// through, however wide the repr.
+pub(crate) fn u128_to_field<F: PrimeField>(value: u128) -> Option<F> {
+    let field = F::from_u128(value);
+    let repr = field.to_repr();
+    let (repr, bytes) = (repr.as_ref(), value.to_le_bytes());
+    let exact = (0..repr.len().max(bytes.len()))
+        .all(|i| repr.get(i).copied().unwrap_or(0) == bytes.get(i).copied().unwrap_or(0));
+    exact.then_some(field)
+}
+
+/// An affine map `scale * w + shift` applied to each window `w` before it is looked up in the table.
+/// For example a shift of `-base` checks that a single-window value lies in `base..base + LOOKUP_RANGE`.
+/// Only the main lookup is transformed; the extra lookup on narrow windows always checks the raw window.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LookupTransform<F: PrimeField> {
+    pub(crate) scale: F,
+    pub(crate) shift: F,
+}
+
+impl<F: PrimeField> Default for LookupTransform<F> {
+    fn default() -> Self {
+        Self {
+            scale: F::ONE,
+            shift: F::ZERO,
+        }
+    }
+}
+
+/// The decompose chip's lookup table, configured on its own so several decompose chips can share one table
+/// instead of each adding its own pair of table columns. Load it once with `load`, rather than through each
+/// chip's `load_table`, since a table can only be assigned once.
+#[derive(Debug, Clone)]
+pub(crate) struct SharedRangeTable<F: PrimeField>(pub(crate) RangeTableConfig<F, LOOKUP_RANGE>);
+
+impl<F: PrimeField> SharedRangeTable<F> {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self(RangeTableConfig::configure(meta))
+    }
+
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.0.load(layouter)
+    }
+}
+
+/// Which part of a decomposition to tamper with in assign_value_with_fault.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy)]
+enum DecompositionFault<F: PrimeField> {
+    /// Replace window `i` of the honest decomposition with the given value.
+    Window(usize, F),
+    /// Assign the given value as the original, alongside the honest windows of the real value.
+    Value(F),
+}
+
 #[derive(Debug, Clone)]
-struct DecomposeRangeCheckConfig<F: PrimeField> {
+pub(crate) struct DecomposeRangeCheckConfig<F: PrimeField> {
     value: Column<Advice>,
     value_decomposed: Column<Advice>, // Assume this value perfectly decomposes
+    // Further window columns for the wide layout, see configure_wide. Empty for the usual single column
+    wide_columns: Vec<Column<Advice>>,
     q_decomposed: Selector,
     q_range_check: Selector,
+    // Only needed when some window is narrower than the table, along with the fixed columns of shifts it looks up
+    // by, one per window column
+    q_short_range_check: Option<(Selector, Vec<Column<Fixed>>)>,
+    q_diff: Selector,
+    q_repack: Selector,
+    q_repack_start: Selector,
+    repack_weight: Column<Fixed>,
     table: RangeTableConfig<F, LOOKUP_RANGE>,
+    plan: DecompositionPlan,
     _marker: PhantomData<F>,
 }
 
 impl<F: PrimeField> DecomposeRangeCheckConfig<F> {
     pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self::configure_with_plan(meta, plan_decomposition(RANGE.ilog2() as usize, NUM_BITS))
+    }
+
+    /// A builder for sizes chosen at runtime, starting from those of configure.
+    pub(crate) fn builder() -> DecomposeRangeCheckBuilder {
+        DecomposeRangeCheckBuilder {
+            range_bits: RANGE.ilog2() as usize,
+            window_bits: NUM_BITS,
+        }
+    }
+
+    /// Configures the chip to decompose values into the windows of `plan`, which may be at most NUM_BITS wide.
+    /// Panics with the reason if the plan is invalid, see try_configure_with_plan.
+    pub fn configure_with_plan(meta: &mut ConstraintSystem<F>, plan: DecompositionPlan) -> Self {
+        Self::try_configure_with_plan(meta, plan).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like configure_with_plan, but returns why the plan is invalid rather than panicking. Nothing is added to
+    /// the constraint system then.
+    pub fn try_configure_with_plan(
+        meta: &mut ConstraintSystem<F>,
+        plan: DecompositionPlan,
+    ) -> Result<Self, RangeCheckError> {
+        Self::try_configure_with_transform(meta, plan, LookupTransform::default())
+    }
+
+    /// Like configure_with_plan, but each window `w` is looked up as `scale * w + shift`, see LookupTransform.
+    pub fn configure_with_transform(
+        meta: &mut ConstraintSystem<F>,
+        plan: DecompositionPlan,
+        transform: LookupTransform<F>,
+    ) -> Self {
+        Self::try_configure_with_transform(meta, plan, transform)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like configure_with_transform, but returns why the plan is invalid rather than panicking.
+    pub fn try_configure_with_transform(
+        meta: &mut ConstraintSystem<F>,
+        plan: DecompositionPlan,
+        transform: LookupTransform<F>,
+    ) -> Result<Self, RangeCheckError> {
+        plan.validate::<F>()?;
+        let table = SharedRangeTable::configure(meta);
+        Self::try_configure_with_table(meta, plan, transform, &table)
+    }
+
+    /// Like configure_with_transform, but looks windows up in `table` rather than configuring a table of its own.
+    pub(crate) fn configure_with_table(
+        meta: &mut ConstraintSystem<F>,
+        plan: DecompositionPlan,
+        transform: LookupTransform<F>,
+        table: &SharedRangeTable<F>,
+    ) -> Self {
+        Self::try_configure_with_table(meta, plan, transform, table)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like configure_with_table, but returns why the plan is invalid rather than panicking.
+    pub(crate) fn try_configure_with_table(
+        meta: &mut ConstraintSystem<F>,
+        plan: DecompositionPlan,
+        transform: LookupTransform<F>,
+        table: &SharedRangeTable<F>,
+    ) -> Result<Self, RangeCheckError> {
+        Self::try_configure_wide_with_table(meta, plan, transform, table, 1)
+    }
+
+    /// Like configure_with_plan, but spreads the windows across `num_columns` advice columns, filling each row
+    /// before moving on to the next, so a value takes about `1 / num_columns` as many rows. Window $i$ sits in
+    /// column $i \bmod n$, $\lfloor i / n \rfloor$ rows below the value. Each extra column costs a lookup.
+    pub fn configure_wide(
+        meta: &mut ConstraintSystem<F>,
+        plan: DecompositionPlan,
+        num_columns: usize,
+    ) -> Self {
+        Self::try_configure_wide(meta, plan, num_columns).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like configure_wide, but returns why the plan is invalid rather than panicking.
+    pub fn try_configure_wide(
+        meta: &mut ConstraintSystem<F>,
+        plan: DecompositionPlan,
+        num_columns: usize,
+    ) -> Result<Self, RangeCheckError> {
+        plan.validate::<F>()?;
+        let table = SharedRangeTable::configure(meta);
+        Self::try_configure_wide_with_table(
+            meta,
+            plan,
+            LookupTransform::default(),
+            &table,
+            num_columns,
+        )
+    }
+
+    fn try_configure_wide_with_table(
+        meta: &mut ConstraintSystem<F>,
+        plan: DecompositionPlan,
+        transform: LookupTransform<F>,
+        table: &SharedRangeTable<F>,
+        num_columns: usize,
+    ) -> Result<Self, RangeCheckError> {
+        // Checked up front rather than inside the gate closure, so a bad plan is rejected before any columns
+        // or gates are added
+        plan.validate::<F>()?;
+        assert!(num_columns > 0, "Windows need at least one column");
         let value = meta.advice_column();
         let value_decomposed = meta.advice_column();
+        let wide_columns: Vec<Column<Advice>> =
+            (1..num_columns).map(|_| meta.advice_column()).collect();
+        let window_columns: Vec<Column<Advice>> = std::iter::once(value_decomposed)
+            .chain(wide_columns.iter().copied())
+            .collect();
         let q_decomposed = meta.selector();
         let q_range_check = meta.complex_selector();
-        let table = RangeTableConfig::configure(meta);
-        //        value     |  value_decomposed |    q_decomposed      |   q_range_check   | range_check_table
+        let q_diff = meta.selector();
+        let table = table.0.clone();
+        let q_repack = meta.selector();
+        let q_repack_start = meta.selector();
+        let repack_weight = meta.fixed_column();
+        // Needed to copy the operands of a difference check into this chip's region, and windows out of it
+        meta.enable_equality(value);
+        for column in &window_columns {
+            meta.enable_equality(*column);
+        }
+        // The rows each selector covers for one value of W windows, starting at its first row. For example
+        // 7 bits in 3-bit windows, where the 1-bit top window is the only short one:
+        //        value     |  value_decomposed |  q_decomposed  |  q_range_check  |  q_short_range_check
+        //       ----------------------------------------------------------------------------------------
+        //          v       |         v_0       |       1        |        1        |          0
+        //          -       |         v_1       |       0        |        1        |          0
+        //          -       |         v_2       |       0        |        1        |          1
+        //
+        //   q_decomposed         first row only. The "decompose" gate reaches down all W rows from there.
+        //   q_range_check        every window row. Looks up each window column in the table.
+        //   q_short_range_check  only rows holding a window narrower than NUM_BITS. The shifted lookup below.
+        //
+        // None of them can be merged. Enabling the decompose gate on every window row would check each
+        // window's suffix against the value column, which is empty below the first row. A lookup selector
+        // must be complex, and a gate only on the first row can't share a selector with lookups on every row.
+        //
+        // In the wide layout, e.g. with 3 columns, the windows fill each row before the next:
+        //        value     |  value_decomposed |     wide_0     |     wide_1     |  q_decomposed  |  q_range_check
         //       ---------------------------------------------------------------------------------------------
-        //          v       |         v_0       |          1           |         1         |        0
-        //          -       |         v_1       |          0           |         1         |        1
-        //          -       |         v_2       |          0           |         1         |        2
+        //          v       |         v_0       |      v_1       |      v_2       |       1        |       1
+        //          -       |         v_3       |      v_4       |       0        |       0        |       1
 
         // Lookup each decomposed value individually, not paying attention to bit count
-        meta.lookup(|meta| {
-            let q = meta.query_selector(q_range_check);
-            let decomposed_value = meta.query_advice(value_decomposed, Rotation::cur());
-            vec![(q.clone() * decomposed_value, table.value)]
+        for column in &window_columns {
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q_range_check);
+                let decomposed_value = meta.query_advice(*column, Rotation::cur());
+                let decomposed_value = Expression::Constant(transform.scale) * decomposed_value
+                    + Expression::Constant(transform.shift);
+                vec![(q.clone() * decomposed_value, table.value)]
+            });
+        }
+
+        // A window of b < NUM_BITS bits is also looked up shifted left by NUM_BITS - b bits, which only
+        // stays inside the table if the window's top NUM_BITS - b bits are zero.
+        // The shift differs per window, so it is held in a fixed column next to each window column. Full-width
+        // windows sharing a row with a short one leave their shift at zero, and so look up zero.
+        let has_short_windows = plan.window_bits.iter().any(|bits| *bits < NUM_BITS);
+        let q_short_range_check = has_short_windows.then(|| {
+            let q_short_range_check = meta.complex_selector();
+            let short_shifts: Vec<Column<Fixed>> =
+                window_columns.iter().map(|_| meta.fixed_column()).collect();
+            for (column, short_shift) in window_columns.iter().zip(&short_shifts) {
+                meta.lookup(|meta| {
+                    let q = meta.query_selector(q_short_range_check);
+                    let decomposed_value = meta.query_advice(*column, Rotation::cur());
+                    let shift = meta.query_fixed(*short_shift, Rotation::cur());
+                    vec![(q * decomposed_value * shift, table.value)]
+                });
+            }
+            (q_short_range_check, short_shifts)
         });
 
         // Ensure that the decomposed values add up to the original value
@@ -84,13 +527,17 @@ impl<F: PrimeField> DecomposeRangeCheckConfig<F> {
             let q = meta.query_selector(q_decomposed);
             let value = meta.query_advice(value, Rotation::cur());
             let mut decomposed_values = vec![];
-            let decomposed_parts = RANGE / LOOKUP_RANGE;
+            let decomposed_parts = plan.num_windows();
+            let shifts = plan.shifts();
 
             // Because we rotate up to 8 times here, this gate adds a lot of overhead.
             // It would be much more efficient to also have a prefix sum at each step,
             // and only cover 1-2 different rotations instead per constraint
             for i in 0..decomposed_parts {
-                decomposed_values.push(meta.query_advice(value_decomposed, Rotation(i as i32)));
+                decomposed_values.push(meta.query_advice(
+                    window_columns[i % num_columns],
+                    Rotation((i / num_columns) as i32),
+                ));
             }
 
             // Combines the decomposed values into a single value and asserts equality
@@ -98,17 +545,11 @@ impl<F: PrimeField> DecomposeRangeCheckConfig<F> {
                 |decomposed_parts: usize,
                  value: Expression<F>,
                  decomposed_values: Vec<Expression<F>>| {
-                    assert!(decomposed_parts > 0, "Empty value!");
-                    assert!(
-                        NUM_BITS * decomposed_parts < 64,
-                        "Value doesn't fit in bits!"
-                    );
-                    const multiplier: usize = 1 << NUM_BITS;
                     (0..decomposed_parts).fold(
                         Expression::Constant(F::from(0 as u64)),
                         |expr, i| {
                             expr + decomposed_values[i].clone()
-                                * Expression::Constant(F::from(1_u64 << (NUM_BITS * i)))
+                                * Expression::Constant(pow2::<F>(shifts[i]))
                         },
                     ) - value
                 };
@@ -122,150 +563,1530 @@ impl<F: PrimeField> DecomposeRangeCheckConfig<F> {
             )
         });
 
-        Self {
+        // Ensure that a witnessed difference d satisfies a = b + d
+        //        value     |  value_decomposed |    q_decomposed      |   q_range_check   |   q_diff
+        //       ---------------------------------------------------------------------------------------------
+        //          d       |         d_0       |          1           |         1         |     1
+        //          a       |         d_1       |          0           |         1         |     0
+        //          b       |         -         |          0           |         0         |     0
+        meta.create_gate("diff", |meta| {
+            let q = meta.query_selector(q_diff);
+            let diff = meta.query_advice(value, Rotation::cur());
+            let a = meta.query_advice(value, Rotation::next());
+            let b = meta.query_advice(value, Rotation(2));
+            Constraints::with_selector(q, [("a = b + d", a - (b + diff))])
+        });
+
+        // Accumulates a weighted sum of copied windows, with acc_0 = 0 and the result in acc_m
+        //        value     |  value_decomposed |  repack_weight  |   q_repack   |  q_repack_start
+        //       ---------------------------------------------------------------------------------------------
+        //        acc_0     |         w_0       |      c_0        |      1       |       1
+        //        acc_1     |         w_1       |      c_1        |      1       |       0
+        //        acc_m     |         -         |       -         |      0       |       0
+        meta.create_gate("repack", |meta| {
+            let q = meta.query_selector(q_repack);
+            let acc = meta.query_advice(value, Rotation::cur());
+            let acc_next = meta.query_advice(value, Rotation::next());
+            let window = meta.query_advice(value_decomposed, Rotation::cur());
+            let weight = meta.query_fixed(repack_weight, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [("acc_next = acc + c * w", acc_next - (acc + weight * window))],
+            )
+        });
+
+        meta.create_gate("repack start", |meta| {
+            let q = meta.query_selector(q_repack_start);
+            let acc = meta.query_advice(value, Rotation::cur());
+            Constraints::with_selector(q, [("acc_0 = 0", acc)])
+        });
+
+        Ok(Self {
             value,
             value_decomposed,
+            wide_columns,
             q_decomposed,
             q_range_check,
+            q_short_range_check,
+            q_diff,
+            q_repack,
+            q_repack_start,
+            repack_weight,
             table,
+            plan,
             _marker: PhantomData,
+        })
+    }
+
+    // Assigns the windows of `value` to value_decomposed starting at `offset`, enabling q_decomposed on the
+    // first row, q_range_check on every row of windows, and q_short_range_check on rows with narrow windows.
+    // The caller assigns `value` itself at `offset`.
+    fn assign_decomposed_values(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<Vec<RangeConstrained<F>>, Error> {
+        let windows = value
+            .map(|value| {
+                let windows = decompose(value, &self.plan);
+                debug_assert_reconstructs(value, &windows, &self.plan);
+                windows
+            })
+            .transpose_vec(self.plan.num_windows());
+        self.assign_windows(region, offset, windows)
+    }
+
+    fn assign_windows(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        windows: Vec<Value<F>>,
+    ) -> Result<Vec<RangeConstrained<F>>, Error> {
+        self.enable_windows(region, offset)?;
+        windows
+            .into_iter()
+            .enumerate()
+            .map(|(i, window)| self.assign_window(region, offset, i, window))
+            .collect()
+    }
+
+    // Enables the selectors, short shifts and padding of a value's windows from `offset`, everything but the
+    // windows themselves
+    fn enable_windows(&self, region: &mut Region<'_, F>, offset: usize) -> Result<(), Error> {
+        let num_columns = 1 + self.wide_columns.len();
+        let num_rows = self.rows_used();
+        self.q_decomposed.enable(region, offset)?;
+        for row in 0..num_rows {
+            self.q_range_check.enable(region, offset + row)?;
+        }
+        if let Some((q_short_range_check, short_shifts)) = &self.q_short_range_check {
+            for (i, bits) in self.plan.window_bits.iter().enumerate() {
+                if *bits < NUM_BITS {
+                    let row = offset + i / num_columns;
+                    q_short_range_check.enable(region, row)?;
+                    region.assign_fixed(
+                        || format!("short shift {:?}", i),
+                        short_shifts[i % num_columns],
+                        row,
+                        || Value::known(F::from(1 << (NUM_BITS - bits))),
+                    )?;
+                }
+            }
         }
+        // The last row of the wide layout may not be full, and its spare cells are looked up too
+        for i in self.plan.num_windows()..num_rows * num_columns {
+            let column = self.wide_columns[i % num_columns - 1];
+            region.assign_advice(
+                || format!("window padding {:?}", i),
+                column,
+                offset + i / num_columns,
+                || Value::known(F::ZERO),
+            )?;
+        }
+        Ok(())
+    }
+
+    // Assigns window `i` of the value at `offset`
+    fn assign_window(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        i: usize,
+        window: Value<F>,
+    ) -> Result<RangeConstrained<F>, Error> {
+        let num_columns = 1 + self.wide_columns.len();
+        let column = match i % num_columns {
+            0 => self.value_decomposed,
+            j => self.wide_columns[j - 1],
+        };
+        region
+            .assign_advice(
+                || format!("decomposed_value {:?}", i),
+                column,
+                offset + i / num_columns,
+                || window,
+            )
+            .map(RangeConstrained)
     }
 
-    // Note that the two types of region.assign_advice calls happen together so that it is the same region
-    pub fn assign_value(&self, mut layouter: impl Layouter<F>, value: u128) -> Result<bool, Error> {
+    // Test-only version of assign_value which assigns a faulty decomposition, to check that the decompose gate catches it
+    #[cfg(test)]
+    fn assign_value_with_fault(
+        &self,
+        mut layouter: impl Layouter<F>,
+        label: Option<&str>,
+        value: u128,
+        fault: DecompositionFault<F>,
+    ) -> Result<Vec<RangeConstrained<F>>, Error> {
         layouter.assign_region(
-            || "Assign value",
+            || region_name(label, "Assign value"),
             |mut region| {
                 let offset = 0;
+                let honest_value = F::from_u128(value);
+                let mut windows = decompose(honest_value, &self.plan);
+
+                let value = match fault {
+                    DecompositionFault::Window(i, window) => {
+                        windows[i] = window;
+                        honest_value
+                    }
+                    DecompositionFault::Value(value) => value,
+                };
+                region.assign_advice(|| "value", self.value, offset, || Value::known(value))?;
+                self.assign_windows(
+                    &mut region,
+                    offset,
+                    windows.into_iter().map(Value::known).collect(),
+                )
+            },
+        )
+    }
+
+    /// The windows the chip assigns for `value`, least significant first, computed with plain integer shifts
+    /// rather than the field arithmetic `assign_value` uses, so tests can check the assigned cells against it.
+    /// As in the circuit, the top window keeps any bits above the planned range. Panics if a window doesn't fit
+    /// in a u64, which only happens for a value far outside the range.
+    pub(crate) fn expected_windows(&self, value: u128) -> Vec<u64> {
+        let num_windows = self.plan.num_windows();
+        self.plan
+            .window_bits
+            .iter()
+            .zip(self.plan.shifts())
+            .enumerate()
+            .map(|(i, (bits, shift))| {
+                let window = value.checked_shr(shift as u32).unwrap_or(0);
+                let window = if i + 1 < num_windows {
+                    window & ((1 << bits) - 1)
+                } else {
+                    window
+                };
+                u64::try_from(window).expect("window doesn't fit in a u64")
+            })
+            .collect()
+    }
+
+    // Note that the two types of region.assign_advice calls happen together so that it is the same region.
+    // Returns `Error::Synthesis` if the field can't represent `value` exactly, see u128_to_field.
+    // `label`, if given, prefixes the region name, see label::region_name. The other assign methods take one too.
+    pub fn assign_value(
+        &self,
+        layouter: impl Layouter<F>,
+        label: Option<&str>,
+        value: u128,
+    ) -> Result<bool, Error> {
+        self.assign_decomposed(layouter, label, value).map(|_| true)
+    }
+
+    // Same as assign_value, but returns the range-checked value cell and its windows
+    fn assign_decomposed(
+        &self,
+        layouter: impl Layouter<F>,
+        label: Option<&str>,
+        value: u128,
+    ) -> Result<DecomposedValue<F>, Error> {
+        let value = u128_to_field(value).ok_or(Error::Synthesis)?;
+        self.assign_field_value(layouter, label, Value::known(value))
+    }
 
-                // Enable q_range_check
-                self.q_decomposed.enable(&mut region, offset)?;
+    /// Range-checks a field element directly, for ranges wider than a u128 (up to the field's capacity).
+    pub fn assign_field_value(
+        &self,
+        mut layouter: impl Layouter<F>,
+        label: Option<&str>,
+        value: Value<F>,
+    ) -> Result<DecomposedValue<F>, Error> {
+        layouter.assign_region(
+            || region_name(label, "Assign value"),
+            |mut region| {
+                let offset = 0;
 
                 // Assign value
-                region.assign_advice(
-                    || "value",
-                    self.value,
-                    offset,
-                    || Value::known(F::from_u128(value)),
-                )?;
+                let cell = region.assign_advice(|| "value", self.value, offset, || value)?;
 
-                // Enable q_decomposed
-                let decomposed_parts = RANGE / LOOKUP_RANGE;
-                let mut decompose_in_progress = value;
-                for i in 0..decomposed_parts {
-                    // offset = i;
-                    self.q_range_check.enable(&mut region, i)?;
-                    let decomposed_val = decompose_in_progress % { 1 << (i * NUM_BITS) };
-                    region.assign_advice(
-                        || format!("decomposed_value {:?}", i),
-                        self.value_decomposed,
-                        i,
-                        || Value::known(F::from_u128(decomposed_val)),
-                    )?;
-                    decompose_in_progress = decompose_in_progress >> (i * NUM_BITS);
-                }
-                Ok(true)
+                // Enable q_decomposed and q_range_check, and assign the windows
+                let windows = self.assign_decomposed_values(&mut region, offset, value)?;
+                Ok(DecomposedValue {
+                    value: cell,
+                    windows,
+                })
             },
         )
     }
-}
-#[derive(Default, Clone)]
-struct DecomposeRangeCheckCircuit<F: PrimeField> {
-    // Since this is only relevant for the witness, we can opt to make this whatever convenient type we want
-    pub value: u128,
-    _marker: PhantomData<F>,
-}
 
-impl<F: PrimeField> Circuit<F> for DecomposeRangeCheckCircuit<F> {
-    type Config = DecomposeRangeCheckConfig<F>;
-    type FloorPlanner = SimpleFloorPlanner;
+    /// Range-checks a value given as its windows, least significant first, rather than as one integer, so that
+    /// a value as wide as the field can be fed in piece by piece, e.g. from a byte stream. Each window is assigned
+    /// and looked up as it arrives, while the weighted sum of the windows so far is accumulated into the value
+    /// cell, which the decompose gate then checks as usual.
+    ///
+    /// `windows` must yield exactly the plan's number of windows, or this returns `Error::Synthesis`. The floor
+    /// planner may run the region more than once, so the iterator is cloned for each run rather than collected.
+    pub fn assign_windows_streaming<I>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        windows: I,
+    ) -> Result<DecomposedValue<F>, Error>
+    where
+        I: IntoIterator<Item = Value<F>>,
+        I::IntoIter: Clone,
+    {
+        let windows = windows.into_iter();
+        let shifts = self.plan.shifts();
+        layouter.assign_region(
+            || "Assign windows",
+            |mut region| {
+                let offset = 0;
+                self.enable_windows(&mut region, offset)?;
+                let mut windows = windows.clone();
+                let mut value = Value::known(F::ZERO);
+                let mut assigned = Vec::with_capacity(shifts.len());
+                for (i, shift) in shifts.iter().enumerate() {
+                    let window = windows.next().ok_or(Error::Synthesis)?;
+                    value = value
+                        .zip(window)
+                        .map(|(value, window)| value + window * pow2::<F>(*shift));
+                    assigned.push(self.assign_window(&mut region, offset, i, window)?);
+                }
+                if windows.next().is_some() {
+                    return Err(Error::Synthesis);
+                }
+                let value = region.assign_advice(|| "value", self.value, offset, || value)?;
+                Ok(DecomposedValue {
+                    value,
+                    windows: assigned,
+                })
+            },
+        )
+    }
 
-    // Circuit without witnesses, called only during key generation
-    fn without_witnesses(&self) -> Self {
-        Self {
-            value: 0,
-            _marker: PhantomData,
-        }
+    /// The number of bits values are range-checked to.
+    pub(crate) fn range_bits(&self) -> usize {
+        self.plan.range_bits()
     }
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let config = DecomposeRangeCheckConfig::configure(meta);
-        config
+    /// Loads the lookup table. Circuits which range-check several values only need to do this once.
+    pub(crate) fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.table.load(layouter)
     }
 
-    fn synthesize(
+    /// Range-checks a cell assigned elsewhere, e.g. by another chip, by copying it into the value column.
+    pub(crate) fn assign_cell(
         &self,
-        config: Self::Config,
         mut layouter: impl Layouter<F>,
-    ) -> Result<(), Error> {
-        config.table.load(&mut layouter)?;
-        print!("Synthesize being called...");
-        assert!(
-            RANGE % LOOKUP_RANGE == 0,
-            "Range must be a multiple of lookup range"
-        );
-        let mut value = config.assign_value(layouter.namespace(|| "Assign all values"), self.value);
-        Ok(())
+        label: Option<&str>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<DecomposedValue<F>, Error> {
+        layouter.assign_region(
+            || region_name(label, "Assign cell"),
+            |mut region| self.assign_cell_in_region(&mut region, 0, value),
+        )
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use halo2_proofs::{
-        circuit::floor_planner::V1,
-        dev::{FailureLocation, MockProver, VerifyFailure},
-        pasta::Fp,
-        plonk::{Any, Circuit},
-    };
+    /// Like assign_cell, but lays the decomposition out in an existing region from `offset`, taking
+    /// `window_rows` rows.
+    pub(crate) fn assign_cell_in_region(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: &AssignedCell<F, F>,
+    ) -> Result<DecomposedValue<F>, Error> {
+        let cell = value.copy_advice(|| "value", region, self.value, offset)?;
+        let windows = self.assign_decomposed_values(region, offset, cell.value().copied())?;
+        Ok(DecomposedValue {
+            value: cell,
+            windows,
+        })
+    }
 
-    use super::*;
+    /// The number of rows one value takes up in assign_value, assign_cell and the like, i.e. the rows of its
+    /// windows, so callers can plan what goes below it.
+    pub(crate) fn rows_used(&self) -> usize {
+        self.plan
+            .num_windows()
+            .div_ceil(1 + self.wide_columns.len())
+    }
 
-    #[test]
-    fn test_range_check_pass() {
-        let k = 10; // 8, 128, etc
+    /// The number of advice rows the lookups of `num_values` values decomposed one after another take up, for
+    /// sizing `k`. On top of these the circuit needs the `LOOKUP_RANGE` table rows, which sit in their own
+    /// columns, and the blinding rows of `ConstraintSystem::minimum_rows`.
+    pub fn lookup_rows(&self, num_values: usize) -> usize {
+        num_values * self.rows_used()
+    }
 
-        // Successful cases
-        for i in 0..RANGE {
-            let i = 0;
-            let circuit = DecomposeRangeCheckCircuit::<Fp> {
-                value: i as u128,
-                _marker: PhantomData,
-            };
+    /// Extracts bits `start_bit..start_bit + len_bits` of a decomposed value into a single cell, constrained to
+    /// equal the weighted sum of the windows covering those bits. Both ends of the bit range must fall on
+    /// window boundaries of the plan.
+    pub fn repack(
+        &self,
+        layouter: impl Layouter<F>,
+        windows: &[RangeConstrained<F>],
+        start_bit: usize,
+        len_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(
+            windows.len(),
+            self.plan.num_windows(),
+            "Expected all windows of a decomposed value"
+        );
+        let end_bit = start_bit + len_bits;
+        let shifts = self.plan.shifts();
+        let is_boundary = |bit: usize| shifts.contains(&bit) || bit == self.plan.range_bits();
+        assert!(
+            is_boundary(start_bit) && is_boundary(end_bit),
+            "Bits {}..{} don't fall on window boundaries {:?}",
+            start_bit,
+            end_bit,
+            shifts
+        );
+        let weighted: Vec<_> = windows
+            .iter()
+            .zip(shifts.iter())
+            .filter(|(_, shift)| (start_bit..end_bit).contains(*shift))
+            .map(|(window, shift)| (window, pow2::<F>(*shift - start_bit)))
+            .collect();
+        self.weighted_sum(layouter, "Repack windows", &weighted)
+    }
 
-            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
-            prover.assert_satisfied();
-        }
+    /// Constrains a cell to the plain sum of all the windows of a decomposed value, i.e. its digit sum in base
+    /// $2^K$ when every window is $K$ bits wide. Useful as a simple checksum.
+    pub fn digit_sum(
+        &self,
+        mut layouter: impl Layouter<F>,
+        windows: &[RangeConstrained<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "Digit sum",
+            |mut region| self.digit_sum_in_region(&mut region, 0, windows),
+        )
     }
 
-    #[test]
-    fn test_range_check_fail() {
-        let k = 10;
-        // Out-of-range `value = 8`
-        let circuit = DecomposeRangeCheckCircuit::<Fp> {
-            value: RANGE as u128,
-            _marker: PhantomData,
-        };
-        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
-        match prover.verify() {
-            Err(e) => {
-                println!("Error successfully achieved!");
-            }
-            _ => assert_eq!(1, 0),
-        }
+    /// Like digit_sum, but lays the sum out in an existing region from `offset`, taking one row per window and
+    /// one for the result.
+    pub(crate) fn digit_sum_in_region(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        windows: &[RangeConstrained<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let weighted: Vec<_> = windows.iter().map(|window| (window, F::ONE)).collect();
+        self.weighted_sum_in_region(region, offset, &weighted)
     }
 
-    // $ cargo test --release --all-features print_range_check_1
-    #[cfg(feature = "dev-graph")]
-    #[test]
-    fn print_range_check_1() {
-        use plotters::prelude::*;
+    fn weighted_sum(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        windows: &[(&RangeConstrained<F>, F)],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || name,
+            |mut region| self.weighted_sum_in_region(&mut region, 0, windows),
+        )
+    }
 
-        let root = BitMapBackend::new("range-check-decomposed-layout.png", (1024, 3096))
-            .into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("Range Check 1 Layout", ("sans-serif", 60))
+    // Copies each window into its own row of the repack gate, accumulating the sum of window * weight
+    fn weighted_sum_in_region(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        windows: &[(&RangeConstrained<F>, F)],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.q_repack_start.enable(region, offset)?;
+        let mut acc =
+            region.assign_advice(|| "acc_0", self.value, offset, || Value::known(F::ZERO))?;
+
+        for (i, (window, weight)) in windows.iter().enumerate() {
+            self.q_repack.enable(region, offset + i)?;
+            let window = window.0.copy_advice(
+                || format!("window {:?}", i),
+                region,
+                self.value_decomposed,
+                offset + i,
+            )?;
+            region.assign_fixed(
+                || format!("weight {:?}", i),
+                self.repack_weight,
+                offset + i,
+                || Value::known(*weight),
+            )?;
+            let next = acc
+                .value()
+                .zip(window.value())
+                .map(|(acc, window)| *acc + *weight * window);
+            acc = region.assign_advice(
+                || format!("acc_{:?}", i + 1),
+                self.value,
+                offset + i + 1,
+                || next,
+            )?;
+        }
+        Ok(acc)
+    }
+
+    /// Constrains two decomposed values to share their top `prefix_len` windows, e.g. the same high part of two
+    /// identifiers from one range partition. Both must be decomposed with this chip's plan.
+    pub fn shared_prefix(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a_windows: &[RangeConstrained<F>],
+        b_windows: &[RangeConstrained<F>],
+        prefix_len: usize,
+    ) -> Result<(), Error> {
+        let num_windows = self.plan.num_windows();
+        assert!(
+            a_windows.len() == num_windows && b_windows.len() == num_windows,
+            "Expected all windows of both decomposed values"
+        );
+        assert!(
+            prefix_len <= num_windows,
+            "A prefix of {} windows is longer than the {} windows of the plan",
+            prefix_len,
+            num_windows
+        );
+        layouter.assign_region(
+            || "Shared prefix",
+            |mut region| {
+                for (a, b) in a_windows.iter().zip(b_windows).rev().take(prefix_len) {
+                    region.constrain_equal(a.0.cell(), b.0.cell())?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Like assign_value, but first checks on the host that `value` is exactly what the circuit will see:
+    /// the plan's range must be narrower than the field's capacity so that `F::from_u128` can't reduce
+    /// anything in range, and `value` must fit in that range. Otherwise returns `Error::Synthesis` up front,
+    /// rather than building a witness which would only be rejected at verification.
+    pub fn assign_value_strict(
+        &self,
+        layouter: impl Layouter<F>,
+        label: Option<&str>,
+        value: u128,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let range_bits = self.plan.range_bits();
+        let fits_range = range_bits >= 128 || value >> range_bits == 0;
+        let no_reduction = u128_to_field::<F>(value).is_some();
+        if range_bits >= F::CAPACITY as usize || !fits_range || !no_reduction {
+            return Err(Error::Synthesis);
+        }
+        self.assign_decomposed(layouter, label, value)
+            .map(|decomposed| decomposed.value)
+    }
+
+    /// Range-checks `value` and constrains it to equal both public inputs at the given (instance column, row)s,
+    /// e.g. a commitment opening and a separate assertion about the same value.
+    pub fn assign_value_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        label: Option<&str>,
+        value: u128,
+        instances: [(Column<Instance>, usize); 2],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let cell = self
+            .assign_decomposed(layouter.namespace(|| "Assign value"), label, value)?
+            .value;
+        // The value column has equality enabled in configure, so the cell can be copied to the instance columns
+        for (column, row) in instances {
+            layouter.constrain_instance(cell.cell(), column, row)?;
+        }
+        Ok(cell)
+    }
+
+    /// Range-checks `value` to `0..2^range_bits` and constrains it to equal the public input at `instance_row`
+    /// of `instance`, which must have equality enabled. A `range_bits` narrower than the plan's is checked by
+    /// repacking the windows below it and constraining the repacked cell to the value, so it must fall on a
+    /// window boundary.
+    pub fn assign_public_bounded(
+        &self,
+        mut layouter: impl Layouter<F>,
+        label: Option<&str>,
+        value: u128,
+        instance: Column<Instance>,
+        instance_row: usize,
+        range_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(
+            range_bits <= self.plan.range_bits(),
+            "A {}-bit bound is wider than the {}-bit plan",
+            range_bits,
+            self.plan.range_bits()
+        );
+        let decomposed =
+            self.assign_decomposed(layouter.namespace(|| "Assign value"), label, value)?;
+        if range_bits < self.plan.range_bits() {
+            let low = self.repack(
+                layouter.namespace(|| "Low windows"),
+                &decomposed.windows,
+                0,
+                range_bits,
+            )?;
+            layouter.assign_region(
+                || "Bound value",
+                |mut region| region.constrain_equal(low.cell(), decomposed.value.cell()),
+            )?;
+        }
+        layouter.constrain_instance(decomposed.value.cell(), instance, instance_row)?;
+        Ok(decomposed.value)
+    }
+
+    /// Witnesses `d = a - b` in-circuit, constrains `a = b + d`, and range-checks `d` to `0..RANGE`.
+    /// A negative difference wraps around the field, and so fails the range check.
+    pub fn assign_diff_range(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<RangeConstrained<F>, Error> {
+        let diff = a.value().zip(b.value()).map(|(a, b)| *a - *b);
+        self.assign_diff(layouter, a, b, diff)
+    }
+
+    /// Same as assign_diff_range, with `a` and `b` copied from rows `a_row` and `b_row` of the instance column,
+    /// which must have equality enabled. This proves the gap between two public values is bounded, without the
+    /// prover supplying them again as witnesses.
+    pub fn assign_instance_diff_range(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        a_row: usize,
+        b_row: usize,
+    ) -> Result<RangeConstrained<F>, Error> {
+        layouter.assign_region(
+            || "Assign instance difference",
+            |mut region| {
+                let offset = 0;
+
+                self.q_diff.enable(&mut region, offset)?;
+                let a = region.assign_advice_from_instance(
+                    || "a",
+                    instance,
+                    a_row,
+                    self.value,
+                    offset + 1,
+                )?;
+                let b = region.assign_advice_from_instance(
+                    || "b",
+                    instance,
+                    b_row,
+                    self.value,
+                    offset + 2,
+                )?;
+                let diff = a.value().zip(b.value()).map(|(a, b)| *a - *b);
+                let diff = region.assign_advice(|| "diff", self.value, offset, || diff)?;
+
+                self.assign_decomposed_values(&mut region, offset, diff.value().copied())?;
+                Ok(RangeConstrained(diff))
+            },
+        )
+    }
+
+    fn assign_diff(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        diff: Value<F>,
+    ) -> Result<RangeConstrained<F>, Error> {
+        layouter.assign_region(
+            || "Assign difference",
+            |mut region| {
+                let offset = 0;
+
+                self.q_diff.enable(&mut region, offset)?;
+                let diff = region.assign_advice(|| "diff", self.value, offset, || diff)?;
+                a.copy_advice(|| "a", &mut region, self.value, offset + 1)?;
+                b.copy_advice(|| "b", &mut region, self.value, offset + 2)?;
+
+                self.assign_decomposed_values(&mut region, offset, diff.value().copied())?;
+                Ok(RangeConstrained(diff))
+            },
+        )
+    }
+}
+#[derive(Default, Clone)]
+/// With PUBLIC set, the circuit also constrains the value, which the decompose gate ties to the concatenation
+/// of its windows, to equal the public input at row 0, so a verifier learns that this public number splits
+/// into in-range windows.
+pub(crate) struct DecomposeRangeCheckCircuit<F: PrimeField, const PUBLIC: bool = false> {
+    // Since this is only relevant for the witness, we can opt to make this whatever convenient type we want
+    pub value: u128,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const PUBLIC: bool> DecomposeRangeCheckCircuit<F, PUBLIC> {
+    fn builder() -> DecomposeRangeCheckCircuitBuilder<F, PUBLIC> {
+        DecomposeRangeCheckCircuitBuilder {
+            circuit: Self::default(),
+        }
+    }
+
+    /// The circuit witnessing `n`.
+    pub(crate) fn known(n: u64) -> Self {
+        Self::builder().value(n as u128).build()
+    }
+
+    /// The keygen circuit. The witness is a plain `u128` rather than a `Value`, so as in without_witnesses
+    /// it's 0, which doesn't change the circuit's shape.
+    fn unknown() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Builds a DecomposeRangeCheckCircuit, e.g. `DecomposeRangeCheckCircuit::builder().value(5).build()`.
+struct DecomposeRangeCheckCircuitBuilder<F: PrimeField, const PUBLIC: bool> {
+    circuit: DecomposeRangeCheckCircuit<F, PUBLIC>,
+}
+
+impl<F: PrimeField, const PUBLIC: bool> DecomposeRangeCheckCircuitBuilder<F, PUBLIC> {
+    fn value(self, value: u128) -> Self {
+        Self {
+            circuit: DecomposeRangeCheckCircuit {
+                value,
+                ..self.circuit
+            },
+        }
+    }
+
+    fn build(self) -> DecomposeRangeCheckCircuit<F, PUBLIC> {
+        self.circuit
+    }
+}
+
+impl<F: PrimeField, const PUBLIC: bool> Circuit<F> for DecomposeRangeCheckCircuit<F, PUBLIC> {
+    type Config = (DecomposeRangeCheckConfig<F>, Option<Column<Instance>>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // Circuit without witnesses, called only during key generation
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = PUBLIC.then(|| {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            instance
+        });
+        let config = DecomposeRangeCheckConfig::configure(meta);
+        (config, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.0.table.load(&mut layouter)?;
+        assert!(
+            RANGE % LOOKUP_RANGE == 0,
+            "Range must be a multiple of lookup range"
+        );
+        let value = Self::assign_witness(
+            &config,
+            layouter.namespace(|| "Assign all values"),
+            Value::known(u128_to_field(self.value).ok_or(Error::Synthesis)?),
+        )?;
+        if let Some(instance) = config.1 {
+            layouter.constrain_instance(value.cell(), instance, 0)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField, const PUBLIC: bool> WitnessInput<F> for DecomposeRangeCheckCircuit<F, PUBLIC> {
+    // An unknown value becomes 0, as in without_witnesses. A value past a u128 can't be held as the witness at
+    // all, rather than being cut down to its low 128 bits.
+    fn from_witness(value: Value<F>) -> Result<Self, Error> {
+        let mut witness = Some(0);
+        value.map(|value| witness = field_to_u128_exact(value));
+        Ok(Self::builder()
+            .value(witness.ok_or(Error::Synthesis)?)
+            .build())
+    }
+
+    fn assign_witness(
+        (config, _): &Self::Config,
+        layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(config.assign_field_value(layouter, None, value)?.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::{Any, Circuit},
+    };
+
+    use super::*;
+    use crate::testing::{
+        assert_selector_rows, assert_witness_range, cell_to_u128, compare_planners,
+    };
+    use crate::testing::{min_k, run_mock_auto, ForgeableCircuit, Forged};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_range_check_pass() {
+        let k = 10; // 8, 128, etc
+
+        // Successful cases
+        for i in 0..RANGE {
+            let i = 0;
+            let circuit = DecomposeRangeCheckCircuit::<Fp> {
+                value: i as u128,
+                _marker: PhantomData,
+            };
+
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_range_check_fail() {
+        let k = 10;
+        // Out-of-range `value = 8`
+        let circuit = DecomposeRangeCheckCircuit::<Fp> {
+            value: RANGE as u128,
+            _marker: PhantomData,
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        match prover.verify() {
+            Err(e) => {
+                println!("Error successfully achieved!");
+            }
+            _ => assert_eq!(1, 0),
+        }
+    }
+
+    // Loads `a` and `b` into the value column, then range-checks `a - b` via assign_diff_range.
+    // The witness for `a - b` can be forged.
+    #[derive(Default)]
+    struct DiffRangeCheckCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for DiffRangeCheckCircuit<F> {
+        type Config = DecomposeRangeCheckConfig<F>;
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            DecomposeRangeCheckConfig::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+            diff: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let (a, b) = layouter.assign_region(
+                || "Load a and b",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", config.value, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", config.value, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            match diff {
+                Some(diff) => config.assign_diff(layouter.namespace(|| "diff"), &a, &b, *diff),
+                None => config.assign_diff_range(layouter.namespace(|| "diff"), &a, &b),
+            }?;
+            Ok(())
+        }
+    }
+
+    fn diff_circuit(a: u64, b: u64) -> Forged<Fp, DiffRangeCheckCircuit<Fp>> {
+        Forged::honest(DiffRangeCheckCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        })
+    }
+
+    #[test]
+    fn test_diff_range_check_pass() {
+        let k = 10;
+        for (a, b) in [(50, 8), (8, 8), (63, 0), (100, 37)] {
+            let prover = MockProver::run(k, &diff_circuit(a, b), vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_diff_range_check_fail() {
+        let k = 10;
+        // A negative difference wraps around the field, and 64 is just out of range
+        for (a, b) in [(8, 50), (0, 1), (100, 36)] {
+            let prover = MockProver::run(k, &diff_circuit(a, b), vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_diff_range_check_malicious_diff() {
+        let k = 10;
+        // 5 is in range, but isn't 50 - 8
+        let circuit = diff_circuit(50, 8).forge(Value::known(Fp::from(5)));
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    // Range-checks the difference between instance rows 0 and 1, with the rows in either order
+    #[derive(Default)]
+    struct InstanceDiffCircuit<F: PrimeField> {
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for InstanceDiffCircuit<F> {
+        type Config = (DecomposeRangeCheckConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (DecomposeRangeCheckConfig::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            config.assign_instance_diff_range(layouter.namespace(|| "diff"), instance, 0, 1)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_instance_diff_range() {
+        let k = 10;
+        let circuit = InstanceDiffCircuit::<Fp>::default();
+        let run = |a: u64, b: u64| {
+            MockProver::run(k, &circuit, vec![vec![Fp::from(a), Fp::from(b)]])
+                .unwrap()
+                .verify()
+        };
+        for (a, b) in [
+            (50, 8),
+            (8, 8),
+            (RANGE as u64 + 7, 8),
+            (RANGE as u64 - 1, 0),
+        ] {
+            assert_eq!(run(a, b), Ok(()));
+        }
+        // A gap of RANGE is too wide, and a negative gap wraps around the field
+        for (a, b) in [(RANGE as u64 + 8, 8), (RANGE as u64, 0), (8, 50), (0, 1)] {
+            assert!(run(a, b).is_err());
+        }
+    }
+
+    #[test]
+    fn test_plan_decomposition() {
+        let plan = plan_decomposition(64, 8);
+        assert_eq!(plan.num_windows(), 8);
+        assert_eq!(plan.window_bits, vec![8; 8]);
+
+        // The default 0..RANGE config
+        assert_eq!(plan_decomposition(6, NUM_BITS).window_bits, vec![3, 3]);
+        // Leftover bits go in a narrower top window
+        let plan = plan_decomposition(8, NUM_BITS);
+        assert_eq!(plan.window_bits, vec![3, 3, 2]);
+        assert_eq!(plan.shifts(), vec![0, 3, 6]);
+        assert_eq!(plan.range_bits(), 8);
+    }
+
+    // Decomposes into the windows of plan_decomposition(PLANNED_RANGE_BITS, NUM_BITS), i.e. 3 + 3 + 2 bits
+    const PLANNED_RANGE_BITS: usize = 8;
+
+    #[derive(Default)]
+    struct PlannedDecomposeCircuit<F: PrimeField> {
+        value: u128,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for PlannedDecomposeCircuit<F> {
+        type Config = DecomposeRangeCheckConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let plan = plan_decomposition(PLANNED_RANGE_BITS, NUM_BITS);
+            DecomposeRangeCheckConfig::configure_with_plan(meta, plan)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            config.assign_value(layouter.namespace(|| "Assign value"), None, self.value)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_with_plan() {
+        let k = 10;
+        for value in [0, 1, 64, 200, 255] {
+            let circuit = PlannedDecomposeCircuit::<Fp> {
+                value,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // These reconstruct correctly, but the top window is 4, 7 and 64: the first two still fit a full
+        // 3-bit window, so only the shifted lookup on the 2-bit top window rejects them
+        for value in [256, 511, 4096] {
+            let circuit = PlannedDecomposeCircuit::<Fp> {
+                value,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    // Checks values lie in BASE..BASE + LOOKUP_RANGE, by looking up the single window shifted down by BASE
+    const BASE: u64 = 100;
+
+    #[derive(Default)]
+    struct ShiftedLookupCircuit<F: PrimeField> {
+        value: u128,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for ShiftedLookupCircuit<F> {
+        type Config = DecomposeRangeCheckConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let transform = LookupTransform {
+                scale: F::ONE,
+                shift: -F::from(BASE),
+            };
+            DecomposeRangeCheckConfig::configure_with_transform(
+                meta,
+                plan_decomposition(NUM_BITS, NUM_BITS),
+                transform,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            config.assign_value(layouter.namespace(|| "Assign value"), None, self.value)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_shifted_lookup() {
+        let k = 10;
+        for value in [BASE, BASE + 1, BASE + LOOKUP_RANGE as u64 - 1] {
+            let circuit = ShiftedLookupCircuit::<Fp> {
+                value: value as u128,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+        for value in [0, BASE - 1, BASE + LOOKUP_RANGE as u64] {
+            let circuit = ShiftedLookupCircuit::<Fp> {
+                value: value as u128,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    // Range-checks `value` and exposes it at row 0 of the first instance column and row 1 of the second
+    #[derive(Default)]
+    struct PublicDecomposeCircuit<F: PrimeField> {
+        value: u128,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for PublicDecomposeCircuit<F> {
+        type Config = (DecomposeRangeCheckConfig<F>, [Column<Instance>; 2]);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instances = [meta.instance_column(), meta.instance_column()];
+            for instance in instances {
+                meta.enable_equality(instance);
+            }
+            (DecomposeRangeCheckConfig::configure(meta), instances)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instances): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            config.assign_value_public(
+                layouter.namespace(|| "Assign public value"),
+                None,
+                self.value,
+                [(instances[0], 0), (instances[1], 1)],
+            )?;
+            Ok(())
+        }
+    }
+
+    fn public_inputs(first: u64, second: u64) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::from(first)], vec![Fp::from(0), Fp::from(second)]]
+    }
+
+    #[test]
+    fn test_public_value_two_instances() {
+        let k = 10;
+        let circuit = PublicDecomposeCircuit::<Fp> {
+            value: 42,
+            _marker: PhantomData,
+        };
+        let prover = MockProver::run(k, &circuit, public_inputs(42, 42)).unwrap();
+        prover.assert_satisfied();
+
+        // Either instance disagreeing with the value fails
+        for (first, second) in [(41, 42), (42, 43)] {
+            let prover = MockProver::run(k, &circuit, public_inputs(first, second)).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // And matching public inputs don't help an out-of-range value
+        let circuit = PublicDecomposeCircuit::<Fp> {
+            value: RANGE as u128,
+            _marker: PhantomData,
+        };
+        let prover =
+            MockProver::run(k, &circuit, public_inputs(RANGE as u64, RANGE as u64)).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    // Range-checks the value to RANGE_BITS bits and exposes it at instance row 1
+    #[derive(Default)]
+    struct PublicBoundedCircuit<F: PrimeField, const RANGE_BITS: usize> {
+        value: u128,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField, const RANGE_BITS: usize> Circuit<F> for PublicBoundedCircuit<F, RANGE_BITS> {
+        type Config = (DecomposeRangeCheckConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (DecomposeRangeCheckConfig::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            config.assign_public_bounded(
+                layouter.namespace(|| "Assign public bounded value"),
+                None,
+                self.value,
+                instance,
+                1,
+                RANGE_BITS,
+            )?;
+            Ok(())
+        }
+    }
+
+    fn run_public_bounded<const RANGE_BITS: usize>(value: u64, public: u64) -> bool {
+        let circuit = PublicBoundedCircuit::<Fp, RANGE_BITS> {
+            value: value as u128,
+            _marker: PhantomData,
+        };
+        MockProver::run(10, &circuit, vec![vec![Fp::from(0), Fp::from(public)]])
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_public_bounded() {
+        // The whole 6-bit range of the plan, and the bottom 3-bit window of it
+        for value in [0, 42, 63] {
+            assert!(run_public_bounded::<6>(value, value), "{}", value);
+        }
+        for value in [0, 5, 7] {
+            assert!(run_public_bounded::<3>(value, value), "{}", value);
+        }
+
+        // The public input disagreeing fails, with the value in range
+        assert!(!run_public_bounded::<6>(42, 41));
+        assert!(!run_public_bounded::<3>(7, 6));
+
+        // And the bound failing does too, with the public input agreeing
+        assert!(!run_public_bounded::<6>(64, 64));
+        for value in [8, 9, 63] {
+            assert!(!run_public_bounded::<3>(value, value), "{}", value);
+        }
+    }
+
+    // Decomposes a 32-bit value into byte-aligned 3 + 3 + 2 bit windows, then exposes bits 8..24 publicly
+    #[derive(Default)]
+    struct RepackCircuit<F: PrimeField> {
+        value: u128,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for RepackCircuit<F> {
+        type Config = (DecomposeRangeCheckConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let plan = DecompositionPlan {
+                window_bits: [3, 3, 2].repeat(4),
+            };
+            (
+                DecomposeRangeCheckConfig::configure_with_plan(meta, plan),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let decomposed = config.assign_decomposed(
+                layouter.namespace(|| "Assign value"),
+                None,
+                self.value,
+            )?;
+            let bits =
+                config.repack(layouter.namespace(|| "Repack"), &decomposed.windows, 8, 16)?;
+            layouter.constrain_instance(bits.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_repack() {
+        let k = 10;
+        let circuit = RepackCircuit::<Fp> {
+            value: 0xdeadbeef,
+            _marker: PhantomData,
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(0xadbe)]]).unwrap();
+        prover.assert_satisfied();
+
+        // Off by one bit in either direction
+        for bits in [0xadbf, 0x56df, 0xdeadbe] {
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(bits)]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    // Decomposes a 32-bit value as in RepackCircuit, optionally with a faulty window, and exposes its digit sum
+    #[derive(Default)]
+    struct DigitSumCircuit<F: PrimeField> {
+        value: u128,
+        fault: Option<DecompositionFault<F>>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for DigitSumCircuit<F> {
+        type Config = (DecomposeRangeCheckConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            RepackCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let windows = match self.fault {
+                Some(fault) => config.assign_value_with_fault(
+                    layouter.namespace(|| "Assign value"),
+                    None,
+                    self.value,
+                    fault,
+                )?,
+                None => {
+                    config
+                        .assign_decomposed(layouter.namespace(|| "Assign value"), None, self.value)?
+                        .windows
+                }
+            };
+            let sum = config.digit_sum(layouter.namespace(|| "Digit sum"), &windows)?;
+            layouter.constrain_instance(sum.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_digit_sum() {
+        let k = 10;
+        let value: u128 = 0xdeadbeef;
+        let plan = DecompositionPlan {
+            window_bits: [3, 3, 2].repeat(4),
+        };
+        let host_sum: u64 = plan
+            .window_bits
+            .iter()
+            .zip(plan.shifts())
+            .map(|(bits, shift)| ((value >> shift) & ((1 << bits) - 1)) as u64)
+            .sum();
+        assert_eq!(host_sum, 54);
+
+        let circuit = DigitSumCircuit::<Fp> { value, fault: None };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(host_sum)]]).unwrap();
+        prover.assert_satisfied();
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(host_sum + 1)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // The low window of 0xdeadbeef is 7. Tampering with it to 6 lowers the digit sum by one, so against
+        // that sum the only failure left is the decompose gate itself.
+        let circuit = DigitSumCircuit {
+            value,
+            fault: Some(DecompositionFault::Window(0, Fp::from(6))),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(host_sum - 1)]]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert!(
+            matches!(
+                &failures[..],
+                [VerifyFailure::ConstraintNotSatisfied { constraint, .. }]
+                    if *constraint == ((0, "decompose").into(), 0, "range check").into()
+            ),
+            "{:?}",
+            failures
+        );
+    }
+
+    #[derive(Default)]
+    struct StrictDecomposeCircuit<F: PrimeField> {
+        value: u128,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for StrictDecomposeCircuit<F> {
+        type Config = DecomposeRangeCheckConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            DecomposeRangeCheckConfig::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            config.assign_value_strict(layouter.namespace(|| "Assign value"), None, self.value)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assign_value_strict() {
+        let k = 10;
+        for value in [0, RANGE as u128 - 1] {
+            let circuit = StrictDecomposeCircuit::<Fp> {
+                value,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // Valid field elements, but outside the declared 0..RANGE, are rejected before proving
+        for value in [RANGE as u128, 1 << 100, u128::MAX] {
+            let circuit = StrictDecomposeCircuit::<Fp> {
+                value,
+                _marker: PhantomData,
+            };
+            assert!(MockProver::run(k, &circuit, vec![]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_u128_field_edges() {
+        // Pasta's 255-bit field holds every u128, so the field image is the integer itself right up to the top
+        for value in [0, RANGE as u128, u64::MAX as u128 + 1, 1 << 127, u128::MAX] {
+            let field = u128_to_field::<Fp>(value).unwrap();
+            assert_eq!(field_to_u128(field), value);
+            assert_eq!(field, Fp::from_u128(value));
+        }
+
+        // u128::MAX is assigned as itself, and its windows still reconstruct it, with all the bits above the
+        // planned 8 in the top window. So it's rejected at verification by that window's lookup, rather than
+        // wrapping into range.
+        let plan = plan_decomposition(PLANNED_RANGE_BITS, NUM_BITS);
+        let windows = decompose(Fp::from_u128(u128::MAX), &plan);
+        assert_eq!(reconstruct(&windows, &plan), Fp::from_u128(u128::MAX));
+        assert_eq!(windows[2], Fp::from_u128(u128::MAX >> 6));
+        for value in [u128::MAX, u128::MAX - 255, 1 << 64, 1 << PLANNED_RANGE_BITS] {
+            let circuit = PlannedDecomposeCircuit::<Fp> {
+                value,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err(), "{:#x}", value);
+        }
+        // And likewise for the default 0..RANGE
+        for value in [u128::MAX, RANGE as u128] {
+            let circuit = DecomposeRangeCheckCircuit::<Fp>::builder()
+                .value(value)
+                .build();
+            let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err(), "{:#x}", value);
+        }
+    }
+
+    #[derive(Default)]
+    struct FaultyDecomposeCircuit<F: PrimeField> {
+        value: u128,
+        fault: Option<DecompositionFault<F>>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for FaultyDecomposeCircuit<F> {
+        type Config = DecomposeRangeCheckConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            DecomposeRangeCheckConfig::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let fault = self.fault.expect("a fault to inject");
+            config.assign_value_with_fault(
+                layouter.namespace(|| "Assign value"),
+                None,
+                self.value,
+                fault,
+            )?;
+            Ok(())
+        }
+    }
+
+    // Asserts the only failure is the decompose gate, on the first row of the value's region
+    // (region 0 is the table)
+    fn assert_decompose_gate_fails(fault: DecompositionFault<Fp>) {
+        let circuit = FaultyDecomposeCircuit {
+            value: 42,
+            fault: Some(fault),
+        };
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1, "{:?}", failures);
+        match &failures[0] {
+            VerifyFailure::ConstraintNotSatisfied {
+                constraint,
+                location,
+                ..
+            } => {
+                assert_eq!(
+                    constraint,
+                    &((0, "decompose").into(), 0, "range check").into()
+                );
+                assert_eq!(
+                    location,
+                    &FailureLocation::InRegion {
+                        region: (1, "Assign value").into(),
+                        offset: 0,
+                    }
+                );
+            }
+            failure => panic!("Unexpected failure {:?}", failure),
+        }
+    }
+
+    #[test]
+    fn test_faulty_decomposition_window() {
+        // 42 = 2 + 5 * 8, so 3 is still a valid window but doesn't reconstruct 42
+        assert_decompose_gate_fails(DecompositionFault::Window(0, Fp::from(3)));
+        assert_decompose_gate_fails(DecompositionFault::Window(1, Fp::from(0)));
+    }
+
+    #[test]
+    fn test_faulty_decomposition_value() {
+        assert_decompose_gate_fails(DecompositionFault::Value(Fp::from(43)));
+    }
+
+    // $ cargo test --release --all-features print_range_check_1
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn print_range_check_1() {
+        use plotters::prelude::*;
+
+        let root = BitMapBackend::new("range-check-decomposed-layout.png", (1024, 3096))
+            .into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root
+            .titled("Range Check 1 Layout", ("sans-serif", 60))
             .unwrap();
 
         let circuit = DecomposeRangeCheckCircuit::<Fp> {
@@ -276,4 +2097,910 @@ mod tests {
             .render(3, &circuit, &root)
             .unwrap();
     }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "host decomposition")]
+    fn test_debug_assert_reconstructs() {
+        let plan = plan_decomposition(6, NUM_BITS);
+        let value = 0b101_110u128;
+        debug_assert_reconstructs(
+            Fp::from_u128(value),
+            &decompose(Fp::from_u128(value), &plan),
+            &plan,
+        );
+
+        // The original assignment reduced and shifted window i by i * NUM_BITS rather than taking
+        // NUM_BITS at a time, giving [0, 6] here instead of [6, 5]
+        let mut remaining = value;
+        let buggy: Vec<Fp> = (0..plan.num_windows())
+            .map(|i| {
+                let window = remaining % (1 << (i * NUM_BITS));
+                remaining >>= i * NUM_BITS;
+                Fp::from_u128(window)
+            })
+            .collect();
+        debug_assert_reconstructs(Fp::from_u128(value), &buggy, &plan);
+    }
+
+    // A ~250-bit decomposition into 83 3-bit windows and a 1-bit top window
+    const WIDE_RANGE_BITS: usize = 250;
+
+    #[derive(Default)]
+    struct WideDecomposeCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for WideDecomposeCircuit<F> {
+        type Config = DecomposeRangeCheckConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let plan = plan_decomposition(WIDE_RANGE_BITS, NUM_BITS);
+            DecomposeRangeCheckConfig::configure_with_plan(meta, plan)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            config.assign_field_value(layouter.namespace(|| "Assign value"), None, self.value)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_field_sized_decomposition() {
+        let wide = Fp::from_u128(u128::MAX) * Fp::from_u128(u128::MAX >> 8) + Fp::from(12345);
+        let top = pow2::<Fp>(WIDE_RANGE_BITS - 1);
+        for value in [Fp::ZERO, wide, top, pow2::<Fp>(WIDE_RANGE_BITS) - Fp::ONE] {
+            let circuit = WideDecomposeCircuit {
+                value: Value::known(value),
+            };
+            run_mock_auto(&circuit, vec![]).assert_satisfied();
+        }
+
+        // One bit too wide, and a field-negative value
+        for value in [pow2::<Fp>(WIDE_RANGE_BITS), -Fp::ONE] {
+            let circuit = WideDecomposeCircuit {
+                value: Value::known(value),
+            };
+            assert!(run_mock_auto(&circuit, vec![]).verify().is_err());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "A 255-bit value doesn't fit in the field's 254-bit capacity")]
+    fn test_decomposition_past_capacity() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        DecomposeRangeCheckConfig::configure_with_plan(
+            &mut meta,
+            plan_decomposition(255, NUM_BITS),
+        );
+    }
+
+    #[test]
+    fn test_validate_plan() {
+        assert_eq!(plan_decomposition(64, NUM_BITS).validate::<Fp>(), Ok(()));
+        assert_eq!(plan_decomposition(254, NUM_BITS).validate::<Fp>(), Ok(()));
+        let reason = |plan: DecompositionPlan| plan.validate::<Fp>().unwrap_err().to_string();
+        assert_eq!(reason(plan_decomposition(0, NUM_BITS)), "Empty value!");
+        assert_eq!(
+            reason(plan_decomposition(8, 4)),
+            "Windows can't be wider than the 3-bit lookup table"
+        );
+        assert_eq!(
+            reason(plan_decomposition(255, NUM_BITS)),
+            "A 255-bit value doesn't fit in the field's 254-bit capacity"
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let build = |range_bits, window_bits| {
+            let mut meta = ConstraintSystem::<Fp>::default();
+            DecomposeRangeCheckConfig::<Fp>::builder()
+                .range_bits(range_bits)
+                .window_bits(window_bits)
+                .build(&mut meta)
+                .map(|config| config.plan)
+        };
+        // Whole windows, a narrower top window for the remainder, and the widest range the field allows
+        assert_eq!(build(6, 3), Ok(plan_decomposition(6, 3)));
+        assert_eq!(build(64, 3).unwrap().window_bits.last(), Some(&1));
+        assert_eq!(build(8, 1).unwrap().num_windows(), 8);
+        assert_eq!(build(254, 3).unwrap().range_bits(), 254);
+        // The defaults are configure's
+        let mut meta = ConstraintSystem::<Fp>::default();
+        assert_eq!(
+            DecomposeRangeCheckConfig::<Fp>::builder()
+                .build(&mut meta)
+                .unwrap()
+                .plan,
+            DecomposeRangeCheckConfig::configure(&mut meta).plan
+        );
+
+        assert_eq!(build(0, 3), Err(RangeCheckError::EmptyRange));
+        assert_eq!(build(8, 0), Err(RangeCheckError::EmptyWindow));
+        assert_eq!(
+            build(8, 4),
+            Err(RangeCheckError::WindowTooWide {
+                window_bits: 4,
+                table_bits: 3
+            })
+        );
+        assert_eq!(
+            build(255, 3),
+            Err(RangeCheckError::ExceedsCapacity {
+                range_bits: 255,
+                capacity: 254
+            })
+        );
+    }
+
+    #[test]
+    fn test_builder_rejects_before_configuring() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let result = DecomposeRangeCheckConfig::<Fp>::builder()
+            .window_bits(4)
+            .build(&mut meta);
+        assert!(result.is_err());
+        assert_eq!(meta.num_advice_columns(), 0);
+    }
+
+    // Each invalid plan is rejected by configure itself, before any gate is created
+    #[test]
+    fn test_invalid_plan_rejected_before_gates() {
+        for plan in [
+            plan_decomposition(0, NUM_BITS),
+            plan_decomposition(8, 4),
+            plan_decomposition(255, NUM_BITS),
+        ] {
+            let reason = plan.validate::<Fp>().unwrap_err().to_string();
+            let result = std::panic::catch_unwind(|| {
+                let mut meta = ConstraintSystem::<Fp>::default();
+                DecomposeRangeCheckConfig::configure_with_plan(&mut meta, plan.clone());
+            });
+            let payload = result.expect_err("an invalid plan should be rejected");
+            assert_eq!(payload.downcast_ref::<String>(), Some(&reason));
+        }
+    }
+
+    #[test]
+    fn test_try_configure() {
+        for (plan, expected) in [
+            (plan_decomposition(0, NUM_BITS), RangeCheckError::EmptyRange),
+            (
+                plan_decomposition(8, 4),
+                RangeCheckError::WindowTooWide {
+                    window_bits: 4,
+                    table_bits: 3,
+                },
+            ),
+            (
+                plan_decomposition(255, NUM_BITS),
+                RangeCheckError::ExceedsCapacity {
+                    range_bits: 255,
+                    capacity: 254,
+                },
+            ),
+        ] {
+            let mut meta = ConstraintSystem::<Fp>::default();
+            assert_eq!(
+                DecomposeRangeCheckConfig::try_configure_with_plan(&mut meta, plan.clone())
+                    .unwrap_err(),
+                expected
+            );
+            assert_eq!(
+                DecomposeRangeCheckConfig::try_configure_wide(&mut meta, plan, 2).unwrap_err(),
+                expected
+            );
+            // Neither configured the table nor any columns
+            assert_eq!(meta.num_advice_columns(), 0);
+            assert_eq!(meta.num_fixed_columns(), 0);
+        }
+
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let config = DecomposeRangeCheckConfig::try_configure_with_plan(
+            &mut meta,
+            plan_decomposition(8, NUM_BITS),
+        )
+        .unwrap();
+        assert_eq!(config.range_bits(), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty value!")]
+    fn test_decomposition_empty() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        DecomposeRangeCheckConfig::configure_with_plan(&mut meta, plan_decomposition(0, NUM_BITS));
+    }
+
+    #[test]
+    fn test_circuit_builder() {
+        let k = 10;
+        let circuit = DecomposeRangeCheckCircuit::<Fp>::builder()
+            .value(42)
+            .build();
+        assert_eq!(circuit.value, 42);
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = DecomposeRangeCheckCircuit::<Fp>::builder()
+            .value(RANGE as u128)
+            .build();
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_circuit_known_unknown() {
+        use halo2_proofs::{pasta::EqAffine, plonk::keygen_vk, poly::commitment::Params};
+
+        let k = 10;
+        let prover =
+            MockProver::run(k, &DecomposeRangeCheckCircuit::<Fp>::known(42), vec![]).unwrap();
+        prover.assert_satisfied();
+        let prover = MockProver::run(
+            k,
+            &DecomposeRangeCheckCircuit::<Fp>::known(RANGE as u64),
+            vec![],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+
+        // Keygen goes through on unknown(), and the key doesn't depend on the witness
+        let params: Params<EqAffine> = Params::new(k);
+        let vk = keygen_vk(&params, &DecomposeRangeCheckCircuit::<Fp, true>::unknown()).unwrap();
+        let vk_known =
+            keygen_vk(&params, &DecomposeRangeCheckCircuit::<Fp, true>::known(42)).unwrap();
+        assert_eq!(
+            format!("{:?}", vk.pinned()),
+            format!("{:?}", vk_known.pinned())
+        );
+    }
+
+    #[test]
+    fn test_public_circuit() {
+        let k = 10;
+        let circuit = |value| {
+            DecomposeRangeCheckCircuit::<Fp, true>::builder()
+                .value(value)
+                .build()
+        };
+        let prover = MockProver::run(k, &circuit(42), vec![vec![Fp::from(42)]]).unwrap();
+        prover.assert_satisfied();
+
+        // A different public number
+        let prover = MockProver::run(k, &circuit(42), vec![vec![Fp::from(41)]]).unwrap();
+        assert!(matches!(
+            &prover.verify().unwrap_err()[..],
+            [VerifyFailure::Permutation { .. }, ..]
+        ));
+
+        // 64 = 0 + 8 * 8 reconstructs to the matching public number, but its top window isn't in the table
+        let prover = MockProver::run(
+            k,
+            &circuit(RANGE as u128),
+            vec![vec![Fp::from(RANGE as u64)]],
+        )
+        .unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert!(
+            failures
+                .iter()
+                .all(|failure| matches!(failure, VerifyFailure::Lookup { .. })),
+            "{:?}",
+            failures
+        );
+    }
+
+    #[test]
+    fn test_decompose_witness_input() {
+        let values = |values: &[u64]| values.iter().map(|v| Fp::from(*v)).collect::<Vec<_>>();
+        assert_witness_range::<DecomposeRangeCheckCircuit<Fp>>(
+            &values(&[0, 42, RANGE as u64 - 1]),
+            &[
+                values(&[RANGE as u64, 1000]),
+                vec![pow2::<Fp>(128) + Fp::from(5)],
+            ]
+            .concat(),
+        );
+    }
+
+    #[test]
+    fn test_expected_windows() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let config = DecomposeRangeCheckConfig::configure(&mut meta);
+        assert_eq!(config.plan.window_bits, vec![3, 3]);
+
+        for (value, windows) in [
+            (0, [0, 0]),
+            (5, [5, 0]),
+            (8, [0, 1]),
+            (42, [2, 5]),
+            (63, [7, 7]),
+        ] {
+            assert_eq!(config.expected_windows(value), windows);
+        }
+        // An out-of-range value spills into the top window
+        assert_eq!(config.expected_windows(64), [0, 8]);
+
+        for value in 0..RANGE as u128 {
+            let windows = config.expected_windows(value);
+            let reconstructed: u128 = windows
+                .iter()
+                .zip(config.plan.shifts())
+                .map(|(window, shift)| (*window as u128) << shift)
+                .sum();
+            assert_eq!(reconstructed, value);
+            let field_windows: Vec<Fp> = windows.iter().map(|w| Fp::from(*w)).collect();
+            assert_eq!(field_windows, decompose(Fp::from_u128(value), &config.plan));
+        }
+    }
+
+    // Range-checks each value, and checks the cells assigned in the circuit against the value and expected_windows
+    #[derive(Default)]
+    struct ExpectedWindowsCircuit {
+        values: Vec<u128>,
+    }
+
+    impl Circuit<Fp> for ExpectedWindowsCircuit {
+        type Config = DecomposeRangeCheckConfig<Fp>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            DecomposeRangeCheckConfig::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            for value in &self.values {
+                let decomposed = config.assign_decomposed(
+                    layouter.namespace(|| format!("value {}", value)),
+                    None,
+                    *value,
+                )?;
+                assert_eq!(cell_to_u128(&decomposed.value), Some(*value));
+                let windows: Option<Vec<u128>> = decomposed
+                    .windows
+                    .iter()
+                    .map(|w| cell_to_u128(&w.0))
+                    .collect();
+                let expected = config.expected_windows(*value);
+                assert_eq!(
+                    windows,
+                    Some(expected.into_iter().map(u128::from).collect())
+                );
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assigned_windows_match_expected() {
+        let circuit = ExpectedWindowsCircuit {
+            values: (0..RANGE as u128).collect(),
+        };
+        run_mock_auto(&circuit, vec![]).assert_satisfied();
+    }
+
+    #[test]
+    fn test_decompose_floor_planners() {
+        // The table isn't a region, so this is a single region under either planner
+        for value in [42, RANGE as u128] {
+            let circuit = || {
+                DecomposeRangeCheckCircuit::<Fp>::builder()
+                    .value(value)
+                    .build()
+            };
+            let (simple_k, v1_k) = compare_planners(circuit, vec![]);
+            assert_eq!(simple_k, v1_k);
+        }
+    }
+
+    // Two decompose chips of different widths looking up windows in one shared table
+    #[derive(Default)]
+    struct SharedTableCircuit<F: PrimeField> {
+        narrow: u128,
+        wide: u128,
+        _marker: PhantomData<F>,
+    }
+
+    fn configure_shared<F: PrimeField>(
+        meta: &mut ConstraintSystem<F>,
+    ) -> (SharedRangeTable<F>, [DecomposeRangeCheckConfig<F>; 2]) {
+        let table = SharedRangeTable::configure(meta);
+        let chips = [6, 9].map(|bits| {
+            DecomposeRangeCheckConfig::configure_with_table(
+                meta,
+                plan_decomposition(bits, NUM_BITS),
+                LookupTransform::default(),
+                &table,
+            )
+        });
+        (table, chips)
+    }
+
+    impl<F: PrimeField> Circuit<F> for SharedTableCircuit<F> {
+        type Config = (SharedRangeTable<F>, [DecomposeRangeCheckConfig<F>; 2]);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            configure_shared(meta)
+        }
+
+        fn synthesize(
+            &self,
+            (table, [narrow, wide]): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            table.load(&mut layouter)?;
+            narrow.assign_value(layouter.namespace(|| "narrow"), None, self.narrow)?;
+            wide.assign_value(layouter.namespace(|| "wide"), None, self.wide)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_shared_range_table() {
+        let circuit = |narrow, wide| SharedTableCircuit::<Fp> {
+            narrow,
+            wide,
+            _marker: PhantomData,
+        };
+        run_mock_auto(&circuit(63, 511), vec![]).assert_satisfied();
+        assert!(run_mock_auto(&circuit(64, 511), vec![]).verify().is_err());
+        assert!(run_mock_auto(&circuit(63, 512), vec![]).verify().is_err());
+
+        // Sharing saves the second chip's two table columns
+        let mut shared = ConstraintSystem::<Fp>::default();
+        configure_shared(&mut shared);
+        let mut separate = ConstraintSystem::<Fp>::default();
+        for bits in [6, 9] {
+            DecomposeRangeCheckConfig::configure_with_plan(
+                &mut separate,
+                plan_decomposition(bits, NUM_BITS),
+            );
+        }
+        assert_eq!(shared.num_fixed_columns() + 2, separate.num_fixed_columns());
+    }
+
+    // 64 bits in 3-bit windows is 22 windows, too many to want in one column
+    const MULTI_COLUMN_RANGE_BITS: usize = 64;
+
+    // Range-checks each of `values` with the windows spread across COLUMNS columns. Setting `fault` replaces
+    // a window of the first value, as in assign_value_with_fault.
+    #[derive(Default)]
+    struct MultiColumnDecomposeCircuit<const COLUMNS: usize> {
+        values: Vec<u128>,
+        fault: Option<(usize, u64)>,
+    }
+
+    impl<const COLUMNS: usize> Circuit<Fp> for MultiColumnDecomposeCircuit<COLUMNS> {
+        type Config = DecomposeRangeCheckConfig<Fp>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![0; self.values.len()],
+                fault: None,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let plan = plan_decomposition(MULTI_COLUMN_RANGE_BITS, NUM_BITS);
+            DecomposeRangeCheckConfig::configure_wide(meta, plan, COLUMNS)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            for (i, value) in self.values.iter().enumerate() {
+                let layouter = layouter.namespace(|| format!("value {}", i));
+                match self.fault.filter(|_| i == 0) {
+                    Some((window, faulty)) => {
+                        let fault = DecompositionFault::Window(window, Fp::from(faulty));
+                        config.assign_value_with_fault(layouter, None, *value, fault)?;
+                    }
+                    None => {
+                        config.assign_value(layouter, None, *value)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn check_wide_layout<const COLUMNS: usize>() {
+        let circuit = |values: Vec<u128>| MultiColumnDecomposeCircuit::<COLUMNS> {
+            values,
+            fault: None,
+        };
+        let values = vec![0, 1, 0xdead_beef_cafe_f00d, u64::MAX as u128];
+        run_mock_auto(&circuit(values.clone()), vec![]).assert_satisfied();
+        for value in [1 << MULTI_COLUMN_RANGE_BITS, u128::MAX] {
+            let prover = run_mock_auto(&circuit(vec![value]), vec![]);
+            assert!(prover.verify().is_err(), "{} columns", COLUMNS);
+        }
+
+        // Each window is a term of the decompose gate wherever it sits, e.g. the first, the last in the first
+        // row, the first in the second row, and the top one
+        let honest = decompose(
+            Fp::from(0xdead_beef),
+            &plan_decomposition(MULTI_COLUMN_RANGE_BITS, NUM_BITS),
+        );
+        for window in [0, COLUMNS - 1, COLUMNS, honest.len() - 1] {
+            let faulty = MultiColumnDecomposeCircuit::<COLUMNS> {
+                values: vec![0xdead_beef],
+                fault: Some((window, field_to_u128(honest[window]) as u64 ^ 1)),
+            };
+            let failures = run_mock_auto(&faulty, vec![]).verify().unwrap_err();
+            assert!(
+                failures.iter().all(|failure| matches!(
+                    failure,
+                    VerifyFailure::ConstraintNotSatisfied { constraint, .. }
+                        if *constraint == ((0, "decompose").into(), 0, "range check").into()
+                )),
+                "{} columns, window {}: {:?}",
+                COLUMNS,
+                window,
+                failures
+            );
+        }
+    }
+
+    #[test]
+    fn test_wide_decomposition() {
+        check_wide_layout::<1>();
+        check_wide_layout::<2>();
+        // 4 and 5 columns leave spare cells in the last row, which are padded with zeros
+        check_wide_layout::<4>();
+        check_wide_layout::<5>();
+    }
+
+    #[test]
+    fn test_wide_decomposition_k() {
+        let values = vec![u64::MAX as u128; 16];
+        let narrow_k = min_k(
+            &MultiColumnDecomposeCircuit::<1> {
+                values: values.clone(),
+                fault: None,
+            },
+            vec![],
+        );
+        let wide_k = min_k(
+            &MultiColumnDecomposeCircuit::<4> {
+                values,
+                fault: None,
+            },
+            vec![],
+        );
+        // 16 values of 64 bits take 352 rows of windows in 1 column and 96 in 4, so two doublings fewer
+        assert_eq!(wide_k + 2, narrow_k);
+    }
+
+    fn check_rows_used<const COLUMNS: usize>(expected: usize) {
+        let mut meta = ConstraintSystem::default();
+        let config = MultiColumnDecomposeCircuit::<COLUMNS>::configure(&mut meta);
+        assert_eq!(config.rows_used(), expected, "{} columns", COLUMNS);
+
+        // Two values in a row, the second starting where the first ends
+        let circuit = MultiColumnDecomposeCircuit::<COLUMNS> {
+            values: vec![1, 2],
+            fault: None,
+        };
+        assert_selector_rows(
+            &circuit,
+            &[],
+            |config| config.q_range_check,
+            0..2 * expected,
+        );
+        assert_selector_rows(&circuit, &[], |config| config.q_decomposed, [0, expected]);
+    }
+
+    // Each gate's selector is enabled on exactly its rows, for one column and for four. 64 bits take 21 full
+    // windows and a 1-bit top window, window 21, which is the only one needing the short lookup.
+    #[test]
+    fn test_selector_rows() {
+        let circuit = MultiColumnDecomposeCircuit::<1> {
+            values: vec![u64::MAX as u128],
+            fault: None,
+        };
+        assert_selector_rows(&circuit, &[], |config| config.q_decomposed, [0]);
+        assert_selector_rows(&circuit, &[], |config| config.q_range_check, 0..22);
+        let q_short =
+            |config: &DecomposeRangeCheckConfig<Fp>| config.q_short_range_check.as_ref().unwrap().0;
+        assert_selector_rows(&circuit, &[], q_short, [21]);
+        for q_other in [
+            |config: &DecomposeRangeCheckConfig<Fp>| config.q_diff,
+            |config: &DecomposeRangeCheckConfig<Fp>| config.q_repack,
+            |config: &DecomposeRangeCheckConfig<Fp>| config.q_repack_start,
+        ] {
+            assert_selector_rows(&circuit, &[], q_other, []);
+        }
+
+        // Across four columns window 21 lands in row 5
+        let circuit = MultiColumnDecomposeCircuit::<4> {
+            values: vec![u64::MAX as u128],
+            fault: None,
+        };
+        assert_selector_rows(&circuit, &[], |config| config.q_decomposed, [0]);
+        assert_selector_rows(&circuit, &[], |config| config.q_range_check, 0..6);
+        assert_selector_rows(&circuit, &[], q_short, [5]);
+    }
+
+    fn check_lookup_rows<const COLUMNS: usize>(num_values: usize) {
+        let mut meta = ConstraintSystem::default();
+        let config = MultiColumnDecomposeCircuit::<COLUMNS>::configure(&mut meta);
+        let lookup_rows = config.lookup_rows(num_values);
+        let circuit = MultiColumnDecomposeCircuit::<COLUMNS> {
+            values: vec![u64::MAX as u128; num_values],
+            fault: None,
+        };
+        // The lookups are enabled on exactly the predicted rows, and nothing else needs more rows, so the
+        // smallest k that fits them with the blinding rows is the one the MockProver needs
+        assert_selector_rows(&circuit, &[], |config| config.q_range_check, 0..lookup_rows);
+        let predicted_k = (lookup_rows + meta.minimum_rows())
+            .next_power_of_two()
+            .ilog2();
+        assert_eq!(min_k(&circuit, vec![]), predicted_k, "{} columns", COLUMNS);
+    }
+
+    #[test]
+    fn test_lookup_rows() {
+        // 22 windows a value, so 16 values take 352 rows in one column and 96 in four
+        check_lookup_rows::<1>(16);
+        check_lookup_rows::<4>(16);
+        // 23 values take 506 rows, which with the blinding rows spill past 2^9
+        check_lookup_rows::<1>(23);
+        check_lookup_rows::<1>(1);
+    }
+
+    #[test]
+    fn test_rows_used() {
+        // 22 windows
+        check_rows_used::<1>(22);
+        check_rows_used::<4>(6);
+        check_rows_used::<5>(5);
+
+        let mut meta = ConstraintSystem::<Fp>::default();
+        assert_eq!(
+            DecomposeRangeCheckConfig::configure(&mut meta).rows_used(),
+            2
+        );
+    }
+
+    // Decomposes 16-bit values into 3-bit windows, with a 1-bit top window: 5 windows of 3 bits, then 1
+    const PREFIX_RANGE_BITS: usize = 16;
+
+    // Decomposes `a` and `b`, and constrains their top `prefix_len` windows to match
+    #[derive(Default)]
+    struct SharedPrefixCircuit<F: PrimeField> {
+        a: u128,
+        b: u128,
+        prefix_len: usize,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for SharedPrefixCircuit<F> {
+        type Config = DecomposeRangeCheckConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                prefix_len: self.prefix_len,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let plan = plan_decomposition(PREFIX_RANGE_BITS, NUM_BITS);
+            DecomposeRangeCheckConfig::configure_with_plan(meta, plan)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            let a = config.assign_decomposed(layouter.namespace(|| "a"), None, self.a)?;
+            let b = config.assign_decomposed(layouter.namespace(|| "b"), None, self.b)?;
+            config.shared_prefix(
+                layouter.namespace(|| "shared prefix"),
+                &a.windows,
+                &b.windows,
+                self.prefix_len,
+            )
+        }
+    }
+
+    #[test]
+    fn test_shared_prefix() {
+        let circuit = |a, b, prefix_len| SharedPrefixCircuit::<Fp> {
+            a,
+            b,
+            prefix_len,
+            _marker: PhantomData,
+        };
+        // 0xf123 and 0xf456 share their top 4 bits, i.e. the 1-bit and the next 3-bit window, and first
+        // differ in window 3
+        let (a, b) = (0xf123, 0xf456);
+        for prefix_len in 0..=2 {
+            run_mock_auto(&circuit(a, b, prefix_len), vec![]).assert_satisfied();
+        }
+        // Any value shares all of its windows with itself
+        run_mock_auto(&circuit(a, a, 6), vec![]).assert_satisfied();
+
+        // A longer prefix catches the first differing window, whichever it is, along with any other differing
+        // window in the prefix
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let config = SharedPrefixCircuit::<Fp>::configure(&mut meta);
+        for (a, b, prefix_len, first_differing) in [
+            (a, b, 3, 3),
+            (a, b, 6, 3),
+            (0xf123, 0x7123, 1, 5),
+            (0x0001, 0x0000, 6, 0),
+        ] {
+            let (a_windows, b_windows) = (config.expected_windows(a), config.expected_windows(b));
+            let differing: BTreeSet<usize> = (6 - prefix_len..6)
+                .filter(|i| a_windows[*i] != b_windows[*i])
+                .collect();
+            assert_eq!(differing.last(), Some(&first_differing));
+
+            let failures = run_mock_auto(&circuit(a, b, prefix_len), vec![])
+                .verify()
+                .unwrap_err();
+            // Region 0 is the table, and regions 1 and 2 hold a's and b's windows, one per row
+            let offsets: BTreeSet<usize> = failures
+                .iter()
+                .map(|failure| match failure {
+                    VerifyFailure::Permutation {
+                        location: FailureLocation::InRegion { region, offset },
+                        ..
+                    } => {
+                        assert!(
+                            [(1, "Assign value").into(), (2, "Assign value").into()]
+                                .contains(region),
+                            "{:?}",
+                            region
+                        );
+                        *offset
+                    }
+                    failure => panic!("Unexpected failure {:?}", failure),
+                })
+                .collect();
+            assert_eq!(offsets, differing, "{:#x} and {:#x}", a, b);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "A prefix of 7 windows is longer than the 6 windows of the plan")]
+    fn test_shared_prefix_too_long() {
+        let circuit = SharedPrefixCircuit::<Fp> {
+            prefix_len: 7,
+            ..SharedPrefixCircuit::default()
+        };
+        let _ = MockProver::run(8, &circuit, vec![]);
+    }
+
+    // Nearly as wide as the field: 83 windows of 3 bits, then a 1-bit top window
+    const STREAMING_RANGE_BITS: usize = 250;
+
+    // Streams the windows of the little-endian `bytes` into the chip, exposing the value at instance row 0.
+    // `tamper` replaces one window, and `num_windows` cuts the stream short.
+    #[derive(Default)]
+    struct StreamingDecomposeCircuit {
+        bytes: [u8; 32],
+        tamper: Option<(usize, u64)>,
+        num_windows: usize,
+    }
+
+    impl Circuit<Fp> for StreamingDecomposeCircuit {
+        type Config = (DecomposeRangeCheckConfig<Fp>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                num_windows: self.num_windows,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let plan = plan_decomposition(STREAMING_RANGE_BITS, NUM_BITS);
+            (
+                DecomposeRangeCheckConfig::configure_with_plan(meta, plan),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            // Reads each window's bits straight out of the bytes, without ever holding the whole value
+            let bytes = &self.bytes;
+            let bit = move |i: usize| ((bytes[i / 8] >> (i % 8)) & 1) as u64;
+            let windows = config
+                .plan
+                .window_bits
+                .clone()
+                .into_iter()
+                .zip(config.plan.shifts())
+                .map(move |(bits, shift)| (0..bits).map(|j| bit(shift + j) << j).sum::<u64>())
+                .enumerate()
+                .map(|(i, window)| match self.tamper {
+                    Some((j, tampered)) if i == j => tampered,
+                    _ => window,
+                })
+                .map(|window| Value::known(Fp::from(window)))
+                .take(self.num_windows);
+            let decomposed =
+                config.assign_windows_streaming(layouter.namespace(|| "stream"), windows)?;
+            layouter.constrain_instance(decomposed.value.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_assign_windows_streaming() {
+        let value = Fp::from(2).pow_vartime([249])
+            + Fp::from(0xdead_beef) * Fp::from(2).pow_vartime([100])
+            + Fp::from(12345);
+        let num_windows = plan_decomposition(STREAMING_RANGE_BITS, NUM_BITS).num_windows();
+        assert_eq!(num_windows, 84);
+        let circuit = StreamingDecomposeCircuit {
+            bytes: value.to_repr(),
+            tamper: None,
+            num_windows,
+        };
+        run_mock_auto(&circuit, vec![vec![value]]).assert_satisfied();
+        assert!(run_mock_auto(&circuit, vec![vec![value + Fp::ONE]])
+            .verify()
+            .is_err());
+
+        // Window 1 of 12345 is 7, and 8 in its place keeps the sum consistent but fails the lookup
+        let tampered = StreamingDecomposeCircuit {
+            tamper: Some((1, 8)),
+            ..circuit
+        };
+        let tampered_value = value + Fp::from(8);
+        assert!(run_mock_auto(&tampered, vec![vec![tampered_value]])
+            .verify()
+            .is_err());
+
+        // A stream one window short of the plan can't be assigned
+        let short = StreamingDecomposeCircuit {
+            tamper: None,
+            num_windows: num_windows - 1,
+            ..tampered
+        };
+        assert!(MockProver::run(10, &short, vec![vec![value]]).is_err());
+    }
 }
@@ -0,0 +1,70 @@
+use halo2_proofs::{dev::MockProver, pasta::Fp, plonk::Error};
+
+use crate::{
+    decompose_range_check::DecomposeRangeCheckCircuit, fib_and_range::FibAndRangeCircuit, fib_lec2,
+    fib_lec2_part2, range_check::RangeCheckCircuit,
+};
+
+// A name, and a run of the circuit it names
+type Example = (&'static str, fn() -> Result<MockProver<Fp>, Error>);
+
+/// Runs each example circuit with an honest witness through the MockProver, printing whether it passed.
+/// Returns whether they all did. `cargo run --example demo` calls this.
+pub fn run_all() -> bool {
+    let examples: [Example; 5] = [
+        ("Fibonacci, 10th term from 1, 1", || {
+            let public_inputs = vec![Fp::from(1), Fp::from(1), Fp::from(55)];
+            MockProver::run(
+                4,
+                &fib_lec2::FibonacciCircuit::known(1, 1),
+                vec![public_inputs],
+            )
+        }),
+        ("Fibonacci in one column, 10th term", || {
+            let public_inputs = vec![Fp::from(1), Fp::from(1), Fp::from(55)];
+            MockProver::run(
+                4,
+                &fib_lec2_part2::FibonacciCircuit::default(),
+                vec![public_inputs],
+            )
+        }),
+        ("Range check, 5 in 0..10", || {
+            MockProver::run(9, &RangeCheckCircuit::<Fp, 10>::known(5), vec![])
+        }),
+        ("Decompose range check, 42 in 0..64", || {
+            MockProver::run(10, &DecomposeRangeCheckCircuit::<Fp>::known(42), vec![])
+        }),
+        ("Fibonacci and range check, 55 in 0..64", || {
+            let circuit = FibAndRangeCircuit::known(1, 1, 10);
+            MockProver::run(6, &circuit, vec![vec![Fp::from(55)]])
+        }),
+    ];
+
+    let mut all_passed = true;
+    for (name, run) in examples {
+        let result = match run() {
+            Ok(prover) => prover
+                .verify()
+                .map_err(|failures| format!("{:?}", failures)),
+            Err(e) => Err(format!("{:?}", e)),
+        };
+        match result {
+            Ok(()) => println!("pass  {}", name),
+            Err(e) => {
+                println!("FAIL  {}: {}", name, e);
+                all_passed = false;
+            }
+        }
+    }
+    all_passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_passes() {
+        assert!(run_all());
+    }
+}
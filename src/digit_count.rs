@@ -0,0 +1,232 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::running_sum_range_check::{RunningSumRangeCheck, RunningSumRangeCheckConfig};
+
+/// Counts the decimal digits of a value below $10^{NUM\_DIGITS}$, writing 0 as the single digit "0". The base
+/// 10 running sum both range-checks the value and gives $z_i = \lfloor value / 10^i \rfloor$, which is nonzero
+/// exactly when the value has a digit at position $i$ or above. So the highest nonzero digit is at position
+/// $count - 1$ for
+///     $$count = 1 + \sum_{i=1}^{NUM\_DIGITS - 1} [z_i \neq 0]$$
+/// where each $[z_i \neq 0]$ is shown with the inverse of $z_i$, or 0 if there is none.
+#[derive(Debug, Clone)]
+struct DigitCountConfig<F: PrimeField, const NUM_DIGITS: usize> {
+    z: Column<Advice>,
+    inv: Column<Advice>,
+    nonzero: Column<Advice>,
+    count: Column<Advice>,
+    q_init: Selector,
+    q_count: Selector,
+    running_sum: RunningSumRangeCheckConfig<F, 0>,
+}
+
+impl<F: PrimeField, const NUM_DIGITS: usize> DigitCountConfig<F, NUM_DIGITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let z = meta.advice_column();
+        let inv = meta.advice_column();
+        let nonzero = meta.advice_column();
+        let count = meta.advice_column();
+        let q_init = meta.selector();
+        let q_count = meta.selector();
+        let running_sum = RunningSumRangeCheck::configure_base(meta, 10);
+        meta.enable_equality(z);
+        meta.enable_equality(count);
+
+        //      z      |    inv    |  nonzero  |  count  | q_init | q_count
+        //    ------------------------------------------------------------------
+        //             |           |           |    1    |   1    |   0
+        //     z_1     | 1 / z_1   |  z_1 != 0 |   c_1   |   0    |   1
+        //     ...     |    ...    |    ...    |   ...   |   0    |   1
+        //   z_{N-1}   | 1/z_{N-1} |    ...    | c_{N-1} |   0    |   1
+        meta.create_gate("digit count init", |meta| {
+            let q = meta.query_selector(q_init);
+            let count = meta.query_advice(count, Rotation::cur());
+            Constraints::with_selector(q, [("count = 1", count - Expression::Constant(F::ONE))])
+        });
+
+        meta.create_gate("digit count", |meta| {
+            let q = meta.query_selector(q_count);
+            let z = meta.query_advice(z, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let nonzero = meta.query_advice(nonzero, Rotation::cur());
+            let count_below = meta.query_advice(count, Rotation::prev());
+            let count = meta.query_advice(count, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [
+                    ("nonzero = z * inv", nonzero.clone() - z.clone() * inv),
+                    (
+                        "z = 0 or nonzero",
+                        z * (Expression::Constant(F::ONE) - nonzero.clone()),
+                    ),
+                    ("c_i = c_{i-1} + nonzero", count - (count_below + nonzero)),
+                ],
+            )
+        });
+
+        Self {
+            z,
+            inv,
+            nonzero,
+            count,
+            q_init,
+            q_count,
+            running_sum,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DigitCountChip<F: PrimeField, const NUM_DIGITS: usize> {
+    config: DigitCountConfig<F, NUM_DIGITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const NUM_DIGITS: usize> DigitCountChip<F, NUM_DIGITS> {
+    fn construct(config: DigitCountConfig<F, NUM_DIGITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> DigitCountConfig<F, NUM_DIGITS> {
+        DigitCountConfig::configure(meta)
+    }
+
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        RunningSumRangeCheck::construct(self.config.running_sum.clone()).load_table(layouter)
+    }
+
+    /// Returns a cell constrained to the number of decimal digits of `value`, which must be below
+    /// $10^{NUM\_DIGITS}$. Zero has one digit.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let zs = RunningSumRangeCheck::construct(config.running_sum.clone()).assign_z(
+            layouter.namespace(|| "decimal digits"),
+            value,
+            NUM_DIGITS,
+        )?;
+        layouter.assign_region(
+            || "digit count",
+            |mut region| {
+                config.q_init.enable(&mut region, 0)?;
+                let mut count =
+                    region.assign_advice(|| "count", config.count, 0, || Value::known(F::ONE))?;
+                for (i, z) in zs.iter().enumerate().take(NUM_DIGITS).skip(1) {
+                    config.q_count.enable(&mut region, i)?;
+                    let z = z.copy_advice(|| format!("z_{}", i), &mut region, config.z, i)?;
+                    let inv = z.value().map(|z| z.invert().unwrap_or(F::ZERO));
+                    region.assign_advice(|| "inv", config.inv, i, || inv)?;
+                    let nonzero = z.value().map(|z| F::from(!bool::from(z.is_zero()) as u64));
+                    region.assign_advice(|| "nonzero", config.nonzero, i, || nonzero)?;
+                    count = region.assign_advice(
+                        || format!("c_{}", i),
+                        config.count,
+                        i,
+                        || count.value().copied() + nonzero,
+                    )?;
+                }
+                Ok(count)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const NUM_DIGITS: usize = 4;
+
+    // Counts the digits of the private value, exposing the count at instance row 0
+    #[derive(Default)]
+    struct DigitCountCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for DigitCountCircuit<F> {
+        type Config = (
+            DigitCountConfig<F, NUM_DIGITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (DigitCountChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = DigitCountChip::construct(config);
+            chip.load_table(&mut layouter)?;
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let count = chip.assign(layouter.namespace(|| "digit count"), &value)?;
+            layouter.constrain_instance(count.cell(), instance, 0)
+        }
+    }
+
+    fn run(value: u64, count: u64) -> bool {
+        let circuit = DigitCountCircuit {
+            value: Value::known(Fp::from(value)),
+        };
+        run_mock_auto(&circuit, vec![vec![Fp::from(count)]])
+            .verify()
+            .is_ok()
+    }
+
+    // The host reference, with 0 written as "0"
+    fn digit_count(value: u64) -> u64 {
+        value.to_string().len() as u64
+    }
+
+    #[test]
+    fn test_digit_count() {
+        for value in [0, 1, 9, 10, 11, 99, 100, 999, 1000, 1234, 9999] {
+            let count = digit_count(value);
+            assert!(run(value, count), "{}", value);
+            assert!(!run(value, count + 1), "{}", value);
+            assert!(!run(value, count - 1), "{}", value);
+        }
+        assert_eq!([0, 9, 10, 999].map(digit_count), [1, 1, 2, 3],);
+    }
+
+    #[test]
+    fn test_digit_count_out_of_range() {
+        // 10^4 has 5 digits, which is more than the running sum checks, so no count passes
+        for count in 0..=5 {
+            assert!(!run(10_000, count), "{}", count);
+        }
+    }
+}
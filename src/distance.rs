@@ -0,0 +1,225 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+/// Constrains `|a - b| <= d` for `a`, `b` and `d` in `0..2^N_BITS`, by witnessing
+///     $$plus = a - b + d, \quad minus = b - a + d$$
+/// and range-checking both to $N + 1$ bits. Each lies in $(-2^N, 2^{N+1})$, and is non-negative exactly when
+/// one side of the inequality holds; a negative one wraps around the field and fails its range check.
+///
+/// As with the less-than gadget, the inputs themselves are not range-checked here.
+#[derive(Debug, Clone)]
+struct WithinDistanceConfig<F: PrimeField, const N_BITS: usize> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    d: Column<Advice>,
+    plus: Column<Advice>,
+    minus: Column<Advice>,
+    q_distance: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> WithinDistanceConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let d = meta.advice_column();
+        let plus = meta.advice_column();
+        let minus = meta.advice_column();
+        let q_distance = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [a, b, d, plus, minus] {
+            meta.enable_equality(column);
+        }
+
+        //     a    |    b    |    d    |     plus     |    minus     | q_distance
+        //    ---------------------------------------------------------------------
+        //     a    |    b    |    d    |  a - b + d   |  b - a + d   |     1
+        meta.create_gate("within distance", |meta| {
+            let q = meta.query_selector(q_distance);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let d = meta.query_advice(d, Rotation::cur());
+            let plus = meta.query_advice(plus, Rotation::cur());
+            let minus = meta.query_advice(minus, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "plus = a - b + d",
+                        plus - (a.clone() - b.clone() + d.clone()),
+                    ),
+                    ("minus = b - a + d", minus - (b - a + d)),
+                ],
+            )
+        });
+
+        Self {
+            a,
+            b,
+            d,
+            plus,
+            minus,
+            q_distance,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct WithinDistanceChip<F: PrimeField, const N_BITS: usize> {
+    config: WithinDistanceConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> WithinDistanceChip<F, N_BITS> {
+    fn construct(config: WithinDistanceConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> WithinDistanceConfig<F, N_BITS> {
+        WithinDistanceConfig::configure(meta)
+    }
+
+    /// Constrains `|a - b| <= d`, failing verification otherwise.
+    fn within_distance(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        d: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let (plus, minus) = layouter.assign_region(
+            || "within distance",
+            |mut region| {
+                let offset = 0;
+                config.q_distance.enable(&mut region, offset)?;
+                let a = a.copy_advice(|| "a", &mut region, config.a, offset)?;
+                let b = b.copy_advice(|| "b", &mut region, config.b, offset)?;
+                let d = d.copy_advice(|| "d", &mut region, config.d, offset)?;
+                let a_minus_b = a.value().zip(b.value()).map(|(a, b)| *a - *b);
+                let plus = region.assign_advice(
+                    || "plus",
+                    config.plus,
+                    offset,
+                    || a_minus_b.zip(d.value()).map(|(diff, d)| diff + *d),
+                )?;
+                let minus = region.assign_advice(
+                    || "minus",
+                    config.minus,
+                    offset,
+                    || a_minus_b.zip(d.value()).map(|(diff, d)| *d - diff),
+                )?;
+                Ok((plus, minus))
+            },
+        )?;
+
+        config
+            .range
+            .assign(layouter.namespace(|| "range check plus"), &plus, N_BITS + 1)?;
+        config.range.assign(
+            layouter.namespace(|| "range check minus"),
+            &minus,
+            N_BITS + 1,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const N_BITS: usize = 8;
+
+    #[derive(Default)]
+    struct WithinDistanceCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+        d: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for WithinDistanceCircuit<F> {
+        type Config = WithinDistanceConfig<F, N_BITS>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            WithinDistanceChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (a, b, d) = layouter.assign_region(
+                || "Load a, b and d",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", config.a, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", config.b, 0, || self.b)?;
+                    let d = region.assign_advice(|| "d", config.d, 0, || self.d)?;
+                    Ok((a, b, d))
+                },
+            )?;
+            WithinDistanceChip::construct(config).within_distance(
+                layouter.namespace(|| "|a - b| <= d"),
+                &a,
+                &b,
+                &d,
+            )
+        }
+    }
+
+    fn circuit(a: u64, b: u64, d: u64) -> WithinDistanceCircuit<Fp> {
+        WithinDistanceCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            d: Value::known(Fp::from(d)),
+        }
+    }
+
+    #[test]
+    fn test_within_distance() {
+        // Inside and exactly at the distance, both ways round, plus the extremes of N_BITS
+        for (a, b, d) in [
+            (10, 12, 5),
+            (10, 15, 5),
+            (7, 7, 0),
+            (0, 255, 255),
+            (200, 0, 255),
+        ] {
+            run_mock_auto(&circuit(a, b, d), vec![]).assert_satisfied();
+            run_mock_auto(&circuit(b, a, d), vec![]).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_outside_distance() {
+        // Just outside the distance, both ways round
+        for (a, b, d) in [(10, 16, 5), (7, 8, 0), (0, 255, 254)] {
+            assert!(run_mock_auto(&circuit(a, b, d), vec![]).verify().is_err());
+            assert!(run_mock_auto(&circuit(b, a, d), vec![]).verify().is_err());
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{decompose_range_check::field_to_u128, table::RangeTableConfig};
+
+/// Proves `value < 2^num_bits` where `num_bits` is a cell rather than a const generic, so the width can be
+/// witnessed or come from a public input. The range table tags each value in `0..RANGE` with its bit length, so
+/// one lookup of `(value, len)` both range-checks the value and pins `len` to its bit length, and a second lookup
+/// of `num_bits - len` into the value column shows that
+///     $$len \le num\_bits$$
+/// which is exactly $value < 2^{num\_bits}$. `num_bits - len` wrapping around the field isn't in the table.
+///
+/// Only values in `0..RANGE` can pass at all, whatever `num_bits` is.
+#[derive(Debug, Clone)]
+struct DynamicBitsConfig<F: PrimeField, const RANGE: usize> {
+    value: Column<Advice>,
+    len: Column<Advice>,
+    num_bits: Column<Advice>,
+    q_lookup: Selector,
+    table: RangeTableConfig<F, RANGE>,
+}
+
+impl<F: PrimeField, const RANGE: usize> DynamicBitsConfig<F, RANGE> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let len = meta.advice_column();
+        let num_bits = meta.advice_column();
+        let q_lookup = meta.complex_selector();
+        let table = RangeTableConfig::configure(meta);
+        meta.enable_equality(value);
+        meta.enable_equality(num_bits);
+
+        //     value   |   len   | num_bits | q_lookup
+        //    ------------------------------------------
+        //       v     | bits(v) |    n     |    1
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let value = meta.query_advice(value, Rotation::cur());
+            let len = meta.query_advice(len, Rotation::cur());
+            vec![(q.clone() * value, table.value), (q * len, table.num_bits)]
+        });
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let len = meta.query_advice(len, Rotation::cur());
+            let num_bits = meta.query_advice(num_bits, Rotation::cur());
+            vec![(q * (num_bits - len), table.value)]
+        });
+
+        Self {
+            value,
+            len,
+            num_bits,
+            q_lookup,
+            table,
+        }
+    }
+
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.table.load(layouter)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DynamicBitsChip<F: PrimeField, const RANGE: usize> {
+    config: DynamicBitsConfig<F, RANGE>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const RANGE: usize> DynamicBitsChip<F, RANGE> {
+    fn construct(config: DynamicBitsConfig<F, RANGE>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> DynamicBitsConfig<F, RANGE> {
+        DynamicBitsConfig::configure(meta)
+    }
+
+    /// Copies `value` into a new region and constrains it to `0..2^num_bits`, for the number of bits in the cell
+    /// `num_bits`. Returns the copied cell.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let len = value
+            .value()
+            .map(|value| F::from(u128::BITS as u64 - field_to_u128(*value).leading_zeros() as u64));
+        layouter.assign_region(
+            || "dynamic bits",
+            |mut region| {
+                config.q_lookup.enable(&mut region, 0)?;
+                region.assign_advice(|| "len", config.len, 0, || len)?;
+                num_bits.copy_advice(|| "num_bits", &mut region, config.num_bits, 0)?;
+                value.copy_advice(|| "value", &mut region, config.value, 0)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::VerifyFailure,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::{
+        corrupt::Corrupted,
+        testing::{run, run_mock_auto},
+    };
+
+    const RANGE: usize = 256;
+
+    // Range-checks the private value to the number of bits at instance row 0.
+    #[derive(Default)]
+    struct DynamicBitsCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for DynamicBitsCircuit<F> {
+        type Config = (
+            DynamicBitsConfig<F, RANGE>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (DynamicBitsChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            let (value, num_bits) = layouter.assign_region(
+                || "Load value and num_bits",
+                |mut region| {
+                    let value = region.assign_advice(|| "value", input, 0, || self.value)?;
+                    let num_bits =
+                        region.assign_advice_from_instance(|| "num_bits", instance, 0, input, 1)?;
+                    Ok((value, num_bits))
+                },
+            )?;
+            DynamicBitsChip::construct(config).assign(
+                layouter.namespace(|| "value < 2^num_bits"),
+                &value,
+                &num_bits,
+            )?;
+            Ok(())
+        }
+    }
+
+    fn circuit(value: Fp) -> DynamicBitsCircuit<Fp> {
+        DynamicBitsCircuit {
+            value: Value::known(value),
+        }
+    }
+
+    fn bits(value: Fp, num_bits: u64) -> bool {
+        run(&circuit(value), &[Fp::from(num_bits)])
+    }
+
+    #[test]
+    fn test_dynamic_bits() {
+        for (value, num_bits) in [(0, 0), (0, 3), (1, 1), (5, 3), (5, 8), (200, 8), (255, 8)] {
+            assert!(bits(Fp::from(value), num_bits), "{} {}", value, num_bits);
+        }
+        for (value, num_bits) in [(1, 0), (5, 2), (200, 7)] {
+            assert!(!bits(Fp::from(value), num_bits), "{} {}", value, num_bits);
+        }
+    }
+
+    #[test]
+    fn test_dynamic_bits_boundary() {
+        // 2^n - 1 is the largest value that fits in n bits, and 2^n the smallest that doesn't
+        for num_bits in 0..8 {
+            let max = (1u64 << num_bits) - 1;
+            assert!(bits(Fp::from(max), num_bits), "2^{} - 1", num_bits);
+            assert!(!bits(Fp::from(max + 1), num_bits), "2^{}", num_bits);
+        }
+    }
+
+    #[test]
+    fn test_dynamic_bits_out_of_table() {
+        // Values outside the table fail for any width, and so do negative ones
+        assert!(!bits(Fp::from(RANGE as u64), 9));
+        assert!(!bits(-Fp::from(1), 8));
+    }
+
+    #[test]
+    fn test_dynamic_bits_forged_len() {
+        // The len cell sits in advice column 2, beside the loaded inputs in column 0. Claiming 8 takes 3 bits
+        // rather than 4, or 0 takes -1 rather than 0, fails the tagged lookup, and only that: len isn't copied
+        // anywhere, and num_bits - len is still in the table.
+        for (value, num_bits) in [(8, 3), (0, 0)] {
+            let forged = Corrupted::<_, 2, 0, -1>::new(circuit(Fp::from(value)));
+            let prover = run_mock_auto(&forged, vec![vec![Fp::from(num_bits)]]);
+            match &prover.verify().unwrap_err()[..] {
+                [VerifyFailure::Lookup {
+                    lookup_index: 0, ..
+                }] => {}
+                failures => panic!("Unexpected failures {:?}", failures),
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use ff::Field;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Expression, Selector};
+
+/// Describes what `C::configure` produced as JSON: the number of columns of each kind, the selectors, and every
+/// gate and lookup argument, each with the degree of its expressions and the cells they query.
+/// This is meant for reading what a chip sets up without drawing the layout image, or for feeding to other tools.
+pub(crate) fn circuit_json<F: Field, C: Circuit<F>>() -> String {
+    let mut meta = ConstraintSystem::default();
+    C::configure(&mut meta);
+    constraint_system_json(&meta)
+}
+
+/// Like circuit_json, but for a ConstraintSystem that's already been configured.
+///
+/// ```text
+/// {
+///   "columns": {"advice": 2, "fixed": 0, "instance": 0},
+///   "selectors": 1,
+///   "degree": 3,
+///   "gates": [{"name": "...", "constraints": [{"name": "...", "degree": 2, "queries": [...]}]}],
+///   "lookups": [{"inputs": [{"degree": 2, "queries": [...]}], "tables": [...]}]
+/// }
+/// ```
+///
+/// A query is `{"column": "advice", "index": 0, "rotation": 1}`, or `{"column": "selector", "index": 0}`. This
+/// version of halo2 doesn't expose a selector's index, so selectors are numbered in the order they're first
+/// queried.
+pub(crate) fn constraint_system_json<F: Field>(meta: &ConstraintSystem<F>) -> String {
+    let mut selectors = vec![];
+    let gates: Vec<String> = meta
+        .gates()
+        .iter()
+        .map(|gate| {
+            let constraints: Vec<String> = gate
+                .polynomials()
+                .iter()
+                .enumerate()
+                .map(|(i, polynomial)| {
+                    format!(
+                        r#"{{"name": {}, {}}}"#,
+                        string(gate.constraint_name(i)),
+                        expression(polynomial, &mut selectors)
+                    )
+                })
+                .collect();
+            format!(
+                r#"{{"name": {}, "constraints": {}}}"#,
+                string(gate.name()),
+                array(constraints)
+            )
+        })
+        .collect();
+    let lookups: Vec<String> = meta
+        .lookups()
+        .iter()
+        .map(|lookup| {
+            let mut expressions = |expressions: &[Expression<F>]| {
+                array(
+                    expressions
+                        .iter()
+                        .map(|e| format!("{{{}}}", expression(e, &mut selectors)))
+                        .collect(),
+                )
+            };
+            let inputs = expressions(lookup.input_expressions());
+            let tables = expressions(lookup.table_expressions());
+            format!(r#"{{"inputs": {}, "tables": {}}}"#, inputs, tables)
+        })
+        .collect();
+    format!(
+        r#"{{"columns": {{"advice": {}, "fixed": {}, "instance": {}}}, "selectors": {}, "degree": {}, "gates": {}, "lookups": {}}}"#,
+        meta.num_advice_columns(),
+        meta.num_fixed_columns(),
+        meta.num_instance_columns(),
+        meta.num_selectors(),
+        meta.degree(),
+        array(gates),
+        array(lookups)
+    )
+}
+
+// The "degree" and "queries" fields of an expression, with each distinct cell it queries listed once
+fn expression<F: Field>(expression: &Expression<F>, selectors: &mut Vec<Selector>) -> String {
+    let selector_index = |selector: Selector, selectors: &mut Vec<Selector>| {
+        selectors
+            .iter()
+            .position(|s| *s == selector)
+            .unwrap_or_else(|| {
+                selectors.push(selector);
+                selectors.len() - 1
+            })
+    };
+    let queried = expression.evaluate(
+        &|_| vec![],
+        &|selector| vec![Query::Selector(selector)],
+        &|query| {
+            vec![Query::Cell(
+                "fixed",
+                query.column_index(),
+                query.rotation().0,
+            )]
+        },
+        &|query| {
+            vec![Query::Cell(
+                "advice",
+                query.column_index(),
+                query.rotation().0,
+            )]
+        },
+        &|query| {
+            vec![Query::Cell(
+                "instance",
+                query.column_index(),
+                query.rotation().0,
+            )]
+        },
+        &|a| a,
+        &|mut a, b| {
+            a.extend(b);
+            a
+        },
+        &|mut a, b| {
+            a.extend(b);
+            a
+        },
+        &|a, _| a,
+    );
+    let mut queries: Vec<String> = vec![];
+    for query in queried {
+        let query = match query {
+            Query::Selector(selector) => format!(
+                r#"{{"column": "selector", "index": {}}}"#,
+                selector_index(selector, selectors)
+            ),
+            Query::Cell(column, index, rotation) => format!(
+                r#"{{"column": {}, "index": {}, "rotation": {}}}"#,
+                string(column),
+                index,
+                rotation
+            ),
+        };
+        if !queries.contains(&query) {
+            queries.push(query);
+        }
+    }
+    format!(
+        r#""degree": {}, "queries": {}"#,
+        expression.degree(),
+        array(queries)
+    )
+}
+
+enum Query {
+    Selector(Selector),
+    // The column kind, its index and the rotation
+    Cell(&'static str, usize, i32),
+}
+
+fn array(items: Vec<String>) -> String {
+    format!("[{}]", items.join(", "))
+}
+
+// A JSON string literal. Gate and constraint names are plain text, so only quotes, backslashes and control
+// characters need escaping.
+fn string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::*;
+    use crate::decompose_range_check::DecomposeRangeCheckConfig;
+
+    // Run with `cargo test --features export test_export_decompose -- --nocapture` to see the JSON
+    #[test]
+    fn test_export_decompose() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        DecomposeRangeCheckConfig::configure(&mut meta);
+        let json = constraint_system_json(&meta);
+        println!("{}", json);
+
+        assert!(json.starts_with(r#"{"columns": {"advice": 2, "fixed": 3, "instance": 0}"#));
+        assert!(json.contains(r#"{"name": "decompose", "constraints": [{"name": "range check", "#));
+        // The default plan's two full-width windows only need the main lookup, of the window column at the
+        // current row, gated by a selector, into the table's fixed column
+        assert!(json.contains(
+            r#""lookups": [{"inputs": [{"degree": 2, "queries": [{"column": "selector", "index": "#
+        ));
+        assert!(json.contains(r#"{"column": "advice", "index": 1, "rotation": 0}"#));
+        assert!(json.contains(
+            r#""tables": [{"degree": 1, "queries": [{"column": "fixed", "index": 0, "rotation": 0}]}]"#
+        ));
+        assert_eq!(json.matches(r#"{"inputs": "#).count(), 1);
+    }
+
+    #[test]
+    fn test_export_escapes_names() {
+        assert_eq!(string(r#"a "b" \ c"#), r#""a \"b\" \\ c""#);
+        assert_eq!(string("line\nbreak"), r#""line\u000abreak""#);
+    }
+}
@@ -0,0 +1,267 @@
+// Putting two chips in one circuit: the Fibonacci chip from fib_lec2 computes a term of the sequence,
+// and the decompose range check from decompose_range_check proves that term is in 0..64.
+// Each chip configures its own columns and gates in the same ConstraintSystem, and the circuit's Config
+// just holds both configs. Cells flow from one chip to the other through copy constraints.
+
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*};
+
+use crate::{
+    decompose_range_check::DecomposeRangeCheckConfig,
+    fib_lec2::{FibonacciChip, FibonacciConfig},
+};
+
+#[derive(Debug, Clone)]
+pub(crate) struct FibAndRangeConfig<F: PrimeField> {
+    fib: FibonacciConfig,
+    range: DecomposeRangeCheckConfig<F>,
+}
+
+// Computes term `n` (counting from 1) of the sequence starting at `a` and `b`, makes it public,
+// and range-checks it. `label`, if given, prefixes the regions of both chips.
+#[derive(Default)]
+pub(crate) struct FibAndRangeCircuit<F: PrimeField> {
+    a: Value<F>,
+    b: Value<F>,
+    n: usize,
+    label: Option<&'static str>,
+}
+
+impl<F: PrimeField> FibAndRangeCircuit<F> {
+    /// The unlabelled circuit for term `n` of the sequence starting at `a` and `b`.
+    pub(crate) fn known(a: u64, b: u64, n: usize) -> Self {
+        Self {
+            a: Value::known(F::from(a)),
+            b: Value::known(F::from(b)),
+            n,
+            label: None,
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for FibAndRangeCircuit<F> {
+    type Config = FibAndRangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The number of terms is part of the circuit's shape, so it's kept for keygen
+    fn without_witnesses(&self) -> Self {
+        Self {
+            n: self.n,
+            label: self.label,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let instance = meta.instance_column();
+        FibAndRangeConfig {
+            fib: FibonacciChip::configure(meta, advice, [instance]),
+            range: DecomposeRangeCheckConfig::configure(meta),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        assert!(self.n >= 3, "The first row already holds terms 1 to 3");
+        // The table is loaded once, however many values get range-checked against it
+        config.range.load_table(&mut layouter)?;
+
+        let fib = FibonacciChip::construct(config.fib);
+        let (_, mut prev_b, mut prev_c) = fib.assign_first_row(
+            layouter.namespace(|| "first row"),
+            self.label,
+            self.a,
+            self.b,
+        )?;
+        for _ in 3..self.n {
+            let c = fib.assign_row(
+                layouter.namespace(|| "next row"),
+                self.label,
+                &prev_b,
+                &prev_c,
+            )?;
+            prev_b = prev_c;
+            prev_c = c;
+        }
+        fib.expose_public(layouter.namespace(|| "out"), &prev_c, 0)?;
+
+        // The term is copied into the range check's value column, so it's the same value in both chips
+        config.range.assign_cell(
+            layouter.namespace(|| "range check term"),
+            self.label,
+            &prev_c.0,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corrupt::Corrupted;
+    use crate::testing::compare_planners;
+    use crate::testing::run_mock_auto;
+    use halo2_proofs::{
+        dev::{metadata, FailureLocation, VerifyFailure},
+        pasta::Fp,
+    };
+
+    fn circuit(n: usize) -> FibAndRangeCircuit<Fp> {
+        FibAndRangeCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+            n,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn test_fib_term_in_range() {
+        // The 10th term, 55, is in 0..64
+        let prover = run_mock_auto(&circuit(10), vec![vec![Fp::from(55)]]);
+        prover.assert_satisfied();
+
+        // The range check doesn't stop a wrong public output being caught
+        let prover = run_mock_auto(&circuit(10), vec![vec![Fp::from(54)]]);
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_fib_term_out_of_range() {
+        // The 11th term, 89, is computed correctly but isn't in 0..64
+        let prover = run_mock_auto(&circuit(11), vec![vec![Fp::from(89)]]);
+        assert!(prover.verify().is_err());
+    }
+
+    // Under SimpleFloorPlanner the range check's region starts at row 0 in advice columns 3 (value) and
+    // 4 (windows), beside the Fibonacci rows in columns 0 to 2. 55 = 7 + 6 * 8, so its windows are 7 and 6.
+    fn decompose_location() -> FailureLocation {
+        FailureLocation::InRegion {
+            region: (9, "Assign cell").into(),
+            offset: 0,
+        }
+    }
+
+    fn assert_decompose_failure(constraint: &metadata::Constraint, location: &FailureLocation) {
+        assert_eq!(
+            *constraint,
+            ((1, "decompose").into(), 0, "range check").into()
+        );
+        assert_eq!(*location, decompose_location());
+    }
+
+    #[test]
+    fn test_corrupted_decompose_window() {
+        // With a window one less, the windows no longer sum to the copied value, which only the
+        // decompose gate catches: the window is still in the table, and nothing else is copied from it
+        let prover = run_mock_auto(
+            &Corrupted::<_, 4, 0, -1>::new(circuit(10)),
+            vec![vec![Fp::from(55)]],
+        );
+        match &prover.verify().unwrap_err()[..] {
+            [VerifyFailure::ConstraintNotSatisfied {
+                constraint,
+                location,
+                ..
+            }] => assert_decompose_failure(constraint, location),
+            failures => panic!("Unexpected failures {:?}", failures),
+        }
+    }
+
+    #[test]
+    fn test_corrupted_decompose_value() {
+        // Changing the copy of the term breaks both its copy constraint and the reconstruction
+        let prover = run_mock_auto(
+            &Corrupted::<_, 3, 0, 1>::new(circuit(10)),
+            vec![vec![Fp::from(55)]],
+        );
+        match &prover.verify().unwrap_err()[..] {
+            [VerifyFailure::ConstraintNotSatisfied {
+                constraint,
+                location,
+                ..
+            }, VerifyFailure::Permutation {
+                column,
+                location: copy_location,
+            }] => {
+                assert_decompose_failure(constraint, location);
+                assert_eq!(*column, (Any::Advice, 3).into());
+                assert_eq!(*copy_location, decompose_location());
+            }
+            failures => panic!("Unexpected failures {:?}", failures),
+        }
+    }
+
+    #[test]
+    fn test_corrupted_fibonacci_term() {
+        // Row 3 holds terms 4, 5 and 6, so bumping its c makes 3 + 5 = 9 and fails the Fibonacci gate
+        let prover = run_mock_auto(
+            &Corrupted::<_, 2, 3, 1>::new(circuit(10)),
+            vec![vec![Fp::from(55)]],
+        );
+        let failures = prover.verify().unwrap_err();
+        match &failures[0] {
+            VerifyFailure::ConstraintNotSatisfied {
+                constraint,
+                location,
+                ..
+            } => {
+                assert_eq!(*constraint, ((0, "Fibonacci").into(), 0, "").into());
+                assert_eq!(
+                    *location,
+                    FailureLocation::InRegion {
+                        region: (4, "next row").into(),
+                        offset: 0,
+                    }
+                );
+            }
+            failure => panic!("Unexpected failure {:?}", failure),
+        }
+        // The next rows copy the honest term, so the bad cell no longer matches them
+        assert!(failures[1..]
+            .iter()
+            .all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+
+    #[test]
+    fn test_fib_and_range_floor_planners() {
+        // The range check's region uses none of the Fibonacci columns, so V1 can place it beside the
+        // Fibonacci rows where SimpleFloorPlanner puts it below them
+        for (n, out) in [(10, 55), (11, 89)] {
+            let (simple_k, v1_k) = compare_planners(|| circuit(n), vec![vec![Fp::from(out)]]);
+            assert!(v1_k <= simple_k);
+        }
+    }
+
+    #[test]
+    fn test_fib_and_range_region_labels() {
+        // 89 fails the range check, which is reported against the labelled range check region, while the
+        // labelled Fibonacci regions all pass
+        let circuit = FibAndRangeCircuit {
+            label: Some("term 11"),
+            ..circuit(11)
+        };
+        let failures = run_mock_auto(&circuit, vec![vec![Fp::from(89)]])
+            .verify()
+            .unwrap_err();
+        let messages: Vec<String> = failures.iter().map(ToString::to_string).collect();
+        assert!(
+            messages
+                .iter()
+                .all(|message| message.contains("'term 11: Assign cell'")),
+            "{:#?}",
+            messages
+        );
+        assert!(messages
+            .iter()
+            .all(|message| !message.contains("next row") && !message.contains("first row")));
+    }
+}
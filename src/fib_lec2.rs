@@ -1,22 +1,23 @@
 // We will add instance columns to our lec1 code to have public inputs
 
-use halo2_proofs::{
-    arithmetic::FieldExt, circuit::*, dev::MockProver, pasta::Fp, plonk::*, poly::Rotation,
-};
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::label::region_name;
 
 #[derive(Clone, Debug)]
-struct ACell<F: FieldExt>(AssignedCell<F, F>);
+pub(crate) struct ACell<F: PrimeField>(pub(crate) AssignedCell<F, F>);
 
 // Defines the configuration of all the columns, and all of the column definitions
 // Will be incrementally populated and passed around
 #[derive(Clone, Debug)]
-struct FibonacciConfig {
+pub(crate) struct FibonacciConfig {
     pub advice: [Column<Advice>; 3],
     pub selector: Selector,
     pub instance: [Column<Instance>; 1],
 }
 
-struct FibonacciChip<F: FieldExt> {
+pub(crate) struct FibonacciChip<F: PrimeField> {
     config: FibonacciConfig,
     _marker: std::marker::PhantomData<F>,
     // In rust, when you have a struct that is generic over a type parameter (here F),
@@ -25,9 +26,9 @@ struct FibonacciChip<F: FieldExt> {
     // so that the compiler can track it.  Otherwise it would give an error. - Jason
 }
 
-impl<F: FieldExt> FibonacciChip<F> {
+impl<F: PrimeField> FibonacciChip<F> {
     // Default constructor
-    fn construct(config: FibonacciConfig) -> Self {
+    pub(crate) fn construct(config: FibonacciConfig) -> Self {
         Self {
             config,
             _marker: std::marker::PhantomData,
@@ -35,7 +36,7 @@ impl<F: FieldExt> FibonacciChip<F> {
     }
 
     // Configure will set what type of columns things are, enable equality, create gates, and return a config with all the gates
-    fn configure(
+    pub(crate) fn configure(
         meta: &mut ConstraintSystem<F>,
         advice: [Column<Advice>; 3],
         instance: [Column<Instance>; 1],
@@ -74,42 +75,30 @@ impl<F: FieldExt> FibonacciChip<F> {
     // These assign functions are to be called by the synthesizer, and will be used to assign values to the columns (the witness)
     // The layouter will collect all the region definitions and compress it horizontally (i.e. squeeze up/down)
     // but not vertically (i.e. will not squeeze left/right, at least right now)
-    fn assign_first_row(
+    // `label`, if given, prefixes the region names, so that a circuit using the chip several times can tell its
+    // uses apart in MockProver failures
+    pub(crate) fn assign_first_row(
         &self,
         mut layouter: impl Layouter<F>,
-        a: Option<F>,
-        b: Option<F>,
+        label: Option<&str>,
+        a: Value<F>,
+        b: Value<F>,
     ) -> Result<(ACell<F>, ACell<F>, ACell<F>), Error> {
         layouter.assign_region(
-            || "first row",
+            || region_name(label, "first row"),
             |mut region| {
                 self.config.selector.enable(&mut region, 0)?;
                 // let a_cell = region.assign_advice_from_instance(|| "1", self.config.instance[0], 0, self.config.advice[0], 0);
                 let a_cell = region
-                    .assign_advice(
-                        || "a",
-                        self.config.advice[0],
-                        0,
-                        || a.ok_or(Error::Synthesis),
-                    )
+                    .assign_advice(|| "a", self.config.advice[0], 0, || a)
                     .map(ACell)?;
                 let b_cell = region
-                    .assign_advice(
-                        || "b",
-                        self.config.advice[1],
-                        0,
-                        || b.ok_or(Error::Synthesis),
-                    )
+                    .assign_advice(|| "b", self.config.advice[1], 0, || b)
                     .map(ACell)?;
-                let c_val = a.and_then(|a| b.map(|b| a + b));
+                let c_val = a.zip(b).map(|(a, b)| a + b);
 
                 let c_cell = region
-                    .assign_advice(
-                        || "c",
-                        self.config.advice[2],
-                        0,
-                        || c_val.ok_or(Error::Synthesis),
-                    )
+                    .assign_advice(|| "c", self.config.advice[2], 0, || c_val)
                     .map(ACell)?;
                 Ok((a_cell, b_cell, c_cell))
             },
@@ -117,14 +106,15 @@ impl<F: FieldExt> FibonacciChip<F> {
     }
 
     // This will be repeatedly called. Note that each time it makes a new region, comprised of a, b, c, s that happen to all be in the same row
-    fn assign_row(
+    pub(crate) fn assign_row(
         &self,
         mut layouter: impl Layouter<F>,
+        label: Option<&str>,
         prev_b: &ACell<F>,
         prev_c: &ACell<F>,
     ) -> Result<ACell<F>, Error> {
         layouter.assign_region(
-            || "next row",
+            || region_name(label, "next row"),
             |mut region| {
                 self.config.selector.enable(&mut region, 0)?;
                 prev_b
@@ -133,25 +123,44 @@ impl<F: FieldExt> FibonacciChip<F> {
                 prev_c
                     .0
                     .copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
-                let c_val = prev_b
-                    .0
-                    .value()
-                    .and_then(|b| prev_c.0.value().map(|c| *b + *c));
+                let c_val = prev_b.0.value().zip(prev_c.0.value()).map(|(b, c)| *b + *c);
                 let c_cell = region
-                    .assign_advice(
-                        || "c",
-                        self.config.advice[2],
-                        0,
-                        || c_val.ok_or(Error::Synthesis),
-                    )
+                    .assign_advice(|| "c", self.config.advice[2], 0, || c_val)
                     .map(ACell);
                 Ok(c_cell)
             },
         )?
     }
 
-    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &ACell<F>, row: usize) {
-        layouter.constrain_instance(cell.0.cell(), self.config.instance[0], row);
+    // Constrains `cell` to equal row `row` of the instance column, and returns that (column, row) slot.
+    // Instance rows are numbered independently of advice rows: `row` is the index into the public inputs
+    // passed to the prover, wherever `cell` happens to sit in the advice columns.
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &ACell<F>,
+        row: usize,
+    ) -> Result<(Column<Instance>, usize), Error> {
+        let instance = self.config.instance[0];
+        layouter.constrain_instance(cell.0.cell(), instance, row)?;
+        Ok((instance, row))
+    }
+
+    // Exposes the two seeds and the output together, each at its own instance row, so the public inputs
+    // are the whole statement "the sequence starting from these seeds reaches this output".
+    pub fn expose_all(
+        &self,
+        mut layouter: impl Layouter<F>,
+        seeds: [&ACell<F>; 2],
+        output: &ACell<F>,
+        rows: &[usize; 3],
+    ) -> Result<Vec<(Column<Instance>, usize)>, Error> {
+        let [a, b] = seeds;
+        [("seed a", a), ("seed b", b), ("output", output)]
+            .into_iter()
+            .zip(rows)
+            .map(|((name, cell), row)| self.expose_public(layouter.namespace(|| name), cell, *row))
+            .collect()
     }
 }
 
@@ -159,14 +168,29 @@ impl<F: FieldExt> FibonacciChip<F> {
 // As long as it's converted to the appropriate field elements in the assign functions called from synthesize, it's fine
 // Recall that circuits can call multiple chips in configure if they'd like!
 #[derive(Default)]
-struct FibonacciCircuit<F: FieldExt> {
-    pub a: Option<F>,
-    pub b: Option<F>,
+pub(crate) struct FibonacciCircuit<F: PrimeField> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+impl<F: PrimeField> FibonacciCircuit<F> {
+    /// The circuit for the sequence starting at `a` and `b`.
+    pub(crate) fn known(a: u64, b: u64) -> Self {
+        Self {
+            a: Value::known(F::from(a)),
+            b: Value::known(F::from(b)),
+        }
+    }
+
+    /// The keygen circuit, with no seeds.
+    fn unknown() -> Self {
+        Self::default()
+    }
 }
 
 // Our circuit will instantiate an instance based on the interface defined on the chip and floorplanner (layouter)
 // There isn't a clear reason this and the chip aren't the same thing, except for better abstractions for complex circuits
-impl<F: FieldExt> Circuit<F> for FibonacciCircuit<F> {
+impl<F: PrimeField> Circuit<F> for FibonacciCircuit<F> {
     type Config = FibonacciConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -196,38 +220,326 @@ impl<F: FieldExt> Circuit<F> for FibonacciCircuit<F> {
         // region: &mut Region<'_, F>,
     ) -> Result<(), Error> {
         let chip = FibonacciChip::construct(config);
-        let (mut prev_a, mut prev_b, mut prev_c) =
-            chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?; // 2 private inputs
+        let (a_cell, b_cell, mut prev_c) =
+            chip.assign_first_row(layouter.namespace(|| "first row"), None, self.a, self.b)?; // 2 private inputs
 
-        // Define the copy constraint from the instance column to our relevant advice cell
-        chip.expose_public(layouter.namespace(|| "private a"), &prev_a, 0);
-        chip.expose_public(layouter.namespace(|| "private b"), &prev_b, 1);
+        let mut prev_b = b_cell.clone();
         for _i in 3..10 {
-            let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            let c_cell =
+                chip.assign_row(layouter.namespace(|| "next row"), None, &prev_b, &prev_c)?;
             prev_b = prev_c;
             prev_c = c_cell;
         }
-        // Define the copy constraint from the instance column to our relevant advice cell
-        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2); // Why is row 2 instead of 10?
+        // Define the copy constraints from the instance column to the seeds and the output.
+        // The output is the 10th term, but it is the 3rd public input, so it goes in instance row 2
+        chip.expose_all(
+            layouter.namespace(|| "public inputs"),
+            [&a_cell, &b_cell],
+            &prev_c,
+            &[0, 1, 2],
+        )?;
         Ok(())
     }
 }
 
-fn main() {
-    let k = 4;
-    let a = Fp::from(1);
-    let b = Fp::from(1);
-    let out = Fp::from(55);
-    let circuit = FibonacciCircuit {
-        a: Some(a),
-        b: Some(b),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        assert_selector_rows, compare_planners, run_many, run_mock_auto, verify_ok,
     };
-    let mut public_inputs = vec![a, b, out];
-    // This prover is faster and 'fake', but is mostly a devtool for debugging
-    let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
-    // This function will pretty-print on errors
-    prover.assert_satisfied();
-    public_inputs[2] += Fp::one();
-    let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
-    // prover.assert_satisfied();
+    use ff::Field;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_fibonacci_public_inputs() {
+        let k = 4;
+        let a = Fp::from(1);
+        let b = Fp::from(1);
+        let out = Fp::from(55);
+        let circuit = FibonacciCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let public_inputs = vec![a, b, out];
+        // This prover is faster and 'fake', but is mostly a devtool for debugging
+        let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
+        // This function will pretty-print on errors
+        prover.assert_satisfied();
+
+        // The seeds and the output are all constrained, so changing any one of them breaks verification
+        for i in 0..public_inputs.len() {
+            let mut public_inputs = public_inputs.clone();
+            public_inputs[i] += Fp::ONE;
+            let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_fibonacci_verify_ok() {
+        let circuit = FibonacciCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        let public_inputs = |out: u64| vec![vec![Fp::from(1), Fp::from(1), Fp::from(out)]];
+        assert!(verify_ok(4, &circuit, public_inputs(55)));
+        assert!(!verify_ok(4, &circuit, public_inputs(56)));
+    }
+
+    #[test]
+    fn test_fibonacci_known_unknown() {
+        use halo2_proofs::{pasta::EqAffine, plonk::keygen_vk, poly::commitment::Params};
+
+        let public_inputs = |a, b, out| vec![vec![Fp::from(a), Fp::from(b), Fp::from(out)]];
+        assert!(verify_ok(
+            4,
+            &FibonacciCircuit::known(1, 1),
+            public_inputs(1, 1, 55)
+        ));
+        // 2, 3, 5, 8, ... reaches 144 at the 10th term
+        assert!(verify_ok(
+            4,
+            &FibonacciCircuit::known(2, 3),
+            public_inputs(2, 3, 144)
+        ));
+        assert!(!verify_ok(
+            4,
+            &FibonacciCircuit::known(2, 3),
+            public_inputs(1, 1, 55)
+        ));
+
+        // Keygen goes through on unknown(), and the key doesn't depend on the seeds
+        let params: Params<EqAffine> = Params::new(4);
+        let vk = keygen_vk(&params, &FibonacciCircuit::unknown()).unwrap();
+        let vk_known = keygen_vk(&params, &FibonacciCircuit::known(1, 1)).unwrap();
+        assert_eq!(
+            format!("{:?}", vk.pinned()),
+            format!("{:?}", vk_known.pinned())
+        );
+    }
+
+    // Run with `cargo test --features trace -- --nocapture` to see the table
+    #[cfg(feature = "trace")]
+    #[test]
+    fn test_fibonacci_trace() {
+        let k = 4;
+        let circuit = FibonacciCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        let public_inputs = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let prover = MockProver::run(k, &circuit, public_inputs.clone()).unwrap();
+        prover.assert_satisfied();
+
+        let trace = crate::trace::dump_assignment(&circuit, &public_inputs).unwrap();
+        println!("{}", trace);
+        // A header, then one row per step: the first row holds the first three terms, and each row after adds one more
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(lines.len(), 1 + 8);
+        let cells = |line: &str| line.split('|').map(str::trim).collect::<Vec<_>>().join(" ");
+        assert_eq!(cells(lines[0]), "row advice_0 advice_1 advice_2 selector_0");
+        assert_eq!(cells(lines[1]), "0 1 1 2 1");
+        assert_eq!(cells(lines[8]), "7 21 34 55 1");
+    }
+
+    // Exposes only the output, as the first and only public input
+    #[derive(Default)]
+    struct FibonacciOutputCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for FibonacciOutputCircuit<F> {
+        type Config = FibonacciConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = FibonacciChip::construct(config.clone());
+            let (_, mut prev_b, mut prev_c) =
+                chip.assign_first_row(layouter.namespace(|| "first row"), None, self.a, self.b)?;
+            for _i in 3..10 {
+                let c_cell =
+                    chip.assign_row(layouter.namespace(|| "next row"), None, &prev_b, &prev_c)?;
+                prev_b = prev_c;
+                prev_c = c_cell;
+            }
+            // The output sits in the last advice row used, but is the first public input
+            let slot = chip.expose_public(layouter.namespace(|| "out"), &prev_c, 0)?;
+            assert_eq!(slot, (config.instance[0], 0));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fibonacci_output_in_instance_row_0() {
+        let k = 4;
+        let circuit = FibonacciOutputCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(55)]]).unwrap();
+        prover.assert_satisfied();
+
+        // The seeds aren't public here, so row 0 can't be mistaken for them
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(1)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_fibonacci_many_seeds() {
+        // The 10th term from each pair of seeds, computed on the host
+        let tenth_term = |a: u64, b: u64| (2..10).fold((a, b), |(a, b), _| (b, a + b)).1;
+        assert_eq!(tenth_term(1, 1), 55);
+
+        // One config and k for all of them, with only the witness and public inputs changing between runs
+        let seeds: Vec<(u64, u64)> = (0..100).map(|i| (i, 3 * i + 1)).collect();
+        let circuits: Vec<_> = seeds
+            .iter()
+            .map(|&(a, b)| {
+                let circuit = FibonacciCircuit {
+                    a: Value::known(Fp::from(a)),
+                    b: Value::known(Fp::from(b)),
+                };
+                let public_inputs = [a, b, tenth_term(a, b)].map(Fp::from).to_vec();
+                (circuit, vec![public_inputs])
+            })
+            .collect();
+        for (result, seeds) in run_many(&circuits).into_iter().zip(&seeds) {
+            assert_eq!(result, Ok(()), "seeds {:?}", seeds);
+        }
+
+        // Each run stands on its own, so one wrong output only fails its own statement
+        let mut circuits = circuits;
+        circuits[42].1[0][2] += Fp::ONE;
+        let failed: Vec<usize> = run_many(&circuits)
+            .iter()
+            .enumerate()
+            .filter_map(|(i, result)| result.is_err().then_some(i))
+            .collect();
+        assert_eq!(failed, vec![42]);
+    }
+
+    #[test]
+    fn test_fibonacci_floor_planners() {
+        let circuit = || FibonacciCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        // Every row uses the same three columns, so neither planner can pack them any tighter
+        for out in [55, 56] {
+            let public_inputs = vec![vec![Fp::from(1), Fp::from(1), Fp::from(out)]];
+            let (simple_k, v1_k) = compare_planners(circuit, public_inputs);
+            assert_eq!(simple_k, v1_k);
+        }
+    }
+
+    #[test]
+    fn test_fibonacci_selector_rows() {
+        let circuit = FibonacciCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        let public_inputs = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        // The first row and each of the 7 rows after it is one a + b = c step, and every one is gated
+        assert_selector_rows(&circuit, &public_inputs, |config| config.selector, 0..8);
+    }
+
+    #[test]
+    #[should_panic(expected = "selector enabled on rows")]
+    fn test_fibonacci_selector_rows_off_by_one() {
+        let circuit = FibonacciCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        let public_inputs = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        // Missing the last step, as an `i < nrows - 1` loop bound would
+        assert_selector_rows(&circuit, &public_inputs, |config| config.selector, 0..7);
+    }
+
+    // Three separate sequences from the same chip, each with its own label, exposing their outputs at
+    // instance rows 0, 1 and 2
+    #[derive(Default)]
+    struct LabelledFibonacciCircuit<F: PrimeField> {
+        seeds: [(Value<F>, Value<F>); 3],
+    }
+
+    impl<F: PrimeField> Circuit<F> for LabelledFibonacciCircuit<F> {
+        type Config = FibonacciConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = FibonacciChip::construct(config);
+            for (i, (a, b)) in self.seeds.iter().enumerate() {
+                let label = format!("fib {}", i);
+                let label = Some(label.as_str());
+                let (_, mut prev_b, mut prev_c) =
+                    chip.assign_first_row(layouter.namespace(|| "first row"), label, *a, *b)?;
+                for _ in 3..10 {
+                    let c_cell = chip.assign_row(
+                        layouter.namespace(|| "next row"),
+                        label,
+                        &prev_b,
+                        &prev_c,
+                    )?;
+                    prev_b = prev_c;
+                    prev_c = c_cell;
+                }
+                chip.expose_public(layouter.namespace(|| "out"), &prev_c, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fibonacci_region_labels() {
+        let circuit = LabelledFibonacciCircuit {
+            seeds: [(1, 1), (2, 3), (0, 1)]
+                .map(|(a, b)| (Value::known(Fp::from(a)), Value::known(Fp::from(b)))),
+        };
+        let outputs = [55, 144, 34].map(Fp::from);
+        run_mock_auto(&circuit, vec![outputs.to_vec()]).assert_satisfied();
+
+        // A wrong output for the second sequence is reported against that sequence's last row
+        let mut public_inputs = outputs.to_vec();
+        public_inputs[1] += Fp::ONE;
+        let failures = run_mock_auto(&circuit, vec![public_inputs])
+            .verify()
+            .unwrap_err();
+        let messages: Vec<String> = failures.iter().map(ToString::to_string).collect();
+        assert!(
+            messages
+                .iter()
+                .any(|message| message.contains("'fib 1: next row'")),
+            "{:#?}",
+            messages
+        );
+        assert!(messages
+            .iter()
+            .all(|message| !message.contains("fib 0") && !message.contains("fib 2")));
+    }
 }
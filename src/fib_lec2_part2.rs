@@ -1,26 +1,21 @@
 // We will build off of the lec2 version to have only one advice column instead
 
-use halo2_proofs::{
-    arithmetic::FieldExt,
-    circuit::*,
-    plonk::*,
-    poly::Rotation,
-    pasta::Fp, dev::MockProver,
-};
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 
 #[derive(Clone, Debug)]
-struct ACell<F: FieldExt>(AssignedCell<F, F>);
+struct ACell<F: PrimeField>(AssignedCell<F, F>);
 
 // Defines the configuration of all the columns, and all of the column definitions
 // Will be incrementally populated and passed around
 #[derive(Clone, Debug)]
-struct FibonacciConfig {
+pub(crate) struct FibonacciConfig {
     pub advice: [Column<Advice>; 1],
     pub selector: Selector,
     pub instance: [Column<Instance>; 1],
 }
 
-struct FibonacciChip<F: FieldExt> {
+struct FibonacciChip<F: PrimeField> {
     config: FibonacciConfig,
     _marker: std::marker::PhantomData<F>,
     // In rust, when you have a struct that is generic over a type parameter (here F),
@@ -29,7 +24,7 @@ struct FibonacciChip<F: FieldExt> {
     // so that the compiler can track it.  Otherwise it would give an error. - Jason
 }
 
-impl<F: FieldExt> FibonacciChip<F> {
+impl<F: PrimeField> FibonacciChip<F> {
     // Default constructor
     fn construct(config: FibonacciConfig) -> Self {
         Self {
@@ -64,7 +59,11 @@ impl<F: FieldExt> FibonacciChip<F> {
             vec![s * (a + b - c)]
         });
 
-        FibonacciConfig { advice: [col_a], selector, instance }
+        FibonacciConfig {
+            advice: [col_a],
+            selector,
+            instance,
+        }
     }
 
     // These assign functions are to be called by the synthesizer, and will be used to assign values to the columns (the witness)
@@ -72,18 +71,51 @@ impl<F: FieldExt> FibonacciChip<F> {
     // but not vertically (i.e. will not squeeze left/right, at least right now)
     fn assign(
         &self,
-        mut layouter: impl Layouter<F>,
+        layouter: impl Layouter<F>,
         // a: Option<F>,
         // b: Option<F>,
         nrows: usize,
-    ) -> Result<(ACell<F>), Error> {
+    ) -> Result<ACell<F>, Error> {
+        self.assign_padded(layouter, nrows, nrows)
+    }
+
+    // The number of rows assign takes up for a table of `nrows` terms, so callers can plan what goes below it.
+    // assign_padded always takes `total_rows`.
+    fn rows_used(nrows: usize) -> usize {
+        nrows
+    }
+
+    // Same as assign, but the region always takes up `total_rows` rows, so that it lines up predictably with
+    // other chips' regions whatever `nrows` is. Rows from `nrows` on are filled with zeros, and have the
+    // selector off, so nothing constrains them.
+    fn assign_padded(
+        &self,
+        mut layouter: impl Layouter<F>,
+        nrows: usize,
+        total_rows: usize,
+    ) -> Result<ACell<F>, Error> {
+        if total_rows < nrows {
+            return Err(Error::Synthesis);
+        }
         layouter.assign_region(
             || "entire table",
             |mut region| {
                 self.config.selector.enable(&mut region, 0)?;
                 self.config.selector.enable(&mut region, 1)?;
-                let a_cell: ACell<F> = ACell::<F>(region.assign_advice_from_instance(|| "a", self.config.instance[0], 0, self.config.advice[0], 0)?);
-                let b_cell: ACell<F> = ACell::<F>(region.assign_advice_from_instance(|| "b", self.config.instance[0], 1, self.config.advice[0], 1)?);
+                let a_cell: ACell<F> = ACell::<F>(region.assign_advice_from_instance(
+                    || "a",
+                    self.config.instance[0],
+                    0,
+                    self.config.advice[0],
+                    0,
+                )?);
+                let b_cell: ACell<F> = ACell::<F>(region.assign_advice_from_instance(
+                    || "b",
+                    self.config.instance[0],
+                    1,
+                    self.config.advice[0],
+                    1,
+                )?);
                 // let a_cell = region.assign_advice(
                 //     || "a",
                 //     self.config.advice[0],
@@ -102,38 +134,51 @@ impl<F: FieldExt> FibonacciChip<F> {
                     if i < nrows - 2 {
                         self.config.selector.enable(&mut region, i)?;
                     }
-                    let c_val = prev_a.0.value().and_then(|a| prev_b.0.value().map(|b| *a + *b));
+                    let c_val = prev_a.0.value().zip(prev_b.0.value()).map(|(a, b)| *a + *b);
 
-                    let c_cell: ACell<F> = region.assign_advice(
-                        || "c",
-                        self.config.advice[0],
-                        i,
-                        || c_val.ok_or(Error::Synthesis),
-                    ).map(ACell)?;
+                    let c_cell: ACell<F> = region
+                        .assign_advice(|| "c", self.config.advice[0], i, || c_val)
+                        .map(ACell)?;
                     prev_a = prev_b;
                     prev_b = c_cell;
                 }
+                for i in nrows..total_rows {
+                    region.assign_advice(
+                        || "padding",
+                        self.config.advice[0],
+                        i,
+                        || Value::known(F::ZERO),
+                    )?;
+                }
                 Ok(prev_b)
             },
         )
     }
 
-    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &ACell<F>, row: usize){
-        layouter.constrain_instance(
-            cell.0.cell(), self.config.instance[0], row
-        );
+    // Constrains `cell` to equal row `row` of the instance column, and returns that (column, row) slot.
+    // Instance rows are numbered independently of advice rows: `row` is the index into the public inputs
+    // passed to the prover, wherever `cell` happens to sit in the advice columns.
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &ACell<F>,
+        row: usize,
+    ) -> Result<(Column<Instance>, usize), Error> {
+        let instance = self.config.instance[0];
+        layouter.constrain_instance(cell.0.cell(), instance, row)?;
+        Ok((instance, row))
     }
 }
 
 #[derive(Default)]
-struct FibonacciCircuit<F: FieldExt> {
-    pub a: Option<F>,
-    pub b: Option<F>,
+pub(crate) struct FibonacciCircuit<F: PrimeField> {
+    pub a: Value<F>,
+    pub b: Value<F>,
 }
 
 // Our circuit will instantiate an instance based on the interface defined on the chip and floorplanner (layouter)
 // There isn't a clear reason this and the chip aren't the same thing, except for better abstractions for complex circuits
-impl<F: FieldExt> Circuit<F> for FibonacciCircuit<F> {
+impl<F: PrimeField> Circuit<F> for FibonacciCircuit<F> {
     type Config = FibonacciConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -159,10 +204,10 @@ impl<F: FieldExt> Circuit<F> for FibonacciCircuit<F> {
         // region: &mut Region<'_, F>,
     ) -> Result<(), Error> {
         let chip = FibonacciChip::construct(config);
-        let mut output = chip.assign(
+        let output = chip.assign(
             layouter.namespace(|| "table"),
             // self.a, self.b,
-            10
+            10,
         )?; // 2 private inputs
 
         // Define the copy constraint from the instance column to our relevant advice cell
@@ -177,43 +222,142 @@ impl<F: FieldExt> Circuit<F> for FibonacciCircuit<F> {
         //     prev_c = c_cell;
         // }
         // Define the copy constraint from the instance column to our relevant advice cell
-        chip.expose_public(layouter.namespace(|| "out"), &output, 2); // Why is row 2 instead of 10?
+        // The output sits in advice row 9, but rows 0 and 1 of the instance column hold the seeds, so it is the
+        // third public input
+        chip.expose_public(layouter.namespace(|| "out"), &output, 2)?;
         Ok(())
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::assert_selector_rows;
+    use ff::Field;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_fibonacci_single_column() {
+        let k = 4;
+        let circuit = FibonacciCircuit::<Fp>::default();
+        let mut public_inputs = vec![Fp::from(1), Fp::from(1), Fp::from(55)];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        public_inputs[2] += Fp::ONE;
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // Each gate covers three rows, so the last gate starts two rows before the end of the table
+        let public_inputs = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        assert_selector_rows(&circuit, &public_inputs, |config| config.selector, 0..8);
+    }
+
+    // A padded table, followed by a second unpadded one in the same column
+    #[derive(Default)]
+    struct PaddedFibonacciCircuit<F: PrimeField> {
+        nrows: usize,
+        total_rows: usize,
+        _marker: std::marker::PhantomData<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for PaddedFibonacciCircuit<F> {
+        type Config = FibonacciConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                nrows: self.nrows,
+                total_rows: self.total_rows,
+                _marker: std::marker::PhantomData,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = FibonacciChip::construct(config);
+            let output = chip.assign_padded(
+                layouter.namespace(|| "padded table"),
+                self.nrows,
+                self.total_rows,
+            )?;
+            chip.expose_public(layouter.namespace(|| "out"), &output, 2)?;
+            chip.assign(layouter.namespace(|| "next table"), 4)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fibonacci_padded() {
+        let k = 5;
+        let public_inputs = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let circuit = PaddedFibonacciCircuit::<Fp> {
+            nrows: 10,
+            total_rows: 16,
+            _marker: std::marker::PhantomData,
+        };
+        // The padding doesn't change the term
+        let prover = MockProver::run(k, &circuit, public_inputs.clone()).unwrap();
+        prover.assert_satisfied();
+
+        // The padding rows 10..16 have the selector off, and the next table starts after them at row 16
+        assert_selector_rows(
+            &circuit,
+            &public_inputs,
+            |config| config.selector,
+            (0..8).chain(16..18),
+        );
+
+        // Padding to fewer rows than the table needs is an error
+        let circuit = PaddedFibonacciCircuit::<Fp> {
+            nrows: 10,
+            total_rows: 9,
+            _marker: std::marker::PhantomData,
+        };
+        assert!(MockProver::run(k, &circuit, public_inputs).is_err());
+    }
+
+    #[test]
+    fn test_fibonacci_rows_used() {
+        // Unpadded, the second table starts right where rows_used says the first one ends
+        let circuit = PaddedFibonacciCircuit::<Fp> {
+            nrows: 10,
+            total_rows: 10,
+            _marker: std::marker::PhantomData,
+        };
+        let public_inputs = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        MockProver::run(5, &circuit, public_inputs.clone())
+            .unwrap()
+            .assert_satisfied();
+        let next = FibonacciChip::<Fp>::rows_used(10);
+        assert_eq!(next, 10);
+        assert_selector_rows(
+            &circuit,
+            &public_inputs,
+            |config| config.selector,
+            (0..8).chain(next..next + 2),
+        );
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
-    fn print(){
-
-    let k = 4;
-    let a = Fp::from(1);
-    let b = Fp::from(1);
-    let out = Fp::from(55);
-    let circuit = FibonacciCircuit {
-        a:Some(a), b:Some(b)
-    };
-    let mut public_inputs = vec![a, b, out];
-    // This prover is faster and 'fake', but is mostly a devtool for debugging
-    let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
-    // This function will pretty-print on errors
-    prover.assert_satisfied();
-    public_inputs[2] += Fp::one();
-    let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
-    // prover.assert_satisfied();
-
-    use plotters::prelude::*;
-
-    let root = BitMapBackend::new("fib-2—layout.png", (1024, 7680)).into_drawing_area();
-    //root.fiti(&WHITE).unwrap();
-    let root1 = root.titled("Fib 2 Layout", ("sans—serif", 60)).unwrap();
-    let circuit:FibonacciCircuit<Fp> = FibonacciCircuit { a: None, b: None };
-    halo2_proofs::dev::CircuitLayout::default()
-        .render(4, &circuit, &root1)
-        .unwrap();
+    fn print() {
+        use plotters::prelude::*;
 
+        let root = BitMapBackend::new("fib-2—layout.png", (1024, 7680)).into_drawing_area();
+        //root.fiti(&WHITE).unwrap();
+        let root1 = root.titled("Fib 2 Layout", ("sans—serif", 60)).unwrap();
+        let circuit = FibonacciCircuit::<Fp>::default();
+        halo2_proofs::dev::CircuitLayout::default()
+            .render(4, &circuit, &root1)
+            .unwrap();
     }
 }
 
@@ -0,0 +1,326 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{ConstraintSystem, Error},
+};
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig,
+    decompose_range_check::{DecomposeRangeCheckConfig, DecomposedValue, DecompositionPlan},
+    less_than::{LessThanChip, LessThanConfig},
+    planner::LayoutPlanner,
+};
+
+/// One operation of a GadgetChain, applied to the output of the one before.
+#[derive(Debug, Clone)]
+enum Step<F: PrimeField> {
+    /// Decomposes the current cell into windows with the decompose chip, which also range-checks it.
+    Decompose,
+    /// Sums the windows of the last decomposition into a cell.
+    DigitSum,
+    /// Range-checks the current cell to the given number of bits with the bits range check.
+    RangeCheck(usize),
+    /// Compares the current cell against the bound in the given cell, handing on the boolean `current < bound`.
+    /// Both must fit in the config's LT_BITS bits, e.g. from a range check step before.
+    LessThan(AssignedCell<F, F>),
+}
+
+/// The chips a GadgetChain's steps run on. Comparisons are over LT_BITS bits.
+#[derive(Debug, Clone)]
+struct GadgetChainConfig<F: PrimeField, const LT_BITS: usize> {
+    decompose: DecomposeRangeCheckConfig<F>,
+    range: BitsRangeCheckConfig<F>,
+    less_than: LessThanConfig<F, LT_BITS>,
+}
+
+impl<F: PrimeField, const LT_BITS: usize> GadgetChainConfig<F, LT_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>, plan: DecompositionPlan) -> Self {
+        Self {
+            decompose: DecomposeRangeCheckConfig::configure_with_plan(meta, plan),
+            range: BitsRangeCheckConfig::configure(meta),
+            less_than: LessThanChip::configure(meta),
+        }
+    }
+
+    /// Loads the decompose chip's lookup table.
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.decompose.load_table(layouter)
+    }
+}
+
+// What the last step handed on: a cell, or a decomposition whose windows a digit sum can use
+enum Link<F: PrimeField> {
+    Cell(AssignedCell<F, F>),
+    Decomposed(DecomposedValue<F>),
+}
+
+impl<F: PrimeField> Link<F> {
+    fn cell(&self) -> &AssignedCell<F, F> {
+        match self {
+            Link::Cell(cell) => cell,
+            Link::Decomposed(decomposed) => &decomposed.value,
+        }
+    }
+}
+
+/// A sequence of steps laid out one after another in a single region, rather than in a region per chip call.
+/// Each step copies the previous step's output in, so the steps are tied together by copy constraints the
+/// same way separate chip calls would be. Each step keeps its own chip's gates and selectors.
+///
+/// Build one with `GadgetChain::builder()`, e.g. `.decompose().digit_sum().range_check(8).build()`.
+#[derive(Debug, Clone)]
+struct GadgetChain<F: PrimeField> {
+    steps: Vec<Step<F>>,
+}
+
+impl<F: PrimeField> GadgetChain<F> {
+    fn builder() -> GadgetChainBuilder<F> {
+        GadgetChainBuilder { steps: vec![] }
+    }
+
+    /// Runs the chain on `input`, returning the last step's output cell. For a decomposition that's the
+    /// decomposed value.
+    fn assign<const LT_BITS: usize>(
+        &self,
+        config: &GadgetChainConfig<F, LT_BITS>,
+        mut layouter: impl Layouter<F>,
+        input: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "gadget chain",
+            |mut region| {
+                let mut rows = LayoutPlanner::new();
+                let mut link = Link::Cell(input.clone());
+                for step in &self.steps {
+                    link = match (step, &link) {
+                        (Step::Decompose, link) => {
+                            let decomposed = config.decompose.assign_cell_in_region(
+                                &mut region,
+                                rows.reserve(config.decompose.rows_used()),
+                                link.cell(),
+                            )?;
+                            Link::Decomposed(decomposed)
+                        }
+                        (Step::DigitSum, Link::Decomposed(decomposed)) => {
+                            let sum = config.decompose.digit_sum_in_region(
+                                &mut region,
+                                rows.reserve(decomposed.windows.len() + 1),
+                                &decomposed.windows,
+                            )?;
+                            Link::Cell(sum)
+                        }
+                        (Step::DigitSum, Link::Cell(_)) => {
+                            unreachable!(
+                                "The builder only allows a digit sum after a decomposition"
+                            )
+                        }
+                        (Step::RangeCheck(num_bits), link) => {
+                            let cell = config.range.assign_in_region(
+                                &mut region,
+                                rows.reserve(BitsRangeCheckConfig::<F>::rows_used(*num_bits)),
+                                link.cell(),
+                                *num_bits,
+                            )?;
+                            Link::Cell(cell)
+                        }
+                        (Step::LessThan(bound), link) => {
+                            let lt = LessThanChip::construct(config.less_than.clone())
+                                .assign_in_region(
+                                    &mut region,
+                                    rows.reserve(LessThanChip::<F, LT_BITS>::rows_used()),
+                                    link.cell(),
+                                    bound,
+                                )?;
+                            Link::Cell(lt)
+                        }
+                    };
+                }
+                Ok(link.cell().clone())
+            },
+        )
+    }
+}
+
+#[derive(Debug)]
+struct GadgetChainBuilder<F: PrimeField> {
+    steps: Vec<Step<F>>,
+}
+
+impl<F: PrimeField> GadgetChainBuilder<F> {
+    fn decompose(mut self) -> Self {
+        self.steps.push(Step::Decompose);
+        self
+    }
+
+    /// Panics unless the step before is a decomposition, since there are no windows to sum otherwise.
+    fn digit_sum(mut self) -> Self {
+        assert!(
+            matches!(self.steps.last(), Some(Step::Decompose)),
+            "A digit sum needs a decomposition right before it"
+        );
+        self.steps.push(Step::DigitSum);
+        self
+    }
+
+    fn range_check(mut self, num_bits: usize) -> Self {
+        self.steps.push(Step::RangeCheck(num_bits));
+        self
+    }
+
+    fn less_than(mut self, bound: AssignedCell<F, F>) -> Self {
+        self.steps.push(Step::LessThan(bound));
+        self
+    }
+
+    fn build(self) -> GadgetChain<F> {
+        GadgetChain { steps: self.steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, Instance},
+    };
+
+    use super::*;
+    use crate::{
+        decompose_range_check::{plan_decomposition, NUM_BITS},
+        testing::run_mock_auto,
+    };
+
+    // 32 bits in 3-bit windows, whose digit sum is at most 10 * 7 + 3 = 73
+    const RANGE_BITS: usize = 32;
+
+    // Comparisons of the digit sum, which is at most 73, against a bound below 2^8
+    const LT_BITS: usize = 8;
+
+    // Decomposes the private value, sums its digits, and range-checks the sum to SUM_BITS bits, exposing the
+    // sum at instance row 0. With a private bound, the chain then compares the sum against it and exposes
+    // `sum < bound` instead.
+    #[derive(Default)]
+    struct DigitSumChainCircuit<const SUM_BITS: usize> {
+        value: Value<Fp>,
+        bound: Option<Value<Fp>>,
+    }
+
+    impl<const SUM_BITS: usize> Circuit<Fp> for DigitSumChainCircuit<SUM_BITS> {
+        type Config = (
+            GadgetChainConfig<Fp, LT_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: Value::unknown(),
+                bound: self.bound.map(|_| Value::unknown()),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            let plan = plan_decomposition(RANGE_BITS, NUM_BITS);
+            (GadgetChainConfig::configure(meta, plan), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let mut chain = GadgetChain::builder()
+                .decompose()
+                .digit_sum()
+                .range_check(SUM_BITS);
+            if let Some(bound) = self.bound {
+                let bound = layouter.assign_region(
+                    || "Load bound",
+                    |mut region| region.assign_advice(|| "bound", input, 0, || bound),
+                )?;
+                chain = chain.less_than(bound);
+            }
+            let chain = chain.build();
+            let sum = chain.assign(&config, layouter.namespace(|| "chain"), &value)?;
+            layouter.constrain_instance(sum.cell(), instance, 0)
+        }
+    }
+
+    fn circuit<const SUM_BITS: usize>(value: u64) -> DigitSumChainCircuit<SUM_BITS> {
+        DigitSumChainCircuit {
+            value: Value::known(Fp::from(value)),
+            bound: None,
+        }
+    }
+
+    fn compare_circuit(value: u64, bound: u64) -> DigitSumChainCircuit<7> {
+        DigitSumChainCircuit {
+            bound: Some(Value::known(Fp::from(bound))),
+            ..circuit(value)
+        }
+    }
+
+    // The sum of the value's base 8 digits
+    fn host_digit_sum(value: u64) -> u64 {
+        (0..RANGE_BITS)
+            .step_by(NUM_BITS)
+            .map(|shift| (value >> shift) & 7)
+            .sum()
+    }
+
+    #[test]
+    fn test_decompose_digit_sum_range_check() {
+        for value in [0, 1, 0o777, 0xdead_beef, u32::MAX as u64] {
+            let sum = Fp::from(host_digit_sum(value));
+            run_mock_auto(&circuit::<7>(value), vec![vec![sum]]).assert_satisfied();
+            assert!(
+                run_mock_auto(&circuit::<7>(value), vec![vec![sum + Fp::from(1)]])
+                    .verify()
+                    .is_err()
+            );
+        }
+        // The decomposition still range-checks the value itself
+        assert!(
+            run_mock_auto(&circuit::<7>(1 << RANGE_BITS), vec![vec![Fp::from(1)]])
+                .verify()
+                .is_err()
+        );
+        // 0xffffffff's digit sum of 73 doesn't fit in 6 bits, but 0o777's of 21 does
+        assert_eq!(host_digit_sum(u32::MAX as u64), 73);
+        assert!(
+            run_mock_auto(&circuit::<6>(u32::MAX as u64), vec![vec![Fp::from(73)]])
+                .verify()
+                .is_err()
+        );
+        run_mock_auto(&circuit::<6>(0o777), vec![vec![Fp::from(21)]]).assert_satisfied();
+
+        // Comparing 0o777's digit sum of 21 against a bound gives 21 < bound, and not its negation
+        for (bound, lt) in [(22, true), (21, false), (0, false), (255, true)] {
+            let lt = Fp::from(lt as u64);
+            run_mock_auto(&compare_circuit(0o777, bound), vec![vec![lt]]).assert_satisfied();
+            assert!(
+                run_mock_auto(&compare_circuit(0o777, bound), vec![vec![Fp::ONE - lt]])
+                    .verify()
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "A digit sum needs a decomposition right before it")]
+    fn test_digit_sum_without_decomposition() {
+        GadgetChain::<Fp>::builder().range_check(8).digit_sum();
+    }
+}
@@ -0,0 +1,261 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::decompose_range_check::field_to_u128;
+
+/// Computes the Hamming weight, i.e. the number of set bits, of a value in `0..2^N_BITS`.
+/// Each bit $b_i$ is witnessed in its own cell and constrained to be boolean. Next to it, a running sum $z$
+/// shifts the bits out of the value, and a running sum $w$ adds them up:
+///     $$z_0 = \alpha, \quad z_i = 2 z_{i+1} + b_i, \quad z_N = 0$$
+///     $$w_0 = 0, \quad w_{i+1} = w_i + b_i$$
+/// so that $w_N$ is the weight. As in the bits range check, $z_N = 0$ also range-checks the value.
+#[derive(Debug, Clone)]
+struct HammingWeightConfig<F: PrimeField, const N_BITS: usize> {
+    bit: Column<Advice>,
+    z: Column<Advice>,
+    w: Column<Advice>,
+    q_bit: Selector,
+    q_start: Selector,
+    q_end: Selector,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> HammingWeightConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let bit = meta.advice_column();
+        let z = meta.advice_column();
+        let w = meta.advice_column();
+        let q_bit = meta.selector();
+        let q_start = meta.selector();
+        let q_end = meta.selector();
+        // Needed to copy the value in, and the weight out
+        meta.enable_equality(z);
+        meta.enable_equality(w);
+
+        //      bit     |      z      |      w      |  q_bit  |  q_start  |  q_end
+        //    ----------------------------------------------------------------------
+        //      b_0     |  value=z_0  |   w_0 = 0   |    1    |     1     |    0
+        //      b_1     |     z_1     |     w_1     |    1    |     0     |    0
+        //      ...     |     ...     |     ...     |   ...   |    ...    |   ...
+        //       -      |     z_N     |  weight=w_N |    0    |     0     |    1
+        meta.create_gate("hamming weight", |meta| {
+            let q = meta.query_selector(q_bit);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let w_cur = meta.query_advice(w, Rotation::cur());
+            let w_next = meta.query_advice(w, Rotation::next());
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "b_i is boolean",
+                        bit.clone() * (Expression::Constant(F::ONE) - bit.clone()),
+                    ),
+                    (
+                        "z_i = 2 * z_{i+1} + b_i",
+                        z_cur - (z_next * Expression::Constant(F::from(2)) + bit.clone()),
+                    ),
+                    ("w_{i+1} = w_i + b_i", w_next - (w_cur + bit)),
+                ],
+            )
+        });
+
+        meta.create_gate("w_0 = 0", |meta| {
+            let q = meta.query_selector(q_start);
+            let w = meta.query_advice(w, Rotation::cur());
+            Constraints::with_selector(q, [("w_0 = 0", w)])
+        });
+
+        meta.create_gate("z_N = 0", |meta| {
+            let q = meta.query_selector(q_end);
+            let z = meta.query_advice(z, Rotation::cur());
+            Constraints::with_selector(q, [("z_N = 0", z)])
+        });
+
+        Self {
+            bit,
+            z,
+            w,
+            q_bit,
+            q_start,
+            q_end,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HammingWeightChip<F: PrimeField, const N_BITS: usize> {
+    config: HammingWeightConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> HammingWeightChip<F, N_BITS> {
+    fn construct(config: HammingWeightConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> HammingWeightConfig<F, N_BITS> {
+        HammingWeightConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to the number of set bits in `value`. The host side reads the low 128 bits,
+    /// so N_BITS must be at most 128.
+    fn weight(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let bits = value
+            .value()
+            .map(|value| {
+                let value = field_to_u128(*value);
+                (0..N_BITS)
+                    .map(|i| F::from(((value >> i) & 1) as u64))
+                    .collect::<Vec<_>>()
+            })
+            .transpose_vec(N_BITS);
+        self.assign_bits(layouter, value, bits)
+    }
+
+    fn assign_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        bits: Vec<Value<F>>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(bits.len(), N_BITS, "Expected one bit per row");
+        let config = &self.config;
+        layouter.assign_region(
+            || "hamming weight",
+            |mut region| {
+                config.q_start.enable(&mut region, 0)?;
+                let mut z = value.copy_advice(|| "z_0", &mut region, config.z, 0)?;
+                let mut w =
+                    region.assign_advice(|| "w_0", config.w, 0, || Value::known(F::ZERO))?;
+                for (i, bit) in bits.iter().enumerate() {
+                    config.q_bit.enable(&mut region, i)?;
+                    region.assign_advice(|| format!("b_{}", i), config.bit, i, || *bit)?;
+                    let z_next = z.value().zip(*bit).map(|(z, bit)| (*z - bit) * F::TWO_INV);
+                    z = region.assign_advice(
+                        || format!("z_{}", i + 1),
+                        config.z,
+                        i + 1,
+                        || z_next,
+                    )?;
+                    let w_next = w.value().zip(*bit).map(|(w, bit)| *w + bit);
+                    w = region.assign_advice(
+                        || format!("w_{}", i + 1),
+                        config.w,
+                        i + 1,
+                        || w_next,
+                    )?;
+                }
+                config.q_end.enable(&mut region, N_BITS)?;
+                Ok(w)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 8;
+
+    // Loads `value` and exposes its weight at instance row 0. The witness for the bits can be forged.
+    #[derive(Default)]
+    struct HammingWeightCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for HammingWeightCircuit<F> {
+        type Config = (
+            HammingWeightConfig<F, N_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type Witness = Vec<Value<F>>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (HammingWeightChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            bits: Option<&Vec<Value<F>>>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let chip = HammingWeightChip::construct(config);
+            let weight = match bits {
+                Some(bits) => {
+                    chip.assign_bits(layouter.namespace(|| "weight"), &value, bits.clone())
+                }
+                None => chip.weight(layouter.namespace(|| "weight"), &value),
+            }?;
+            layouter.constrain_instance(weight.cell(), instance, 0)
+        }
+    }
+
+    fn circuit(value: u64) -> Forged<Fp, HammingWeightCircuit<Fp>> {
+        Forged::honest(HammingWeightCircuit {
+            value: Value::known(Fp::from(value)),
+        })
+    }
+
+    #[test]
+    fn test_hamming_weight() {
+        for (value, weight) in [
+            (0, 0),
+            (0b1000_0000, 1),
+            (0b0010_0000, 1),
+            (0xff, N_BITS as u64),
+            (0b1011_0010, 4),
+        ] {
+            assert!(run(&circuit(value), &[Fp::from(weight)]));
+            for wrong in [weight + 1, weight.wrapping_sub(1)] {
+                assert!(!run(&circuit(value), &[Fp::from(wrong)]));
+            }
+        }
+
+        // 256 needs 9 bits
+        assert!(!run(&circuit(256), &[Fp::ONE]));
+    }
+
+    #[test]
+    fn test_hamming_weight_non_boolean_bit() {
+        // 2 = 2 * 1 + 0, but also 2 = 2 * 0 + 2, with a "bit" of 2 that would claim weight 2
+        let bits = |low: u64, next: u64| {
+            [low, next]
+                .into_iter()
+                .chain([0; N_BITS - 2])
+                .map(|bit| Value::known(Fp::from(bit)))
+                .collect()
+        };
+        assert!(run(&circuit(2).forge(bits(0, 1)), &[Fp::ONE]));
+        assert!(!run(&circuit(2).forge(bits(2, 0)), &[Fp::from(2)]));
+    }
+}
@@ -0,0 +1,220 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig,
+    less_than::{LessThanChip, LessThanConfig},
+};
+
+/// Computes `in_range = (lo <= value < hi)` for bounds `lo` and `hi` that are cells rather than consts,
+/// so they can come from public inputs or other chips. The value and both bounds are first range-checked to
+/// N_BITS bits, which the less-than gadget needs, and then compared twice:
+///     $$in\_range = (1 - (value < lo)) \cdot (value < hi)$$
+#[derive(Debug, Clone)]
+struct InRangeConfig<F: PrimeField, const N_BITS: usize> {
+    below_lo: Column<Advice>,
+    below_hi: Column<Advice>,
+    in_range: Column<Advice>,
+    q_in_range: Selector,
+    less_than: LessThanConfig<F, N_BITS>,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> InRangeConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let below_lo = meta.advice_column();
+        let below_hi = meta.advice_column();
+        let in_range = meta.advice_column();
+        let q_in_range = meta.selector();
+        let less_than = LessThanChip::configure(meta);
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [below_lo, below_hi, in_range] {
+            meta.enable_equality(column);
+        }
+
+        //   below_lo   |   below_hi   |   in_range   | q_in_range
+        //  ---------------------------------------------------------
+        //  value < lo  |  value < hi  |  in_range    |     1
+        meta.create_gate("in range", |meta| {
+            let q = meta.query_selector(q_in_range);
+            let below_lo = meta.query_advice(below_lo, Rotation::cur());
+            let below_hi = meta.query_advice(below_hi, Rotation::cur());
+            let in_range = meta.query_advice(in_range, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [(
+                    "in_range = (1 - below_lo) * below_hi",
+                    in_range - (Expression::Constant(F::ONE) - below_lo) * below_hi,
+                )],
+            )
+        });
+
+        Self {
+            below_lo,
+            below_hi,
+            in_range,
+            q_in_range,
+            less_than,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct InRangeChip<F: PrimeField, const N_BITS: usize> {
+    config: InRangeConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> InRangeChip<F, N_BITS> {
+    fn construct(config: InRangeConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> InRangeConfig<F, N_BITS> {
+        InRangeConfig::configure(meta)
+    }
+
+    /// Returns a boolean cell constrained to `lo <= value < hi`.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        lo: &AssignedCell<F, F>,
+        hi: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        for (name, cell) in [("value", value), ("lo", lo), ("hi", hi)] {
+            config.range.assign(
+                layouter.namespace(|| format!("range check {}", name)),
+                cell,
+                N_BITS,
+            )?;
+        }
+
+        let less_than = LessThanChip::construct(config.less_than.clone());
+        let below_lo = less_than.assign(layouter.namespace(|| "value < lo"), value, lo)?;
+        let below_hi = less_than.assign(layouter.namespace(|| "value < hi"), value, hi)?;
+
+        layouter.assign_region(
+            || "in range",
+            |mut region| {
+                let offset = 0;
+                config.q_in_range.enable(&mut region, offset)?;
+                let below_lo =
+                    below_lo.copy_advice(|| "below_lo", &mut region, config.below_lo, offset)?;
+                let below_hi =
+                    below_hi.copy_advice(|| "below_hi", &mut region, config.below_hi, offset)?;
+                let in_range = below_lo
+                    .value()
+                    .zip(below_hi.value())
+                    .map(|(below_lo, below_hi)| (F::ONE - *below_lo) * *below_hi);
+                region.assign_advice(|| "in_range", config.in_range, offset, || in_range)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const N_BITS: usize = 8;
+
+    // Reads `lo` and `hi` from instance rows 0 and 1, and exposes whether the private value is in range at row 2
+    #[derive(Default)]
+    struct InRangeCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for InRangeCircuit<F> {
+        type Config = (InRangeConfig<F, N_BITS>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (InRangeChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (value, lo, hi) = layouter.assign_region(
+                || "Load value and bounds",
+                |mut region| {
+                    let value = region.assign_advice(|| "value", input, 0, || self.value)?;
+                    let lo = region.assign_advice_from_instance(|| "lo", instance, 0, input, 1)?;
+                    let hi = region.assign_advice_from_instance(|| "hi", instance, 1, input, 2)?;
+                    Ok((value, lo, hi))
+                },
+            )?;
+            let in_range = InRangeChip::construct(config).assign(
+                layouter.namespace(|| "lo <= value < hi"),
+                &value,
+                &lo,
+                &hi,
+            )?;
+            layouter.constrain_instance(in_range.cell(), instance, 2)
+        }
+    }
+
+    fn run(value: u64, lo: u64, hi: u64, in_range: bool) -> bool {
+        let circuit = InRangeCircuit {
+            value: Value::known(Fp::from(value)),
+        };
+        let public_inputs = vec![vec![Fp::from(lo), Fp::from(hi), Fp::from(in_range as u64)]];
+        run_mock_auto(&circuit, public_inputs).verify().is_ok()
+    }
+
+    #[test]
+    fn test_in_range() {
+        for (value, lo, hi, in_range) in [
+            (10, 10, 20, true),
+            (19, 10, 20, true),
+            (15, 10, 20, true),
+            (20, 10, 20, false),
+            (9, 10, 20, false),
+            (0, 0, 255, true),
+            (255, 0, 255, false),
+            // An empty interval
+            (10, 10, 10, false),
+        ] {
+            assert!(run(value, lo, hi, in_range));
+            // The result is constrained, so claiming the opposite fails
+            assert!(!run(value, lo, hi, !in_range));
+        }
+    }
+
+    #[test]
+    fn test_in_range_out_of_bits() {
+        // 256 doesn't fit in N_BITS, whether it's the value or a bound
+        for (value, lo, hi) in [(256, 10, 20), (15, 256, 20), (15, 10, 256)] {
+            assert!(!run(value, lo, hi, false));
+            assert!(!run(value, lo, hi, true));
+        }
+    }
+}
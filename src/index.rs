@@ -0,0 +1,165 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{ConstraintSystem, Error},
+};
+use std::marker::PhantomData;
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig,
+    less_than::{LessThanChip, LessThanConfig},
+};
+
+/// Checks that `index` is a valid index into an array whose `length` is a cell, so it can be known only at
+/// proving time rather than fixed like a const RANGE. Both are range-checked to N_BITS bits, which the
+/// less-than gadget needs, and then $index < length$ is enforced. An empty array, $length = 0$, has no valid
+/// index.
+#[derive(Debug, Clone)]
+struct IndexConfig<F: PrimeField, const N_BITS: usize> {
+    less_than: LessThanConfig<F, N_BITS>,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> IndexConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            less_than: LessThanChip::configure(meta),
+            range: BitsRangeCheckConfig::configure(meta),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IndexChip<F: PrimeField, const N_BITS: usize> {
+    config: IndexConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> IndexChip<F, N_BITS> {
+    fn construct(config: IndexConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> IndexConfig<F, N_BITS> {
+        IndexConfig::configure(meta)
+    }
+
+    /// Constrains `index < length`, returning the range-checked copy of `index`.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        index: &AssignedCell<F, F>,
+        length: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let index =
+            config
+                .range
+                .assign(layouter.namespace(|| "range check index"), index, N_BITS)?;
+        let length =
+            config
+                .range
+                .assign(layouter.namespace(|| "range check length"), length, N_BITS)?;
+        LessThanChip::construct(config.less_than.clone()).assert_less_than(
+            layouter.namespace(|| "index < length"),
+            &index,
+            &length,
+        )?;
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const N_BITS: usize = 8;
+
+    // Reads the length from instance row 0, and exposes the validated private index at row 1
+    #[derive(Default)]
+    struct IndexCircuit<F: PrimeField> {
+        index: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for IndexCircuit<F> {
+        type Config = (IndexConfig<F, N_BITS>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (IndexChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (index, length) = layouter.assign_region(
+                || "Load index and length",
+                |mut region| {
+                    let index = region.assign_advice(|| "index", input, 0, || self.index)?;
+                    let length =
+                        region.assign_advice_from_instance(|| "length", instance, 0, input, 1)?;
+                    Ok((index, length))
+                },
+            )?;
+            let index = IndexChip::construct(config).assign(
+                layouter.namespace(|| "index < length"),
+                &index,
+                &length,
+            )?;
+            layouter.constrain_instance(index.cell(), instance, 1)
+        }
+    }
+
+    fn run(index: u64, length: u64) -> bool {
+        let circuit = IndexCircuit {
+            index: Value::known(Fp::from(index)),
+        };
+        let public_inputs = vec![vec![Fp::from(length), Fp::from(index)]];
+        run_mock_auto(&circuit, public_inputs).verify().is_ok()
+    }
+
+    #[test]
+    fn test_valid_index() {
+        for (index, length) in [(0, 1), (4, 5), (9, 10), (254, 255), (0, 255)] {
+            assert!(run(index, length), "{} < {}", index, length);
+        }
+    }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        for length in [1, 5, 10, 255] {
+            assert!(run(length - 1, length));
+            assert!(!run(length, length), "{} == {}", length, length);
+            assert!(!run(length + 1, length));
+        }
+        // A length past N_BITS would let any 8-bit index through if it weren't range-checked
+        assert!(!run(3, 256));
+    }
+
+    #[test]
+    fn test_empty_array() {
+        for index in [0, 1, 255] {
+            assert!(!run(index, 0), "{} indexed an empty array", index);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+/// The name of a chip's region `name`, prefixed with the caller's `label` if it gave one, e.g. "fib 1: next row"
+/// for the label "fib 1". Chips which take a label pass it through here, so that a circuit using a chip several
+/// times can tell its regions apart in MockProver failures.
+pub(crate) fn region_name(label: Option<&str>, name: &str) -> String {
+    match label {
+        Some(label) => format!("{}: {}", label, name),
+        None => name.to_string(),
+    }
+}
@@ -0,0 +1,307 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::{cmp::Ordering, marker::PhantomData};
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+/// Compares two field elements as integers, reading their little-endian reprs from the top byte down.
+pub(crate) fn field_cmp<F: PrimeField>(a: &F, b: &F) -> Ordering {
+    let (a, b) = (a.to_repr(), b.to_repr());
+    a.as_ref().iter().rev().cmp(b.as_ref().iter().rev())
+}
+
+/// $2^n$ as a field element.
+pub(crate) fn pow2<F: PrimeField>(n: usize) -> F {
+    F::from(2).pow_vartime([n as u64])
+}
+
+/// Computes `lt = (a < b)` for `a` and `b` in `0..2^N_BITS`.
+/// We witness a boolean `lt` and
+///     $$d = b - a - 1 + (1 - lt) \cdot 2^{N}$$
+/// and range-check $d$ to $N$ bits. If $a < b$ then $d = b - a - 1$, otherwise $d = 2^N - (a - b) - 1$;
+/// only the correct `lt` puts $d$ in range.
+///
+/// The inputs themselves are not range-checked here: the caller must already know they fit in N_BITS bits,
+/// otherwise a field-negative input can pass the comparison.
+#[derive(Debug, Clone)]
+pub(crate) struct LessThanConfig<F: PrimeField, const N_BITS: usize> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    lt: Column<Advice>,
+    diff: Column<Advice>,
+    q_less_than: Selector,
+    q_assert: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> LessThanConfig<F, N_BITS> {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let lt = meta.advice_column();
+        let diff = meta.advice_column();
+        let q_less_than = meta.selector();
+        let q_assert = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [a, b, lt, diff] {
+            meta.enable_equality(column);
+        }
+
+        //     a    |    b    |   lt   |   diff   | q_less_than | q_assert
+        //    ------------------------------------------------------------
+        //     a    |    b    |   lt   |    d     |      1      |  0 / 1
+        meta.create_gate("less than", |meta| {
+            let q = meta.query_selector(q_less_than);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                q,
+                [
+                    ("lt is boolean", lt.clone() * (one.clone() - lt.clone())),
+                    (
+                        "d = b - a - 1 + (1 - lt) * 2^N",
+                        diff - (b - a - one.clone())
+                            - (one - lt) * Expression::Constant(pow2(N_BITS)),
+                    ),
+                ],
+            )
+        });
+
+        meta.create_gate("assert less than", |meta| {
+            let q = meta.query_selector(q_assert);
+            let lt = meta.query_advice(lt, Rotation::cur());
+            Constraints::with_selector(q, [("lt = 1", Expression::Constant(F::ONE) - lt)])
+        });
+
+        Self {
+            a,
+            b,
+            lt,
+            diff,
+            q_less_than,
+            q_assert,
+            range,
+        }
+    }
+}
+
+// The `lt` and `d` cells of a comparison row
+type LtAndDiff<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+#[derive(Debug, Clone)]
+pub(crate) struct LessThanChip<F: PrimeField, const N_BITS: usize> {
+    config: LessThanConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> LessThanChip<F, N_BITS> {
+    pub(crate) fn construct(config: LessThanConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> LessThanConfig<F, N_BITS> {
+        LessThanConfig::configure(meta)
+    }
+
+    /// Returns a boolean cell constrained to `a < b`.
+    pub(crate) fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.assign_inner(layouter, a, b, false)
+    }
+
+    /// Constrains `a < b`, failing verification otherwise.
+    pub(crate) fn assert_less_than(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        self.assign_inner(layouter, a, b, true).map(|_| ())
+    }
+
+    /// The number of rows assign_in_region takes up: the comparison row, beside the first row of the range check
+    /// of `d`.
+    pub(crate) fn rows_used() -> usize {
+        BitsRangeCheckConfig::<F>::rows_used(N_BITS)
+    }
+
+    /// Like assign, but lays the comparison and the range check of `d` out in an existing region from `offset`,
+    /// taking `rows_used` rows.
+    pub(crate) fn assign_in_region(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let (lt, diff) = self.assign_comparison(region, offset, a, b, false)?;
+        self.config
+            .range
+            .assign_in_region(region, offset, &diff, N_BITS)?;
+        Ok(lt)
+    }
+
+    fn assign_inner(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        enforce: bool,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let (lt, diff) = layouter.assign_region(
+            || "less than",
+            |mut region| self.assign_comparison(&mut region, 0, a, b, enforce),
+        )?;
+
+        self.config
+            .range
+            .assign(layouter.namespace(|| "range check diff"), &diff, N_BITS)?;
+        Ok(lt)
+    }
+
+    // Assigns the comparison row at `offset`, returning `lt` and `d` for the caller to range-check
+    fn assign_comparison(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        enforce: bool,
+    ) -> Result<LtAndDiff<F>, Error> {
+        let config = &self.config;
+        config.q_less_than.enable(region, offset)?;
+        if enforce {
+            config.q_assert.enable(region, offset)?;
+        }
+
+        let a = a.copy_advice(|| "a", region, config.a, offset)?;
+        let b = b.copy_advice(|| "b", region, config.b, offset)?;
+        let lt_value = a
+            .value()
+            .zip(b.value())
+            .map(|(a, b)| field_cmp(a, b) == Ordering::Less);
+        let diff_value = a.value().zip(b.value()).zip(lt_value).map(|((a, b), lt)| {
+            let diff = *b - *a - F::ONE;
+            if lt {
+                diff
+            } else {
+                diff + pow2::<F>(N_BITS)
+            }
+        });
+
+        let lt = region.assign_advice(
+            || "lt",
+            config.lt,
+            offset,
+            || lt_value.map(|lt| F::from(lt as u64)),
+        )?;
+        let diff = region.assign_advice(|| "diff", config.diff, offset, || diff_value)?;
+        Ok((lt, diff))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+
+    const N_BITS: usize = 8;
+
+    #[derive(Default)]
+    struct LessThanCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+        enforce: bool,
+    }
+
+    impl<F: PrimeField> Circuit<F> for LessThanCircuit<F> {
+        type Config = (LessThanConfig<F, N_BITS>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                enforce: self.enforce,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (LessThanChip::configure(meta), input)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = LessThanChip::construct(config);
+            let (a, b) = layouter.assign_region(
+                || "Load a and b",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            if self.enforce {
+                chip.assert_less_than(layouter.namespace(|| "a < b"), &a, &b)
+            } else {
+                chip.assign(layouter.namespace(|| "a < b"), &a, &b)
+                    .map(|_| ())
+            }
+        }
+    }
+
+    fn circuit(a: u64, b: u64, enforce: bool) -> LessThanCircuit<Fp> {
+        LessThanCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            enforce,
+        }
+    }
+
+    #[test]
+    fn test_less_than() {
+        let k = 6;
+        // Without enforcing, lt is just a correctly constrained boolean either way
+        for (a, b) in [(0, 1), (3, 200), (254, 255), (5, 5), (255, 0), (100, 7)] {
+            let prover = MockProver::run(k, &circuit(a, b, false), vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_assert_less_than() {
+        let k = 6;
+        for (a, b) in [(0, 1), (3, 200), (254, 255)] {
+            let prover = MockProver::run(k, &circuit(a, b, true), vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+        for (a, b) in [(5, 5), (255, 0), (100, 7)] {
+            let prover = MockProver::run(k, &circuit(a, b, true), vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
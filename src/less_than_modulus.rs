@@ -0,0 +1,271 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::decompose_range_check::{
+    decompose, field_to_u128, plan_decomposition, DecomposeRangeCheckConfig, DecompositionPlan,
+    LookupTransform, SharedRangeTable, NUM_BITS,
+};
+
+/// Range-checks a value against a constant bound $r$ which need not be a power of two, such as the order of a
+/// prime-order subgroup, so that the value is a canonical scalar for that group.
+/// The value is decomposed into $K$-bit windows $w_i$ by the decompose chip, and $r$ into constant limbs $r_i$
+/// with the same plan. The windows are then compared against the limbs from the most significant down, with a
+/// boolean $lt_j$ recording whether the top $j$ windows are already less than the top $j$ limbs:
+///     $$lt_0 = 0, \quad lt_n = 1$$
+/// Once less, the value stays less. While equal, a window equal to its limb stays equal, and a window below its
+/// limb makes the value less, which is checked by looking up $r_i - 1 - w_i$ in the $K$-bit table.
+/// Ending with $lt_n = 1$ rules out the value being equal to $r$ too.
+///
+/// The bound must fit in the field's capacity, as for any decomposition, e.g. the 253-bit ed25519 group order
+/// in a Pasta circuit.
+#[derive(Debug, Clone)]
+pub(crate) struct LessThanModulusConfig<F: PrimeField> {
+    decompose: DecomposeRangeCheckConfig<F>,
+    table: SharedRangeTable<F>,
+    window: Column<Advice>,
+    lt: Column<Advice>,
+    limb: Column<Fixed>,
+    q_compare: Selector,
+    q_start: Selector,
+    q_end: Selector,
+    plan: DecompositionPlan,
+    // The limbs of the bound, least significant first like the windows
+    limbs: Vec<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> LessThanModulusConfig<F> {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, modulus: F) -> Self {
+        // The bound's bit length, reading its little-endian repr from the top byte down
+        let repr = modulus.to_repr();
+        let modulus_bits = repr
+            .as_ref()
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, byte)| **byte != 0)
+            .map_or(0, |(i, byte)| 8 * (i + 1) - byte.leading_zeros() as usize);
+        let plan = plan_decomposition(modulus_bits, NUM_BITS);
+        let limbs = decompose(modulus, &plan);
+
+        let table = SharedRangeTable::configure(meta);
+        let decompose = DecomposeRangeCheckConfig::configure_with_table(
+            meta,
+            plan.clone(),
+            LookupTransform::default(),
+            &table,
+        );
+        let window = meta.advice_column();
+        let lt = meta.advice_column();
+        let limb = meta.fixed_column();
+        let q_compare = meta.complex_selector();
+        let q_start = meta.selector();
+        let q_end = meta.selector();
+        // Needed to copy the windows over from the decomposition
+        meta.enable_equality(window);
+
+        //     window   |   lt    |   limb    | q_compare | q_start | q_end
+        //    -------------------------------------------------------------
+        //    w_{n-1}   |  lt_0   |  r_{n-1}  |     1     |    1    |   0
+        //    w_{n-2}   |  lt_1   |  r_{n-2}  |     1     |    0    |   0
+        //      ...     |   ...   |    ...    |    ...    |   ...   |  ...
+        //      -       |  lt_n   |     -     |     0     |    0    |   1
+        meta.create_gate("compare window", |meta| {
+            let q = meta.query_selector(q_compare);
+            let window = meta.query_advice(window, Rotation::cur());
+            let lt_prev = meta.query_advice(lt, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::next());
+            let limb = meta.query_fixed(limb, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                q,
+                [
+                    ("lt is boolean", lt.clone() * (one.clone() - lt.clone())),
+                    (
+                        "once less, always less",
+                        lt_prev.clone() * (one.clone() - lt.clone()),
+                    ),
+                    (
+                        "still equal means window = limb",
+                        (one.clone() - lt_prev) * (one - lt) * (window - limb),
+                    ),
+                ],
+            )
+        });
+
+        // Only when this window is the one that makes the value less, it must be below its limb
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_compare);
+            let window = meta.query_advice(window, Rotation::cur());
+            let lt_prev = meta.query_advice(lt, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::next());
+            let limb = meta.query_fixed(limb, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            vec![(
+                q * (one.clone() - lt_prev) * lt * (limb - one - window),
+                table.0.value,
+            )]
+        });
+
+        meta.create_gate("start equal", |meta| {
+            let q = meta.query_selector(q_start);
+            let lt = meta.query_advice(lt, Rotation::cur());
+            Constraints::with_selector(q, [("lt_0 = 0", lt)])
+        });
+
+        meta.create_gate("end less", |meta| {
+            let q = meta.query_selector(q_end);
+            let lt = meta.query_advice(lt, Rotation::cur());
+            Constraints::with_selector(q, [("lt_n = 1", Expression::Constant(F::ONE) - lt)])
+        });
+
+        Self {
+            decompose,
+            table,
+            window,
+            lt,
+            limb,
+            q_compare,
+            q_start,
+            q_end,
+            plan,
+            limbs,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Loads the lookup table shared by the decomposition and the comparison.
+    pub(crate) fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.table.load(layouter)
+    }
+
+    /// Assigns `value` and constrains it to be less than the bound. Returns the checked value cell.
+    pub(crate) fn assign_less_than_modulus(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let decomposed =
+            self.decompose
+                .assign_field_value(layouter.namespace(|| "decompose"), None, value)?;
+        let num_windows = self.plan.num_windows();
+
+        layouter.assign_region(
+            || "Less than modulus",
+            |mut region| {
+                self.q_start.enable(&mut region, 0)?;
+                let mut lt =
+                    region.assign_advice(|| "lt_0", self.lt, 0, || Value::known(F::ZERO))?;
+                // Most significant window first
+                for (offset, i) in (0..num_windows).rev().enumerate() {
+                    self.q_compare.enable(&mut region, offset)?;
+                    let window = decomposed.windows[i].0.copy_advice(
+                        || format!("window {}", i),
+                        &mut region,
+                        self.window,
+                        offset,
+                    )?;
+                    let limb = self.limbs[i];
+                    region.assign_fixed(
+                        || format!("limb {}", i),
+                        self.limb,
+                        offset,
+                        || Value::known(limb),
+                    )?;
+                    let next = lt.value().zip(window.value()).map(|(lt, window)| {
+                        let less = field_to_u128(*window) < field_to_u128(limb);
+                        F::from((*lt == F::ONE || less) as u64)
+                    });
+                    lt = region.assign_advice(
+                        || format!("lt_{}", offset + 1),
+                        self.lt,
+                        offset + 1,
+                        || next,
+                    )?;
+                }
+                self.q_end.enable(&mut region, num_windows)?;
+                Ok(())
+            },
+        )?;
+        Ok(decomposed.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::run_mock_auto;
+    use ff::Field;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, pasta::Fp, plonk::Circuit};
+
+    // The order of the prime-order subgroup of ed25519, 2^252 + 27742317777372353535851937790883648493
+    fn ed25519_order() -> Fp {
+        Fp::from_str_vartime(
+            "7237005577332262213973186563042994240857116359379907606001950938285454250989",
+        )
+        .unwrap()
+    }
+
+    #[derive(Default)]
+    struct LessThanModulusCircuit {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for LessThanModulusCircuit {
+        type Config = LessThanModulusConfig<Fp>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            LessThanModulusConfig::configure(meta, ed25519_order())
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            config.assign_less_than_modulus(layouter.namespace(|| "scalar"), self.value)?;
+            Ok(())
+        }
+    }
+
+    fn circuit(value: Fp) -> LessThanModulusCircuit {
+        LessThanModulusCircuit {
+            value: Value::known(value),
+        }
+    }
+
+    #[test]
+    fn test_less_than_modulus_pass() {
+        let r = ed25519_order();
+        // The top bit alone, and r with its low limb lowered, which is only less in the last window
+        for value in [
+            Fp::ZERO,
+            Fp::ONE,
+            Fp::from(2).pow_vartime([252]),
+            r - Fp::from(8),
+            r - Fp::ONE,
+        ] {
+            run_mock_auto(&circuit(value), vec![]).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_less_than_modulus_fail() {
+        let r = ed25519_order();
+        // r itself, just past it, and a value which only fits in the decomposition's 253 bits
+        for value in [r, r + Fp::ONE, Fp::from(2).pow_vartime([253]) - Fp::ONE] {
+            assert!(run_mock_auto(&circuit(value), vec![]).verify().is_err());
+        }
+    }
+}
@@ -1,5 +1,67 @@
 // mod fib_lec1;
-// mod fib_lec2_part2;
+mod abs_diff;
+mod add_no_overflow;
+mod allowlist;
+mod ascii_digit;
+mod batch_range_check;
+pub mod bench;
+mod bit_reverse;
+mod bits_range_check;
+mod bitwise_and;
+mod boolean;
+mod bounded_sum;
+mod byte_decompose;
+mod concat;
+mod coprime;
+#[cfg(test)]
+mod corrupt;
+mod cyclic_range;
 mod decompose_range_check;
+pub mod demo;
+mod digit_count;
+mod distance;
+mod dynamic_bits;
+#[cfg(feature = "export")]
+mod export;
+mod fib_and_range;
+mod fib_lec2;
+mod fib_lec2_part2;
+mod gadget_chain;
+mod hamming_weight;
+mod in_range;
+mod index;
+mod label;
+mod less_than;
+mod less_than_modulus;
+mod limb_add;
+mod mod_reduce;
+mod msb;
+mod mul_range;
+mod multi_range;
+mod multiple_of;
+mod negate_range;
+mod offset;
+mod parity;
+mod planner;
 mod range_check;
+mod range_proof;
+mod repdigit;
+mod round_to_multiple;
+mod running_max;
+mod running_sum_range_check;
+mod sorted;
+mod strictly_between;
 mod table;
+#[cfg(test)]
+mod testing;
+// The selector checks in testing reuse the trace recorder
+#[cfg(any(test, feature = "trace"))]
+mod trace;
+mod utf8_continuation;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod wasm;
+mod witness;
+
+// plotters draws with native font and image libraries
+#[cfg(all(target_arch = "wasm32", feature = "dev-graph"))]
+compile_error!("The dev-graph feature doesn't build for wasm32");
@@ -0,0 +1,399 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig, decompose_range_check::field_to_u128, less_than::pow2,
+};
+
+/// Adds two big integers held as little-endian limbs of `limb_bits` bits each, propagating the carry from each
+/// limb into the next:
+///     $$a_i + b_i + c_{i-1} = s_i + 2^{limb\_bits} \cdot c_i, \quad c_{-1} = 0$$
+/// Each carry $c_i$ is boolean and each sum limb $s_i$ is range-checked to `limb_bits` bits, which pins them
+/// both down as long as the addend limbs fit in `limb_bits` bits too: the left side is then below
+/// $2^{limb\_bits + 1}$, so it has exactly one such split. The addends are the caller's to range-check.
+#[derive(Debug, Clone)]
+struct LimbAddConfig<F: PrimeField> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    sum: Column<Advice>,
+    carry: Column<Advice>,
+    base: Column<Fixed>,
+    q_start: Selector,
+    q_add: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> LimbAddConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let sum = meta.advice_column();
+        let carry = meta.advice_column();
+        let base = meta.fixed_column();
+        let q_start = meta.selector();
+        let q_add = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [a, b, sum, carry] {
+            meta.enable_equality(column);
+        }
+
+        //     a    |    b    |   sum   |  carry  |     base     | q_start | q_add
+        //    ------------------------------------------------------------------------
+        //          |         |         |    0    |              |    1    |   0
+        //    a_0   |   b_0   |   s_0   |   c_0   | 2^limb_bits  |    0    |   1
+        //    ...   |   ...   |   ...   |   ...   |     ...      |    0    |   1
+        //  a_{n-1} | b_{n-1} | s_{n-1} | c_{n-1} | 2^limb_bits  |    0    |   1
+        meta.create_gate("carry start", |meta| {
+            let q = meta.query_selector(q_start);
+            let carry = meta.query_advice(carry, Rotation::cur());
+            Constraints::with_selector(q, [("c_{-1} = 0", carry)])
+        });
+
+        meta.create_gate("limb add", |meta| {
+            let q = meta.query_selector(q_add);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let sum = meta.query_advice(sum, Rotation::cur());
+            let carry_in = meta.query_advice(carry, Rotation::prev());
+            let carry = meta.query_advice(carry, Rotation::cur());
+            let base = meta.query_fixed(base, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "a + b + c_{i-1} = s + base * c",
+                        a + b + carry_in - (sum + base * carry.clone()),
+                    ),
+                    (
+                        "c is boolean",
+                        carry.clone() * (Expression::Constant(F::ONE) - carry),
+                    ),
+                ],
+            )
+        });
+
+        Self {
+            a,
+            b,
+            sum,
+            carry,
+            base,
+            q_start,
+            q_add,
+            range,
+        }
+    }
+}
+
+type SumAndCarry<F> = (Vec<AssignedCell<F, F>>, AssignedCell<F, F>);
+
+#[derive(Debug, Clone)]
+struct LimbAddChip<F: PrimeField> {
+    config: LimbAddConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> LimbAddChip<F> {
+    fn construct(config: LimbAddConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> LimbAddConfig<F> {
+        LimbAddConfig::configure(meta)
+    }
+
+    /// Returns the limbs of `a + b`, least significant first, and the carry out of the top limb. `a_limbs` and
+    /// `b_limbs` must be the same length and each limb must fit in `limb_bits` bits, which is at most 127.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        a_limbs: &[AssignedCell<F, F>],
+        b_limbs: &[AssignedCell<F, F>],
+        limb_bits: usize,
+    ) -> Result<SumAndCarry<F>, Error> {
+        // Two limbs and a carry have to add up without overflowing the u128 the witness is computed in
+        assert!(
+            limb_bits <= 127,
+            "The sum of two {}-bit limbs doesn't fit in a u128",
+            limb_bits
+        );
+        let mut carry = Value::known(0u128);
+        let carries = a_limbs
+            .iter()
+            .zip(b_limbs)
+            .map(|(a, b)| {
+                let limb_sum = a
+                    .value()
+                    .zip(b.value())
+                    .zip(carry)
+                    .map(|((a, b), carry)| field_to_u128(*a) + field_to_u128(*b) + carry);
+                carry = limb_sum.map(|limb_sum| limb_sum >> limb_bits);
+                carry.map(|carry| F::from_u128(carry))
+            })
+            .collect();
+        self.assign_carries(layouter, a_limbs, b_limbs, limb_bits, carries)
+    }
+
+    // Like assign, but with the given witness for the carries, and each sum limb whatever the gate then needs
+    fn assign_carries(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a_limbs: &[AssignedCell<F, F>],
+        b_limbs: &[AssignedCell<F, F>],
+        limb_bits: usize,
+        carries: Vec<Value<F>>,
+    ) -> Result<SumAndCarry<F>, Error> {
+        assert_eq!(
+            a_limbs.len(),
+            b_limbs.len(),
+            "The addends have different numbers of limbs"
+        );
+        assert!(!a_limbs.is_empty(), "The addends have no limbs");
+        assert!(
+            limb_bits < F::CAPACITY as usize,
+            "A sum of {}-bit limbs can wrap around the field",
+            limb_bits
+        );
+        let config = &self.config;
+        let base = pow2::<F>(limb_bits);
+        let (sums, carry) = layouter.assign_region(
+            || "limb add",
+            |mut region| {
+                config.q_start.enable(&mut region, 0)?;
+                let mut carry =
+                    region.assign_advice(|| "c_{-1}", config.carry, 0, || Value::known(F::ZERO))?;
+                let mut sums = vec![];
+                for (i, ((a, b), carry_out)) in
+                    a_limbs.iter().zip(b_limbs).zip(&carries).enumerate()
+                {
+                    let offset = i + 1;
+                    config.q_add.enable(&mut region, offset)?;
+                    let a = a.copy_advice(|| format!("a_{}", i), &mut region, config.a, offset)?;
+                    let b = b.copy_advice(|| format!("b_{}", i), &mut region, config.b, offset)?;
+                    region.assign_fixed(|| "base", config.base, offset, || Value::known(base))?;
+                    let sum = a.value().copied() + b.value().copied() + carry.value().copied()
+                        - carry_out.map(|carry_out| base * carry_out);
+                    sums.push(region.assign_advice(
+                        || format!("s_{}", i),
+                        config.sum,
+                        offset,
+                        || sum,
+                    )?);
+                    carry = region.assign_advice(
+                        || format!("c_{}", i),
+                        config.carry,
+                        offset,
+                        || *carry_out,
+                    )?;
+                }
+                Ok((sums, carry))
+            },
+        )?;
+        for (i, sum) in sums.iter().enumerate() {
+            config.range.assign(
+                layouter.namespace(|| format!("range check s_{}", i)),
+                sum,
+                limb_bits,
+            )?;
+        }
+        Ok((sums, carry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, run_mock_auto, ForgeableCircuit, Forged};
+
+    const LIMB_BITS: usize = 8;
+    const NUM_LIMBS: usize = 3;
+
+    // Adds two numbers of 3 `limb_bits`-bit limbs, exposing the sum limbs at instance rows 0..3 and the carry at
+    // row 3. The witness for the carries can be forged.
+    #[derive(Default)]
+    struct LimbAddCircuit<F: PrimeField> {
+        a: [Value<F>; NUM_LIMBS],
+        b: [Value<F>; NUM_LIMBS],
+        limb_bits: usize,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for LimbAddCircuit<F> {
+        type Config = (LimbAddConfig<F>, Column<Advice>, Column<Instance>);
+        type Witness = Vec<Value<F>>;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                limb_bits: self.limb_bits,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (LimbAddChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            carries: Option<&Vec<Value<F>>>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "Load limbs",
+                |mut region| {
+                    let mut load = |name: &str, limbs: &[Value<F>], start: usize| {
+                        limbs
+                            .iter()
+                            .enumerate()
+                            .map(|(i, limb)| {
+                                region.assign_advice(
+                                    || format!("{}_{}", name, i),
+                                    input,
+                                    start + i,
+                                    || *limb,
+                                )
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                    };
+                    let a = load("a", &self.a, 0)?;
+                    let b = load("b", &self.b, NUM_LIMBS)?;
+                    Ok((a, b))
+                },
+            )?;
+            let chip = LimbAddChip::construct(config);
+            let namespace = layouter.namespace(|| "a + b");
+            let (sums, carry) = match carries {
+                Some(carries) => {
+                    chip.assign_carries(namespace, &a, &b, self.limb_bits, carries.clone())
+                }
+                None => chip.assign(namespace, &a, &b, self.limb_bits),
+            }?;
+            for (i, sum) in sums.iter().enumerate() {
+                layouter.constrain_instance(sum.cell(), instance, i)?;
+            }
+            layouter.constrain_instance(carry.cell(), instance, NUM_LIMBS)
+        }
+    }
+
+    fn limbs(value: u64) -> [Value<Fp>; NUM_LIMBS] {
+        [0, 1, 2].map(|i| Value::known(Fp::from((value >> (i * LIMB_BITS)) & 0xff)))
+    }
+
+    // The limbs of the sum and the carry out, as the public inputs
+    fn public(sum: u64) -> [Fp; NUM_LIMBS + 1] {
+        [0, 1, 2, 3].map(|i| Fp::from((sum >> (i * LIMB_BITS)) & 0xff))
+    }
+
+    fn circuit(a: u64, b: u64) -> Forged<Fp, LimbAddCircuit<Fp>> {
+        Forged::honest(LimbAddCircuit {
+            a: limbs(a),
+            b: limbs(b),
+            limb_bits: LIMB_BITS,
+        })
+    }
+
+    #[test]
+    fn test_limb_add_without_carries() {
+        for (a, b) in [(0, 0), (0x010203, 0x102030), (0x7f7f7f, 0x808080)] {
+            assert!(run(&circuit(a, b), &public(a + b)), "{:x} {:x}", a, b);
+            assert!(!run(&circuit(a, b), &public(a + b + 1)), "{:x} {:x}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_limb_add_with_carries() {
+        // Carries out of one limb, through every limb, and out of the top limb
+        for (a, b) in [
+            (0x0000ff, 0x000001),
+            (0x00ffff, 0x000001),
+            (0xffffff, 0x000001),
+            (0xffffff, 0xffffff),
+            (0x123456, 0xfedcba),
+        ] {
+            assert!(run(&circuit(a, b), &public(a + b)), "{:x} {:x}", a, b);
+            assert!(!run(&circuit(a, b), &public(a + b - 1)), "{:x} {:x}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_limb_add_malicious_carry() {
+        let with_carries = |a, b, carries: [Fp; NUM_LIMBS]| {
+            circuit(a, b).forge(carries.map(Value::known).to_vec())
+        };
+        // The honest carries pass through assign_carries
+        assert!(run(
+            &with_carries(0xff, 0x01, [Fp::ONE, Fp::ZERO, Fp::ZERO]),
+            &public(0x100)
+        ));
+        // Dropping a carry leaves a sum limb of 256, and adding one a negative sum limb, which the range check
+        // rejects whatever the public inputs claim
+        for (carries, claimed) in [
+            ([Fp::ZERO, Fp::ZERO, Fp::ZERO], 0x100),
+            ([Fp::ZERO, Fp::ZERO, Fp::ZERO], 0x000),
+            ([Fp::ONE, Fp::ONE, Fp::ZERO], 0x100),
+            ([-Fp::ONE, Fp::ZERO, Fp::ZERO], 0x000),
+        ] {
+            assert!(
+                !run(&with_carries(0xff, 0x01, carries), &public(claimed)),
+                "{:?}",
+                carries
+            );
+        }
+
+        // Carries that aren't bits can make every sum limb fit, e.g. 0xff + 0x01 as limbs 1, 0, 0 with
+        // c_0 = (0x100 - 1) / 256, so only the boolean check rejects them
+        let base_inv = Fp::from(256).invert().unwrap();
+        let c_0 = (Fp::from(0x100) - Fp::ONE) * base_inv;
+        let c_1 = c_0 * base_inv;
+        let c_2 = c_1 * base_inv;
+        let circuit = with_carries(0xff, 0x01, [c_0, c_1, c_2]);
+        let public = vec![vec![Fp::ONE, Fp::ZERO, Fp::ZERO, c_2]];
+        let failures = run_mock_auto(&circuit, public).verify().unwrap_err();
+        assert!(!failures.is_empty());
+        assert!(failures.iter().all(|failure| matches!(
+            failure,
+            VerifyFailure::ConstraintNotSatisfied { constraint, .. }
+                if constraint.to_string().contains("c is boolean")
+        )));
+    }
+
+    // Adds 2^127 - 1 to itself in both low limbs, the largest limbs the u128 witness can add
+    fn wide_circuit(limb_bits: usize) -> Forged<Fp, LimbAddCircuit<Fp>> {
+        let max = Value::known(Fp::from_u128((1 << 127) - 1));
+        Forged::honest(LimbAddCircuit {
+            a: [max, max, Value::known(Fp::ZERO)],
+            b: [max, max, Value::known(Fp::ZERO)],
+            limb_bits,
+        })
+    }
+
+    #[test]
+    fn test_limb_add_widest_limbs() {
+        // Both of the low limbs carry: 2 * (2^127 - 1) = 2^127 - 2 + 2^127, and then 2^127 - 1 with the carry in
+        let sums = [(1 << 127) - 2, (1 << 127) - 1, 1, 0].map(Fp::from_u128);
+        assert!(run(&wide_circuit(127), &sums));
+        let wrong = [(1 << 127) - 1, (1 << 127) - 1, 0, 0].map(Fp::from_u128);
+        assert!(!run(&wide_circuit(127), &wrong));
+    }
+
+    #[test]
+    #[should_panic(expected = "The sum of two 128-bit limbs doesn't fit in a u128")]
+    fn test_limb_add_limbs_too_wide() {
+        run_mock_auto(&wide_circuit(128), vec![vec![Fp::ZERO; NUM_LIMBS + 1]]);
+    }
+}
@@ -0,0 +1,243 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig,
+    decompose_range_check::field_to_u128,
+    less_than::{LessThanChip, LessThanConfig},
+};
+
+/// Reduces `value` modulo `m` by witnessing a quotient `q` and remainder `r` such that
+///     $$value = q \cdot m + r, \quad r < m, \quad q < 2^{N}$$
+/// `r` is range-checked to N_BITS bits and then compared against `m` with the less-than gadget,
+/// which assumes `m` also fits in N_BITS bits. Since both `q` and `r` are bounded,
+/// `q * m + r` can't wrap around the field, so the reduction is unique.
+#[derive(Debug, Clone)]
+pub(crate) struct ModReduceConfig<F: PrimeField, const N_BITS: usize> {
+    value: Column<Advice>,
+    m: Column<Advice>,
+    q: Column<Advice>,
+    r: Column<Advice>,
+    q_mod_reduce: Selector,
+    range: BitsRangeCheckConfig<F>,
+    less_than: LessThanConfig<F, N_BITS>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> ModReduceConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let m = meta.advice_column();
+        let q = meta.advice_column();
+        let r = meta.advice_column();
+        let q_mod_reduce = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        let less_than = LessThanChip::configure(meta);
+        for column in [value, m, q, r] {
+            meta.enable_equality(column);
+        }
+
+        //     value   |    m    |    q    |    r    | q_mod_reduce
+        //    ------------------------------------------------------
+        //       v     |    m    |    q    |    r    |      1
+        meta.create_gate("mod reduce", |meta| {
+            let s = meta.query_selector(q_mod_reduce);
+            let value = meta.query_advice(value, Rotation::cur());
+            let m = meta.query_advice(m, Rotation::cur());
+            let q = meta.query_advice(q, Rotation::cur());
+            let r = meta.query_advice(r, Rotation::cur());
+            Constraints::with_selector(s, [("value = q * m + r", value - (q * m + r))])
+        });
+
+        Self {
+            value,
+            m,
+            q,
+            r,
+            q_mod_reduce,
+            range,
+            less_than,
+        }
+    }
+}
+
+pub(crate) type QuotientAndRemainder<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+#[derive(Debug, Clone)]
+pub(crate) struct ModReduceChip<F: PrimeField, const N_BITS: usize> {
+    config: ModReduceConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> ModReduceChip<F, N_BITS> {
+    pub(crate) fn construct(config: ModReduceConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> ModReduceConfig<F, N_BITS> {
+        ModReduceConfig::configure(meta)
+    }
+
+    /// Returns the constrained `(q, r)` such that `value = q * m + r` and `r < m`.
+    /// The host-side division reads the low 128 bits of each input, so N_BITS must be at most 64.
+    pub(crate) fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        m: &AssignedCell<F, F>,
+    ) -> Result<QuotientAndRemainder<F>, Error> {
+        let q_and_r = value.value().zip(m.value()).map(|(value, m)| {
+            let (value, m) = (field_to_u128(*value), field_to_u128(*m));
+            // A zero modulus has no valid remainder, so this witness fails the r < m check
+            let (q, r) = value
+                .checked_div(m)
+                .zip(value.checked_rem(m))
+                .unwrap_or((0, value));
+            (F::from_u128(q), F::from_u128(r))
+        });
+        self.assign_q_and_r(layouter, value, m, q_and_r)
+    }
+
+    pub(crate) fn assign_q_and_r(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        m: &AssignedCell<F, F>,
+        q_and_r: Value<(F, F)>,
+    ) -> Result<QuotientAndRemainder<F>, Error> {
+        let config = &self.config;
+        let (m, q, r) = layouter.assign_region(
+            || "mod reduce",
+            |mut region| {
+                let offset = 0;
+                config.q_mod_reduce.enable(&mut region, offset)?;
+                value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                let m = m.copy_advice(|| "m", &mut region, config.m, offset)?;
+                let q =
+                    region.assign_advice(|| "q", config.q, offset, || q_and_r.map(|(q, _)| q))?;
+                let r =
+                    region.assign_advice(|| "r", config.r, offset, || q_and_r.map(|(_, r)| r))?;
+                Ok((m, q, r))
+            },
+        )?;
+
+        config
+            .range
+            .assign(layouter.namespace(|| "range check q"), &q, N_BITS)?;
+        config
+            .range
+            .assign(layouter.namespace(|| "range check r"), &r, N_BITS)?;
+        LessThanChip::construct(config.less_than.clone()).assert_less_than(
+            layouter.namespace(|| "r < m"),
+            &r,
+            &m,
+        )?;
+        Ok((q, r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::*;
+    use crate::testing::{ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 8;
+
+    // Loads `value` and `m`, then reduces. The witness for `q` and `r` can be forged.
+    #[derive(Default)]
+    struct ModReduceCircuit<F: PrimeField> {
+        value: Value<F>,
+        m: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for ModReduceCircuit<F> {
+        type Config = ModReduceConfig<F, N_BITS>;
+        type Witness = Value<(F, F)>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            ModReduceChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+            q_and_r: Option<&Value<(F, F)>>,
+        ) -> Result<(), Error> {
+            let (value, m) = layouter.assign_region(
+                || "Load value and m",
+                |mut region| {
+                    let value = region.assign_advice(|| "value", config.value, 0, || self.value)?;
+                    let m = region.assign_advice(|| "m", config.m, 0, || self.m)?;
+                    Ok((value, m))
+                },
+            )?;
+            let chip = ModReduceChip::construct(config);
+            match q_and_r {
+                Some(q_and_r) => {
+                    chip.assign_q_and_r(layouter.namespace(|| "mod"), &value, &m, *q_and_r)
+                }
+                None => chip.assign(layouter.namespace(|| "mod"), &value, &m),
+            }?;
+            Ok(())
+        }
+    }
+
+    fn circuit(value: u64, m: u64) -> Forged<Fp, ModReduceCircuit<Fp>> {
+        Forged::honest(ModReduceCircuit {
+            value: Value::known(Fp::from(value)),
+            m: Value::known(Fp::from(m)),
+        })
+    }
+
+    #[test]
+    fn test_mod_reduce_pass() {
+        let k = 7;
+        // Includes value < m (q = 0), exact multiples, and m = 1
+        for (value, m) in [
+            (17, 5),
+            (3, 10),
+            (0, 7),
+            (40, 8),
+            (255, 16),
+            (200, 1),
+            (65279, 255),
+        ] {
+            let prover = MockProver::run(k, &circuit(value, m), vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_mod_reduce_fail() {
+        let k = 7;
+        // A zero modulus has no remainder, and q = 65280 / 255 = 256 doesn't fit in N_BITS
+        for (value, m) in [(17, 0), (65280, 255)] {
+            let prover = MockProver::run(k, &circuit(value, m), vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_mod_reduce_malicious_remainder() {
+        let k = 7;
+        // 17 = 2 * 5 + 7 satisfies the reduction gate, but r >= m
+        let malicious = circuit(17, 5).forge(Value::known((Fp::from(2), Fp::from(7))));
+        let prover = MockProver::run(k, &malicious, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // r = m is also rejected
+        let malicious = circuit(15, 5).forge(Value::known((Fp::from(2), Fp::from(5))));
+        let prover = MockProver::run(k, &malicious, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::decompose_range_check::{plan_decomposition, DecomposeRangeCheckConfig};
+
+/// Finds the position of the most significant set bit of an N_BITS-bit value, i.e. $\lfloor \log_2 v \rfloor$,
+/// or N_BITS for zero, which has no set bit. The decompose chip splits the value into 1-bit windows, which also
+/// range-checks it, and the bits are then walked from the top down. At bit $b_i$, $s_{i+1}$ is whether any
+/// higher bit is set, and the MSB flag is
+///     $$f_i = b_i \cdot (1 - s_{i+1})$$
+/// i.e. all higher bits are zero and this bit is one, which holds for at most one bit. The position is carried
+/// down as $m_i = m_{i+1} + f_i \cdot (i - m_{i+1})$, starting from the sentinel $m_N = N$.
+#[derive(Debug, Clone)]
+struct MsbConfig<F: PrimeField, const N_BITS: usize> {
+    bit: Column<Advice>,
+    seen: Column<Advice>,
+    first: Column<Advice>,
+    msb: Column<Advice>,
+    position: Column<Fixed>,
+    q_init: Selector,
+    q_msb: Selector,
+    decompose: DecomposeRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> MsbConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let bit = meta.advice_column();
+        let seen = meta.advice_column();
+        let first = meta.advice_column();
+        let msb = meta.advice_column();
+        let position = meta.fixed_column();
+        let q_init = meta.selector();
+        let q_msb = meta.selector();
+        let decompose =
+            DecomposeRangeCheckConfig::configure_with_plan(meta, plan_decomposition(N_BITS, 1));
+        meta.enable_equality(bit);
+        meta.enable_equality(msb);
+
+        //      bit     |   seen    |  first  |     msb      | position | q_init | q_msb
+        //    ---------------------------------------------------------------------------
+        //              |     0     |         |      N       |          |   1    |   0
+        //   b_{N-1}    | s_{N-1}   | f_{N-1} |   m_{N-1}    |   N-1    |   0    |   1
+        //     ...      |    ...    |   ...   |     ...      |   ...    |   0    |   1
+        //     b_0      |    s_0    |   f_0   |     m_0      |    0     |   0    |   1
+        meta.create_gate("msb init", |meta| {
+            let q = meta.query_selector(q_init);
+            let seen = meta.query_advice(seen, Rotation::cur());
+            let msb = meta.query_advice(msb, Rotation::cur());
+            let sentinel = Expression::Constant(F::from(N_BITS as u64));
+            Constraints::with_selector(q, [("s_N = 0", seen), ("m_N = N", msb - sentinel)])
+        });
+
+        meta.create_gate("msb", |meta| {
+            let q = meta.query_selector(q_msb);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let seen_above = meta.query_advice(seen, Rotation::prev());
+            let seen = meta.query_advice(seen, Rotation::cur());
+            let first = meta.query_advice(first, Rotation::cur());
+            let msb_above = meta.query_advice(msb, Rotation::prev());
+            let msb = meta.query_advice(msb, Rotation::cur());
+            let position = meta.query_fixed(position, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "higher bits zero, this bit one",
+                        first.clone() - bit * (one - seen_above.clone()),
+                    ),
+                    ("s_i = s_{i+1} + f_i", seen - (seen_above + first.clone())),
+                    (
+                        "m_i = m_{i+1} + f_i * (i - m_{i+1})",
+                        msb - (msb_above.clone() + first * (position - msb_above)),
+                    ),
+                ],
+            )
+        });
+
+        Self {
+            bit,
+            seen,
+            first,
+            msb,
+            position,
+            q_init,
+            q_msb,
+            decompose,
+        }
+    }
+
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.decompose.load_table(layouter)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MsbChip<F: PrimeField, const N_BITS: usize> {
+    config: MsbConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> MsbChip<F, N_BITS> {
+    fn construct(config: MsbConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MsbConfig<F, N_BITS> {
+        MsbConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to the position of the highest set bit of `value`, which must fit in N_BITS
+    /// bits, or to N_BITS if `value` is zero.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let decomposed =
+            config
+                .decompose
+                .assign_cell(layouter.namespace(|| "bits"), None, value)?;
+        layouter.assign_region(
+            || "msb",
+            |mut region| {
+                config.q_init.enable(&mut region, 0)?;
+                region.assign_advice(|| "s_N", config.seen, 0, || Value::known(F::ZERO))?;
+                let mut msb = region.assign_advice(
+                    || "m_N",
+                    config.msb,
+                    0,
+                    || Value::known(F::from(N_BITS as u64)),
+                )?;
+                let mut seen = Value::known(false);
+                for (i, bit) in decomposed.windows.iter().enumerate().rev() {
+                    let offset = N_BITS - i;
+                    config.q_msb.enable(&mut region, offset)?;
+                    region.assign_fixed(
+                        || format!("position {}", i),
+                        config.position,
+                        offset,
+                        || Value::known(F::from(i as u64)),
+                    )?;
+                    let bit = bit.0.copy_advice(
+                        || format!("b_{}", i),
+                        &mut region,
+                        config.bit,
+                        offset,
+                    )?;
+                    let first = bit
+                        .value()
+                        .zip(seen)
+                        .map(|(bit, seen)| *bit == F::ONE && !seen);
+                    seen = seen.zip(first).map(|(seen, first)| seen || first);
+                    let msb_value = msb.value().copied().zip(first).map(|(msb, first)| {
+                        if first {
+                            F::from(i as u64)
+                        } else {
+                            msb
+                        }
+                    });
+                    region.assign_advice(
+                        || format!("f_{}", i),
+                        config.first,
+                        offset,
+                        || first.map(|first| F::from(first as u64)),
+                    )?;
+                    region.assign_advice(
+                        || format!("s_{}", i),
+                        config.seen,
+                        offset,
+                        || seen.map(|seen| F::from(seen as u64)),
+                    )?;
+                    msb = region.assign_advice(
+                        || format!("m_{}", i),
+                        config.msb,
+                        offset,
+                        || msb_value,
+                    )?;
+                }
+                Ok(msb)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const N_BITS: usize = 8;
+
+    // Finds the MSB of the private value, exposing it at instance row 0
+    #[derive(Default)]
+    struct MsbCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MsbCircuit<F> {
+        type Config = (MsbConfig<F, N_BITS>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (MsbChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let msb = MsbChip::construct(config).assign(layouter.namespace(|| "msb"), &value)?;
+            layouter.constrain_instance(msb.cell(), instance, 0)
+        }
+    }
+
+    fn run(value: u64, msb: u64) -> bool {
+        let circuit = MsbCircuit {
+            value: Value::known(Fp::from(value)),
+        };
+        run_mock_auto(&circuit, vec![vec![Fp::from(msb)]])
+            .verify()
+            .is_ok()
+    }
+
+    // The host reference
+    fn msb(value: u64) -> u64 {
+        value.checked_ilog2().map_or(N_BITS as u64, u64::from)
+    }
+
+    #[test]
+    fn test_msb_powers_of_two() {
+        for i in 0..N_BITS as u64 {
+            assert!(run(1 << i, i), "2^{}", i);
+            assert!(!run(1 << i, i + 1), "2^{}", i);
+        }
+        // Zero has no set bit
+        assert!(run(0, N_BITS as u64));
+        assert!(!run(0, 0));
+    }
+
+    #[test]
+    fn test_msb() {
+        for value in [
+            1,
+            3,
+            5,
+            6,
+            7,
+            37,
+            100,
+            0b0111_1111,
+            0b1000_0001,
+            0b1010_1010,
+            255,
+        ] {
+            assert!(run(value, msb(value)), "{:#010b}", value);
+            // The lowest set bit, and one position off either way
+            for wrong in [
+                value.trailing_zeros() as u64,
+                msb(value) + 1,
+                msb(value).wrapping_sub(1),
+            ] {
+                if wrong != msb(value) {
+                    assert!(!run(value, wrong), "{:#010b} to {}", value, wrong);
+                }
+            }
+        }
+        assert_eq!(msb(100), 6);
+    }
+
+    #[test]
+    fn test_msb_out_of_range() {
+        // 0x180 doesn't fit in 8 bits, so neither its true MSB nor the MSB of its low byte passes
+        for claimed in [8, 7] {
+            assert!(!run(0x1_80, claimed));
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+/// Multiplies two cells, $c = a \cdot b$, and range-checks the product to `num_bits` bits with the bits range
+/// check, so a product which overflows the bound fails verification.
+/// This is the building block for fixed-point multiplication, where the product has to stay in a known width.
+///
+/// The inputs are range-checked to `num_bits` bits too. Otherwise a product could wrap around the field and land
+/// back in range, e.g. $2^{-1} \cdot 2 = 1$. With both inputs below $2^{num\_bits}$ and
+/// `2 * num_bits < F::CAPACITY`, $a \cdot b$ can't reach the modulus. An honest product that fits in `num_bits`
+/// bits has inputs that fit too, unless one of them is zero.
+#[derive(Debug, Clone)]
+struct MulRangeConfig<F: PrimeField> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    q_mul: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> MulRangeConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let q_mul = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [a, b, c] {
+            meta.enable_equality(column);
+        }
+
+        //     a    |    b    |    c    | q_mul
+        //    -------------------------------------
+        //     a    |    b    |  a * b  |   1
+        meta.create_gate("mul", |meta| {
+            let q = meta.query_selector(q_mul);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            Constraints::with_selector(q, [("c = a * b", c - a * b)])
+        });
+
+        Self {
+            a,
+            b,
+            c,
+            q_mul,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MulRangeChip<F: PrimeField> {
+    config: MulRangeConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> MulRangeChip<F> {
+    fn construct(config: MulRangeConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MulRangeConfig<F> {
+        MulRangeConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to `a * b`, which must fit in `num_bits` bits, as must `a` and `b`.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let c = a.value().zip(b.value()).map(|(a, b)| *a * *b);
+        self.assign_product(layouter, a, b, c, num_bits)
+    }
+
+    fn assign_product(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        c: Value<F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(
+            2 * num_bits < F::CAPACITY as usize,
+            "A product of {}-bit values can wrap around the field",
+            num_bits
+        );
+        let config = &self.config;
+        let c = layouter.assign_region(
+            || "mul",
+            |mut region| {
+                let offset = 0;
+                config.q_mul.enable(&mut region, offset)?;
+                a.copy_advice(|| "a", &mut region, config.a, offset)?;
+                b.copy_advice(|| "b", &mut region, config.b, offset)?;
+                region.assign_advice(|| "c", config.c, offset, || c)
+            },
+        )?;
+        config
+            .range
+            .assign(layouter.namespace(|| "range check a"), a, num_bits)?;
+        config
+            .range
+            .assign(layouter.namespace(|| "range check b"), b, num_bits)?;
+        config
+            .range
+            .assign(layouter.namespace(|| "range check c"), &c, num_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::pasta::Fp;
+
+    use super::*;
+    use crate::testing::{run_mock_auto, ForgeableCircuit, Forged};
+
+    const NUM_BITS: usize = 8;
+
+    // Loads `a` and `b`, then multiplies them. The witness for `c` can be forged.
+    #[derive(Default)]
+    struct MulRangeCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for MulRangeCircuit<F> {
+        type Config = (MulRangeConfig<F>, Column<Advice>);
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (MulRangeChip::configure(meta), input)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input): Self::Config,
+            mut layouter: impl Layouter<F>,
+            c: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "Load a and b",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            let chip = MulRangeChip::construct(config);
+            let layouter = layouter.namespace(|| "a * b");
+            match c {
+                Some(c) => chip.assign_product(layouter, &a, &b, *c, NUM_BITS),
+                None => chip.assign(layouter, &a, &b, NUM_BITS),
+            }?;
+            Ok(())
+        }
+    }
+
+    fn circuit(a: u64, b: u64) -> Forged<Fp, MulRangeCircuit<Fp>> {
+        Forged::honest(MulRangeCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        })
+    }
+
+    #[test]
+    fn test_mul_range() {
+        for (a, b) in [(0, 200), (1, 255), (15, 17), (3, 5)] {
+            run_mock_auto(&circuit(a, b), vec![]).assert_satisfied();
+        }
+        // Products of 256 and up overflow 8 bits
+        for (a, b) in [(16, 16), (2, 128), (255, 255)] {
+            assert!(run_mock_auto(&circuit(a, b), vec![]).verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_mul_range_malicious_product() {
+        // 12 is in range, but isn't 3 * 5
+        let malicious = circuit(3, 5).forge(Value::known(Fp::from(12)));
+        assert!(run_mock_auto(&malicious, vec![]).verify().is_err());
+
+        let honest = circuit(3, 5).forge(Value::known(Fp::from(15)));
+        run_mock_auto(&honest, vec![]).assert_satisfied();
+    }
+
+    #[test]
+    fn test_mul_range_wrapped_product() {
+        // 2^-1 * 2 = 1 is in range, but 2^-1 isn't
+        let wrapped = Forged::honest(MulRangeCircuit {
+            a: Value::known(Fp::from(2).invert().unwrap()),
+            b: Value::known(Fp::from(2)),
+        });
+        assert!(run_mock_auto(&wrapped, vec![]).verify().is_err());
+        // An 8-bit product with a wide input is rejected too, when the other input is zero
+        assert!(run_mock_auto(&circuit(0, 256), vec![]).verify().is_err());
+    }
+}
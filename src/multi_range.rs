@@ -0,0 +1,406 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig, boolean::bool_check,
+    decompose_range_check::field_to_u128,
+};
+
+/// Proves a value lies in at least one of several intervals `lo <= value < hi`, e.g. that an enum tag is one of
+/// the valid tags. The intervals are fixed when the circuit is laid out, one row each, and the prover witnesses
+/// a one-hot choice $s_j$ of which one the value is in. Running sums select its bounds,
+///     $$lo = \sum_j s_j \cdot lo_j, \quad hi = \sum_j s_j \cdot hi_j$$
+/// and its index, with $\sum_j s_j = 1$. Then $value - lo$ and $hi - 1 - value$ are range-checked to N_BITS bits.
+/// Both bounds fit in N_BITS bits, so the two differences sum to less than $2^{N\_BITS}$ and neither can wrap
+/// around the field, which puts the value in the chosen interval.
+#[derive(Debug, Clone)]
+struct MultiRangeConfig<F: PrimeField, const N_BITS: usize> {
+    chosen: Column<Advice>,
+    count: Column<Advice>,
+    lo: Column<Advice>,
+    hi: Column<Advice>,
+    index: Column<Advice>,
+    value: Column<Advice>,
+    above_lo: Column<Advice>,
+    below_hi: Column<Advice>,
+    interval_lo: Column<Fixed>,
+    interval_hi: Column<Fixed>,
+    position: Column<Fixed>,
+    q_init: Selector,
+    q_interval: Selector,
+    q_bounds: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> MultiRangeConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let chosen = meta.advice_column();
+        let count = meta.advice_column();
+        let lo = meta.advice_column();
+        let hi = meta.advice_column();
+        let index = meta.advice_column();
+        let value = meta.advice_column();
+        let above_lo = meta.advice_column();
+        let below_hi = meta.advice_column();
+        let interval_lo = meta.fixed_column();
+        let interval_hi = meta.fixed_column();
+        let position = meta.fixed_column();
+        let q_init = meta.selector();
+        let q_interval = meta.selector();
+        let q_bounds = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [index, value, above_lo, below_hi] {
+            meta.enable_equality(column);
+        }
+
+        //   s   | count | lo  | hi  | index | value | above_lo | below_hi | lo_j, hi_j |  j  | q_init | q_interval | q_bounds
+        //  -----------------------------------------------------------------------------------------------------------------
+        //       |   0   |  0  |  0  |   0   |       |          |          |            |     |   1    |     0      |    0
+        //  s_0  |  ...  | ... | ... |  ...  |       |          |          | lo_0, hi_0 |  0  |   0    |     1      |    0
+        //  ...  |  ...  | ... | ... |  ...  |       |          |          |    ...     | ... |   0    |     1      |    0
+        // s_n-1 |   1   | lo  | hi  |   i   |   v   |  v - lo  | hi-1-v   |    ...     | n-1 |   0    |     1      |    1
+        meta.create_gate("multi range init", |meta| {
+            let q = meta.query_selector(q_init);
+            Constraints::with_selector(
+                q,
+                [
+                    ("count = 0", meta.query_advice(count, Rotation::cur())),
+                    ("lo = 0", meta.query_advice(lo, Rotation::cur())),
+                    ("hi = 0", meta.query_advice(hi, Rotation::cur())),
+                    ("index = 0", meta.query_advice(index, Rotation::cur())),
+                ],
+            )
+        });
+
+        meta.create_gate("multi range", |meta| {
+            let q = meta.query_selector(q_interval);
+            let chosen = meta.query_advice(chosen, Rotation::cur());
+            let interval_lo = meta.query_fixed(interval_lo, Rotation::cur());
+            let interval_hi = meta.query_fixed(interval_hi, Rotation::cur());
+            let position = meta.query_fixed(position, Rotation::cur());
+            let mut step = |column: Column<Advice>, add: Expression<F>| {
+                meta.query_advice(column, Rotation::cur())
+                    - (meta.query_advice(column, Rotation::prev()) + chosen.clone() * add)
+            };
+            let count = step(count, Expression::Constant(F::ONE));
+            let lo = step(lo, interval_lo);
+            let hi = step(hi, interval_hi);
+            let index = step(index, position);
+            Constraints::with_selector(
+                q,
+                [
+                    ("s_j is boolean", bool_check(chosen.clone())),
+                    ("count_j = count_{j-1} + s_j", count),
+                    ("lo_j = lo_{j-1} + s_j * lo", lo),
+                    ("hi_j = hi_{j-1} + s_j * hi", hi),
+                    ("index_j = index_{j-1} + s_j * j", index),
+                ],
+            )
+        });
+
+        meta.create_gate("multi range bounds", |meta| {
+            let q = meta.query_selector(q_bounds);
+            let count = meta.query_advice(count, Rotation::cur());
+            let lo = meta.query_advice(lo, Rotation::cur());
+            let hi = meta.query_advice(hi, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+            let above_lo = meta.query_advice(above_lo, Rotation::cur());
+            let below_hi = meta.query_advice(below_hi, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                q,
+                [
+                    ("one interval chosen", count - one.clone()),
+                    ("above_lo = value - lo", above_lo - (value.clone() - lo)),
+                    ("below_hi = hi - 1 - value", below_hi - (hi - one - value)),
+                ],
+            )
+        });
+
+        Self {
+            chosen,
+            count,
+            lo,
+            hi,
+            index,
+            value,
+            above_lo,
+            below_hi,
+            interval_lo,
+            interval_hi,
+            position,
+            q_init,
+            q_interval,
+            q_bounds,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MultiRangeChip<F: PrimeField, const N_BITS: usize> {
+    config: MultiRangeConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> MultiRangeChip<F, N_BITS> {
+    fn construct(config: MultiRangeConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MultiRangeConfig<F, N_BITS> {
+        MultiRangeConfig::configure(meta)
+    }
+
+    /// Constrains `value` to lie in one of the half-open intervals `lo..hi` in `ranges`, and returns a cell
+    /// holding the index of the interval it was shown to be in. Where intervals overlap, that's the first one
+    /// containing the value. Each interval must be non-empty and end at most at `2^N_BITS`.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        ranges: &[(u64, u64)],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let choice = value.value().map(|value| {
+            let value = field_to_u128(*value);
+            ranges
+                .iter()
+                .position(|(lo, hi)| (*lo as u128..*hi as u128).contains(&value))
+        });
+        self.assign_choice(layouter, value, ranges, choice)
+    }
+
+    // Like assign, but with the given witness for which interval the value is in. None chooses no interval.
+    fn assign_choice(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        ranges: &[(u64, u64)],
+        choice: Value<Option<usize>>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!ranges.is_empty(), "No intervals to check against");
+        for (lo, hi) in ranges {
+            assert!(lo < hi, "Interval {}..{} is empty", lo, hi);
+            assert!(
+                (*hi as u128) <= 1 << N_BITS,
+                "Interval {}..{} doesn't fit in {} bits",
+                lo,
+                hi,
+                N_BITS
+            );
+        }
+
+        let config = &self.config;
+        let (index, above_lo, below_hi) = layouter.assign_region(
+            || "multi range",
+            |mut region| {
+                config.q_init.enable(&mut region, 0)?;
+                let zero = || Value::known(F::ZERO);
+                region.assign_advice(|| "count", config.count, 0, zero)?;
+                let mut lo = region.assign_advice(|| "lo", config.lo, 0, zero)?;
+                let mut hi = region.assign_advice(|| "hi", config.hi, 0, zero)?;
+                let mut index = region.assign_advice(|| "index", config.index, 0, zero)?;
+
+                for (j, (interval_lo, interval_hi)) in ranges.iter().enumerate() {
+                    let offset = j + 1;
+                    config.q_interval.enable(&mut region, offset)?;
+                    let fixed = [
+                        (config.interval_lo, *interval_lo),
+                        (config.interval_hi, *interval_hi),
+                        (config.position, j as u64),
+                    ];
+                    for (column, constant) in fixed {
+                        region.assign_fixed(
+                            || format!("interval {}", j),
+                            column,
+                            offset,
+                            || Value::known(F::from(constant)),
+                        )?;
+                    }
+                    let chosen = choice.map(|choice| F::from((choice == Some(j)) as u64));
+                    region.assign_advice(
+                        || format!("s_{}", j),
+                        config.chosen,
+                        offset,
+                        || chosen,
+                    )?;
+                    let count = choice.map(|choice| F::from(choice.is_some_and(|i| i <= j) as u64));
+                    region.assign_advice(|| "count", config.count, offset, || count)?;
+                    let step = |acc: &AssignedCell<F, F>, add: u64| {
+                        acc.value()
+                            .copied()
+                            .zip(chosen)
+                            .map(|(acc, s)| acc + s * F::from(add))
+                    };
+                    lo = region.assign_advice(
+                        || "lo",
+                        config.lo,
+                        offset,
+                        || step(&lo, *interval_lo),
+                    )?;
+                    hi = region.assign_advice(
+                        || "hi",
+                        config.hi,
+                        offset,
+                        || step(&hi, *interval_hi),
+                    )?;
+                    index = region.assign_advice(
+                        || "index",
+                        config.index,
+                        offset,
+                        || step(&index, j as u64),
+                    )?;
+                }
+
+                let offset = ranges.len();
+                config.q_bounds.enable(&mut region, offset)?;
+                let value = value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                let above_lo = region.assign_advice(
+                    || "above_lo",
+                    config.above_lo,
+                    offset,
+                    || value.value().copied() - lo.value().copied(),
+                )?;
+                let below_hi = region.assign_advice(
+                    || "below_hi",
+                    config.below_hi,
+                    offset,
+                    || hi.value().copied() - Value::known(F::ONE) - value.value().copied(),
+                )?;
+                Ok((index, above_lo, below_hi))
+            },
+        )?;
+
+        for (name, cell) in [("value - lo", &above_lo), ("hi - 1 - value", &below_hi)] {
+            config.range.assign(
+                layouter.namespace(|| format!("range check {}", name)),
+                cell,
+                N_BITS,
+            )?;
+        }
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 8;
+
+    // Enum tags 1..4, 10..12 and 200..256
+    const RANGES: [(u64, u64); 3] = [(1, 4), (10, 12), (200, 256)];
+
+    // Checks the private value against RANGES, exposing the matched index at instance row 0.
+    // The witness for the choice of interval can be forged.
+    #[derive(Default)]
+    struct MultiRangeCircuit<F: PrimeField> {
+        value: Value<F>,
+        ranges: Vec<(u64, u64)>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for MultiRangeCircuit<F> {
+        type Config = (
+            MultiRangeConfig<F, N_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type Witness = Value<Option<usize>>;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                ranges: self.ranges.clone(),
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (MultiRangeChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            choice: Option<&Value<Option<usize>>>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let chip = MultiRangeChip::construct(config);
+            let namespace = layouter.namespace(|| "value in ranges");
+            let index = match choice {
+                Some(choice) => chip.assign_choice(namespace, &value, &self.ranges, *choice),
+                None => chip.assign(namespace, &value, &self.ranges),
+            }?;
+            layouter.constrain_instance(index.cell(), instance, 0)
+        }
+    }
+
+    fn circuit(value: u64, ranges: &[(u64, u64)]) -> Forged<Fp, MultiRangeCircuit<Fp>> {
+        Forged::honest(MultiRangeCircuit {
+            value: Value::known(Fp::from(value)),
+            ranges: ranges.to_vec(),
+        })
+    }
+
+    #[test]
+    fn test_multi_range() {
+        for (value, index) in [(1, 0), (3, 0), (10, 1), (11, 1), (200, 2), (255, 2)] {
+            let circuit = circuit(value, &RANGES);
+            assert!(run(&circuit, &[Fp::from(index)]), "{}", value);
+            // The right interval, but the wrong index claimed
+            assert!(!run(&circuit, &[Fp::from((index + 1) % 3)]), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_multi_range_gap() {
+        // Below, between and right at the ends of the intervals
+        for value in [0, 4, 9, 12, 199] {
+            let circuit = circuit(value, &RANGES);
+            for index in 0..3 {
+                assert!(!run(&circuit, &[Fp::from(index)]), "{}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_range_overlapping() {
+        let ranges = [(0, 10), (5, 20)];
+        // The honest prover takes the first interval a value is in, but either one proves membership
+        assert!(run(&circuit(7, &ranges), &[Fp::from(0)]));
+        for choice in [0, 1] {
+            let circuit = circuit(7, &ranges).forge(Value::known(Some(choice)));
+            assert!(run(&circuit, &[Fp::from(choice as u64)]), "{}", choice);
+        }
+        assert!(run(&circuit(15, &ranges), &[Fp::from(1)]));
+        assert!(!run(&circuit(20, &ranges), &[Fp::from(1)]));
+    }
+
+    #[test]
+    fn test_multi_range_wrong_choice() {
+        // Choosing an interval the value isn't in, or none at all
+        for choice in [Some(0), Some(2), None] {
+            let circuit = circuit(10, &RANGES).forge(Value::known(choice));
+            let index = choice.unwrap_or(0) as u64;
+            assert!(!run(&circuit, &[Fp::from(index)]), "{:?}", choice);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+/// Checks that `value` is an exact multiple of a constant $k$, the divisibility counterpart of the mod reduce
+/// chip. With $k$ in a fixed column and a witnessed quotient $q$:
+///     $$value = k \cdot q$$
+/// and $q$ range-checked to N_BITS bits. In the field every value is $k$ times something, namely
+/// $value \cdot k^{-1}$, which for a non-multiple wraps around to a huge element. Bounding $q$ rules that out,
+/// as long as $k \cdot 2^{N\_BITS}$ stays below the field's modulus.
+#[derive(Debug, Clone)]
+struct MultipleOfConfig<F: PrimeField, const N_BITS: usize> {
+    value: Column<Advice>,
+    quotient: Column<Advice>,
+    k: Column<Fixed>,
+    q_multiple: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> MultipleOfConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let quotient = meta.advice_column();
+        let k = meta.fixed_column();
+        let q_multiple = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [value, quotient] {
+            meta.enable_equality(column);
+        }
+
+        //     value   |  quotient  |   k   | q_multiple
+        //    ---------------------------------------------
+        //     value   |     q      |   k   |     1
+        meta.create_gate("multiple", |meta| {
+            let q = meta.query_selector(q_multiple);
+            let value = meta.query_advice(value, Rotation::cur());
+            let quotient = meta.query_advice(quotient, Rotation::cur());
+            let k = meta.query_fixed(k, Rotation::cur());
+            Constraints::with_selector(q, [("value = k * quotient", value - k * quotient)])
+        });
+
+        Self {
+            value,
+            quotient,
+            k,
+            q_multiple,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MultipleOfChip<F: PrimeField, const N_BITS: usize> {
+    config: MultipleOfConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> MultipleOfChip<F, N_BITS> {
+    fn construct(config: MultipleOfConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MultipleOfConfig<F, N_BITS> {
+        MultipleOfConfig::configure(meta)
+    }
+
+    /// Constrains `value` to be `k` times an N_BITS-bit quotient, returning the quotient. Only zero is a
+    /// multiple of zero.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        k: u64,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let k_inv = Option::<F>::from(F::from(k).invert()).unwrap_or(F::ZERO);
+        let quotient = value.value().map(|value| *value * k_inv);
+        self.assign_quotient(layouter, value, k, quotient)
+    }
+
+    // Like assign, but with the given witness for the quotient
+    fn assign_quotient(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        k: u64,
+        quotient: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let quotient = layouter.assign_region(
+            || "multiple",
+            |mut region| {
+                let offset = 0;
+                config.q_multiple.enable(&mut region, offset)?;
+                region.assign_fixed(|| "k", config.k, offset, || Value::known(F::from(k)))?;
+                value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                region.assign_advice(|| "quotient", config.quotient, offset, || quotient)
+            },
+        )?;
+        config.range.assign(
+            layouter.namespace(|| "range check quotient"),
+            &quotient,
+            N_BITS,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 8;
+
+    // Checks the private value is a multiple of K, exposing the quotient at instance row 0. The witness for the
+    // quotient can be forged.
+    #[derive(Default)]
+    struct MultipleOfCircuit<F: PrimeField, const K: u64> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField, const K: u64> ForgeableCircuit<F> for MultipleOfCircuit<F, K> {
+        type Config = (
+            MultipleOfConfig<F, N_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (MultipleOfChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            quotient: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let chip = MultipleOfChip::construct(config);
+            let quotient = match quotient {
+                Some(quotient) => {
+                    chip.assign_quotient(layouter.namespace(|| "multiple"), &value, K, *quotient)
+                }
+                None => chip.assign(layouter.namespace(|| "multiple"), &value, K),
+            }?;
+            layouter.constrain_instance(quotient.cell(), instance, 0)
+        }
+    }
+
+    fn circuit<const K: u64>(value: u64) -> Forged<Fp, MultipleOfCircuit<Fp, K>> {
+        Forged::honest(MultipleOfCircuit {
+            value: Value::known(Fp::from(value)),
+        })
+    }
+
+    #[test]
+    fn test_multiple_of() {
+        for value in [0, 7, 21, 700, 7 * 255] {
+            let quotient = Fp::from(value / 7);
+            assert!(run(&circuit::<7>(value), &[quotient]), "{}", value);
+            assert!(!run(&circuit::<7>(value), &[quotient + Fp::from(1)]));
+        }
+        // Everything is a multiple of 1, up to the quotient's 8 bits
+        for value in [0, 1, 200, 255] {
+            assert!(run(&circuit::<1>(value), &[Fp::from(value)]));
+        }
+        assert!(run(&circuit::<0>(0), &[Fp::from(0)]));
+    }
+
+    #[test]
+    fn test_not_multiple_of() {
+        // The only quotient that satisfies the gate is value / 7 in the field, which wraps around
+        let seven_inv = Fp::from(7).invert().unwrap();
+        for value in [1, 6, 8, 22, 699] {
+            assert!(
+                !run(&circuit::<7>(value), &[Fp::from(value) * seven_inv]),
+                "{}",
+                value
+            );
+        }
+        // A multiple of 7 whose quotient doesn't fit in 8 bits
+        assert!(!run(&circuit::<7>(7 * 256), &[Fp::from(256)]));
+        // Only zero is a multiple of zero
+        assert!(!run(&circuit::<0>(5), &[Fp::from(0)]));
+    }
+
+    #[test]
+    fn test_multiple_of_forged_quotient() {
+        // Rounding 22 / 7 down to 3 keeps the quotient in range, but 7 * 3 != 22
+        let forged = circuit::<7>(22).forge(Value::known(Fp::from(3)));
+        assert!(!run(&forged, &[Fp::from(3)]));
+    }
+}
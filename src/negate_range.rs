@@ -0,0 +1,235 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{bits_range_check::BitsRangeCheckConfig, less_than::pow2};
+
+/// Witnesses the two's-complement negation of a `bits`-bit value, $neg = -value \bmod 2^{bits}$, for signed
+/// arithmetic. With a boolean $z$ flagging $value = 0$ and $2^{bits}$ in a fixed column:
+///     $$value + neg = (1 - z) \cdot 2^{bits}, \quad z \cdot value = 0$$
+/// and both $value$ and $neg$ range-checked to `bits` bits. A zero value can't claim $z = 0$, since $neg$
+/// would have to be $2^{bits}$, which is out of range; a nonzero one can't claim $z = 1$.
+#[derive(Debug, Clone)]
+struct NegateRangeConfig<F: PrimeField> {
+    value: Column<Advice>,
+    neg: Column<Advice>,
+    is_zero: Column<Advice>,
+    modulus: Column<Fixed>,
+    q_negate: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> NegateRangeConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let neg = meta.advice_column();
+        let is_zero = meta.advice_column();
+        let modulus = meta.fixed_column();
+        let q_negate = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [value, neg] {
+            meta.enable_equality(column);
+        }
+
+        //     value   |   neg   |  is_zero  |  modulus  | q_negate
+        //    ----------------------------------------------------------
+        //     value   |   neg   |     z     |  2^bits   |    1
+        meta.create_gate("negate", |meta| {
+            let q = meta.query_selector(q_negate);
+            let value = meta.query_advice(value, Rotation::cur());
+            let neg = meta.query_advice(neg, Rotation::cur());
+            let is_zero = meta.query_advice(is_zero, Rotation::cur());
+            let modulus = meta.query_fixed(modulus, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "is_zero is boolean",
+                        is_zero.clone() * (one.clone() - is_zero.clone()),
+                    ),
+                    ("is_zero * value = 0", is_zero.clone() * value.clone()),
+                    (
+                        "value + neg = (1 - is_zero) * 2^bits",
+                        value + neg - (one - is_zero) * modulus,
+                    ),
+                ],
+            )
+        });
+
+        Self {
+            value,
+            neg,
+            is_zero,
+            modulus,
+            q_negate,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct NegateRangeChip<F: PrimeField> {
+    config: NegateRangeConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> NegateRangeChip<F> {
+    fn construct(config: NegateRangeConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> NegateRangeConfig<F> {
+        NegateRangeConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to `-value mod 2^bits`, where `value` must fit in `bits` bits.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let neg = value.value().map(|value| {
+            if value.is_zero_vartime() {
+                F::ZERO
+            } else {
+                pow2::<F>(bits) - *value
+            }
+        });
+        self.assign_neg(layouter, value, neg, bits)
+    }
+
+    // Like assign, but with the given witness for the negation
+    fn assign_neg(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        neg: Value<F>,
+        bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let (value, neg) = layouter.assign_region(
+            || "negate",
+            |mut region| {
+                let offset = 0;
+                config.q_negate.enable(&mut region, offset)?;
+                region.assign_fixed(
+                    || "2^bits",
+                    config.modulus,
+                    offset,
+                    || Value::known(pow2::<F>(bits)),
+                )?;
+                let value = value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                let is_zero = value
+                    .value()
+                    .map(|value| F::from(value.is_zero_vartime() as u64));
+                region.assign_advice(|| "is_zero", config.is_zero, offset, || is_zero)?;
+                let neg = region.assign_advice(|| "neg", config.neg, offset, || neg)?;
+                Ok((value, neg))
+            },
+        )?;
+        config
+            .range
+            .assign(layouter.namespace(|| "range check value"), &value, bits)?;
+        config
+            .range
+            .assign(layouter.namespace(|| "range check neg"), &neg, bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const BITS: usize = 8;
+
+    // Negates the private value, exposing the negation at instance row 0. The witness for the negation can be
+    // forged.
+    #[derive(Default)]
+    struct NegateRangeCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for NegateRangeCircuit<F> {
+        type Config = (NegateRangeConfig<F>, Column<Advice>, Column<Instance>);
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (NegateRangeChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            neg: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let chip = NegateRangeChip::construct(config);
+            let neg = match neg {
+                Some(neg) => chip.assign_neg(layouter.namespace(|| "negate"), &value, *neg, BITS),
+                None => chip.assign(layouter.namespace(|| "negate"), &value, BITS),
+            }?;
+            layouter.constrain_instance(neg.cell(), instance, 0)
+        }
+    }
+
+    fn circuit(value: Fp) -> Forged<Fp, NegateRangeCircuit<Fp>> {
+        Forged::honest(NegateRangeCircuit {
+            value: Value::known(value),
+        })
+    }
+
+    #[test]
+    fn test_negate() {
+        // Zero, one, either side of the sign bit at 128, and the largest value, which negates to one
+        for (value, neg) in [
+            (0, 0),
+            (1, 255),
+            (127, 129),
+            (128, 128),
+            (129, 127),
+            (255, 1),
+        ] {
+            let circuit = circuit(Fp::from(value));
+            assert!(run(&circuit, &[Fp::from(neg)]), "{}", value);
+            assert!(!run(&circuit, &[Fp::from(neg + 1)]), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_negate_out_of_range() {
+        // 256 doesn't fit in 8 bits, even though 256 + 0 would satisfy the gate
+        assert!(!run(&circuit(Fp::from(256)), &[Fp::from(0)]));
+    }
+
+    #[test]
+    fn test_negate_malicious() {
+        // The field negation, or 2^8 for zero, satisfy the sum but not the range check on neg
+        for (value, neg) in [(5, -Fp::from(5)), (0, Fp::from(256))] {
+            let malicious = circuit(Fp::from(value)).forge(Value::known(neg));
+            assert!(!run(&malicious, &[neg]), "{}", value);
+        }
+        // A negation that's in range but wrong
+        let malicious = circuit(Fp::from(5)).forge(Value::known(Fp::from(250)));
+        assert!(!run(&malicious, &[Fp::from(250)]));
+    }
+}
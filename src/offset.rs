@@ -0,0 +1,64 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::Region,
+    plonk::{Error, Selector},
+};
+use std::fmt;
+
+/// A failure to enable a selector, saying where. halo2 only reports `NotEnoughRowsAvailable` with the circuit's
+/// `k` when an offset runs past the usable rows, which is easy to hit when composing chips into one region and
+/// says nothing about which one overran. Converts back into the halo2 error, so `?` works inside regions.
+#[derive(Debug)]
+pub(crate) enum OffsetError {
+    OutOfBounds {
+        region: String,
+        offset: usize,
+        current_k: u32,
+    },
+    Other(Error),
+}
+
+impl fmt::Display for OffsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OffsetError::OutOfBounds {
+                region,
+                offset,
+                current_k,
+            } => write!(
+                f,
+                "Offset {} of region \"{}\" is past the usable rows of a 2^{} row circuit",
+                offset, region, current_k
+            ),
+            OffsetError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<OffsetError> for Error {
+    fn from(error: OffsetError) -> Self {
+        match error {
+            OffsetError::OutOfBounds { current_k, .. } => {
+                Error::NotEnoughRowsAvailable { current_k }
+            }
+            OffsetError::Other(e) => e,
+        }
+    }
+}
+
+/// Enables `selector` at `offset` of `region`, which the caller names as `region_name` for the error.
+pub(crate) fn enable_at<F: PrimeField>(
+    selector: &Selector,
+    region: &mut Region<'_, F>,
+    region_name: &str,
+    offset: usize,
+) -> Result<(), OffsetError> {
+    selector.enable(region, offset).map_err(|e| match e {
+        Error::NotEnoughRowsAvailable { current_k } => OffsetError::OutOfBounds {
+            region: region_name.to_string(),
+            offset,
+            current_k,
+        },
+        e => OffsetError::Other(e),
+    })
+}
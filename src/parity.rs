@@ -0,0 +1,217 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{bits_range_check::BitsRangeCheckConfig, decompose_range_check::field_to_u128};
+
+/// Splits the lowest bit off a value already range-checked to `n` bits, e.g. by the decompose chip:
+///     $$value = 2 \cdot half + is\_odd$$
+/// with `is_odd` boolean and `half` decomposed into single bits by the bits range check, to $n - 1$ bits.
+/// A wrong `is_odd` makes `half` a field element far outside that range, so it fails the check.
+#[derive(Debug, Clone)]
+struct ParityConfig<F: PrimeField> {
+    value: Column<Advice>,
+    is_odd: Column<Advice>,
+    half: Column<Advice>,
+    q_parity: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> ParityConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let is_odd = meta.advice_column();
+        let half = meta.advice_column();
+        let q_parity = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [value, is_odd, half] {
+            meta.enable_equality(column);
+        }
+
+        //     value   |  is_odd  |   half   | q_parity
+        //    ---------------------------------------------
+        //       v     |    b     |    h     |    1
+        meta.create_gate("parity", |meta| {
+            let q = meta.query_selector(q_parity);
+            let value = meta.query_advice(value, Rotation::cur());
+            let is_odd = meta.query_advice(is_odd, Rotation::cur());
+            let half = meta.query_advice(half, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "is_odd is boolean",
+                        is_odd.clone() * (Expression::Constant(F::ONE) - is_odd.clone()),
+                    ),
+                    (
+                        "value = 2 * half + is_odd",
+                        value - (half * Expression::Constant(F::from(2)) + is_odd),
+                    ),
+                ],
+            )
+        });
+
+        Self {
+            value,
+            is_odd,
+            half,
+            q_parity,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ParityChip<F: PrimeField> {
+    config: ParityConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ParityChip<F> {
+    fn construct(config: ParityConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ParityConfig<F> {
+        ParityConfig::configure(meta)
+    }
+
+    /// Returns a boolean cell constrained to the lowest bit of `value`, which must fit in `num_bits` bits.
+    fn is_odd(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let is_odd = value
+            .value()
+            .map(|value| F::from((field_to_u128(*value) & 1) as u64));
+        self.assign_is_odd(layouter, value, num_bits, is_odd)
+    }
+
+    fn assign_is_odd(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+        is_odd: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(num_bits > 0, "Need at least one bit");
+        let config = &self.config;
+        let (is_odd, half) = layouter.assign_region(
+            || "parity",
+            |mut region| {
+                let offset = 0;
+                config.q_parity.enable(&mut region, offset)?;
+                let value = value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                let is_odd = region.assign_advice(|| "is_odd", config.is_odd, offset, || is_odd)?;
+                let half = value
+                    .value()
+                    .zip(is_odd.value())
+                    .map(|(value, is_odd)| (*value - *is_odd) * F::TWO_INV);
+                let half = region.assign_advice(|| "half", config.half, offset, || half)?;
+                Ok((is_odd, half))
+            },
+        )?;
+
+        config.range.assign(
+            layouter.namespace(|| "range check half"),
+            &half,
+            num_bits - 1,
+        )?;
+        Ok(is_odd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::{
+        decompose_range_check::DecomposeRangeCheckConfig,
+        testing::{run, ForgeableCircuit, Forged},
+    };
+
+    // Range-checks `value` with the decompose chip, then exposes its parity. The witness for the low bit can be
+    // forged.
+    #[derive(Default)]
+    struct ParityCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for ParityCircuit<F> {
+        type Config = (
+            DecomposeRangeCheckConfig<F>,
+            ParityConfig<F>,
+            Column<Instance>,
+        );
+        type Witness = Value<F>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (
+                DecomposeRangeCheckConfig::configure(meta),
+                ParityChip::configure(meta),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (decompose, parity, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            is_odd: Option<&Value<F>>,
+        ) -> Result<(), Error> {
+            decompose.load_table(&mut layouter)?;
+            let value = decompose
+                .assign_field_value(layouter.namespace(|| "range check"), None, self.value)?
+                .value;
+            let chip = ParityChip::construct(parity);
+            let num_bits = decompose.range_bits();
+            let is_odd = match is_odd {
+                Some(is_odd) => {
+                    chip.assign_is_odd(layouter.namespace(|| "parity"), &value, num_bits, *is_odd)
+                }
+                None => chip.is_odd(layouter.namespace(|| "parity"), &value, num_bits),
+            }?;
+            layouter.constrain_instance(is_odd.cell(), instance, 0)
+        }
+    }
+
+    fn circuit(value: u64) -> Forged<Fp, ParityCircuit<Fp>> {
+        Forged::honest(ParityCircuit {
+            value: Value::known(Fp::from(value)),
+        })
+    }
+
+    #[test]
+    fn test_parity() {
+        // The decompose chip checks values in 0..64
+        for value in [0, 1, 2, 7, 42, 62, 63] {
+            assert!(run(&circuit(value), &[Fp::from(value % 2)]), "{}", value);
+            assert!(
+                !run(&circuit(value), &[Fp::from(1 - value % 2)]),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_parity_malicious_is_odd() {
+        // Claiming the opposite parity, or a non-boolean one, can't be made consistent with the value
+        for (value, is_odd) in [(42, 1), (7, 0), (6, 2)] {
+            let malicious = circuit(value).forge(Value::known(Fp::from(is_odd)));
+            assert!(!run(&malicious, &[Fp::from(is_odd)]), "{}", value);
+        }
+    }
+}
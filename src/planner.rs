@@ -0,0 +1,223 @@
+use ff::Field;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, FloorPlanner};
+use std::marker::PhantomData;
+
+/// Runs `C` under the floor planner `P` instead of its own `C::FloorPlanner`, so any circuit in the crate can
+/// be laid out by either planner without changing its definition, e.g. `WithFloorPlanner::<_, V1>::new(circuit)`.
+///
+/// The two planners in halo2 differ in how they place regions:
+/// - `SimpleFloorPlanner` lays regions out one after another in the order they're assigned. Each region
+///   starts below the lowest row any earlier region used in any of its columns.
+/// - `V1` makes a measurement pass first, then packs each region as high as its own columns allow. A region
+///   whose columns are disjoint from an earlier one can sit beside it rather than below it. It also
+///   gathers all constants into a region of their own at the end.
+///
+/// So V1 never needs more rows than SimpleFloorPlanner, and needs fewer when consecutive regions use different
+/// columns. Regions stay contiguous and keep their offsets under both, so gates only see different absolute rows.
+#[derive(Debug, Clone)]
+pub(crate) struct WithFloorPlanner<C, P> {
+    circuit: C,
+    _marker: PhantomData<P>,
+}
+
+impl<C, P> WithFloorPlanner<C, P> {
+    pub(crate) fn new(circuit: C) -> Self {
+        Self {
+            circuit,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: Field, C: Circuit<F>, P: FloorPlanner> Circuit<F> for WithFloorPlanner<C, P> {
+    type Config = C::Config;
+    type FloorPlanner = P;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.circuit.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl halo2_proofs::circuit::Layouter<F>,
+    ) -> Result<(), Error> {
+        self.circuit.synthesize(config, layouter)
+    }
+}
+
+/// Hands out row offsets to gadgets stacked one after another in a single region, so each chip's
+/// `_in_region` assign call starts on the row below the last one's without the caller summing row counts:
+///
+/// ```text
+/// let mut rows = LayoutPlanner::new();
+/// let decomposed = decompose.assign_cell_in_region(&mut region, rows.reserve(decompose.rows_used()), &a)?;
+/// let b = range.assign_in_region(&mut region, rows.reserve(BitsRangeCheckConfig::rows_used(8)), &b, 8)?;
+/// ```
+///
+/// The floor planner may run a region's closure more than once, so make a new LayoutPlanner inside it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LayoutPlanner {
+    next: usize,
+}
+
+impl LayoutPlanner {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves `rows` rows below everything reserved so far, returning the offset of the first.
+    pub(crate) fn reserve(&mut self, rows: usize) -> usize {
+        let offset = self.next;
+        self.next += rows;
+        offset
+    }
+
+    /// The number of rows reserved so far, i.e. the offset the next gadget would start at.
+    pub(crate) fn rows_used(&self) -> usize {
+        self.next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Advice, Column, Instance},
+    };
+
+    use super::*;
+    use crate::{
+        bits_range_check::BitsRangeCheckConfig, decompose_range_check::DecomposeRangeCheckConfig,
+        testing::run_mock_auto,
+    };
+
+    #[test]
+    fn test_layout_planner_offsets() {
+        let mut rows = LayoutPlanner::new();
+        assert_eq!(rows.reserve(3), 0);
+        assert_eq!(rows.reserve(0), 3);
+        assert_eq!(rows.reserve(5), 3);
+        assert_eq!(rows.reserve(1), 8);
+        assert_eq!(rows.rows_used(), 9);
+    }
+
+    // Stacks three gadgets in one region: a decomposition of the private `a`, a bits range check of the private
+    // `b`, and the digit sum of a's windows, which is exposed at instance row 0. The decomposition and digit sum
+    // share columns, so they only stay out of each other's way if their rows don't overlap. Setting `overlap`
+    // puts the digit sum back at offset 0, on top of the decomposition.
+    #[derive(Default)]
+    struct StackedCircuit {
+        a: Value<Fp>,
+        b: Value<Fp>,
+        overlap: bool,
+    }
+
+    impl Circuit<Fp> for StackedCircuit {
+        type Config = (
+            DecomposeRangeCheckConfig<Fp>,
+            BitsRangeCheckConfig<Fp>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                overlap: self.overlap,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (
+                DecomposeRangeCheckConfig::configure(meta),
+                BitsRangeCheckConfig::configure(meta),
+                input,
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (decompose, range, input, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            decompose.load_table(&mut layouter)?;
+            let (a, b) = layouter.assign_region(
+                || "Load a and b",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+            let sum = layouter.assign_region(
+                || "stacked gadgets",
+                |mut region| {
+                    let mut rows = LayoutPlanner::new();
+                    let decomposed = decompose.assign_cell_in_region(
+                        &mut region,
+                        rows.reserve(decompose.rows_used()),
+                        &a,
+                    )?;
+                    range.assign_in_region(
+                        &mut region,
+                        rows.reserve(BitsRangeCheckConfig::<Fp>::rows_used(8)),
+                        &b,
+                        8,
+                    )?;
+                    let offset = rows.reserve(decomposed.windows.len() + 1);
+                    let offset = if self.overlap { 0 } else { offset };
+                    decompose.digit_sum_in_region(&mut region, offset, &decomposed.windows)
+                },
+            )?;
+            layouter.constrain_instance(sum.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_layout_planner_stacks_gadgets() {
+        // 0o52 has digits 2 and 5
+        let circuit = StackedCircuit {
+            a: Value::known(Fp::from(0o52)),
+            b: Value::known(Fp::from(200)),
+            overlap: false,
+        };
+        run_mock_auto(&circuit, vec![vec![Fp::from(7)]]).assert_satisfied();
+        assert!(run_mock_auto(&circuit, vec![vec![Fp::from(8)]])
+            .verify()
+            .is_err());
+
+        // Each gadget still checks its own input
+        let circuit = StackedCircuit {
+            b: Value::known(Fp::from(256)),
+            ..circuit
+        };
+        assert!(run_mock_auto(&circuit, vec![vec![Fp::from(7)]])
+            .verify()
+            .is_err());
+    }
+
+    #[test]
+    fn test_layout_planner_overlap_fails() {
+        // Laid over the decomposition, the digit sum's accumulator overwrites the copy of a
+        let circuit = StackedCircuit {
+            a: Value::known(Fp::from(0o52)),
+            b: Value::known(Fp::from(200)),
+            overlap: true,
+        };
+        assert!(run_mock_auto(&circuit, vec![vec![Fp::from(7)]])
+            .verify()
+            .is_err());
+    }
+}
@@ -0,0 +1,1145 @@
+use ff::{Field, PrimeField};
+
+use halo2_proofs::{
+    circuit::*,
+    pasta::{EqAffine, Fp},
+    plonk::*,
+    poly::{commitment::Params, Rotation},
+};
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::floor_planner::V1,
+    dev::{FailureLocation, VerifyFailure},
+    plonk::Circuit,
+};
+use std::any::{Any, TypeId};
+use std::cmp::Ordering;
+
+use crate::{
+    decompose_range_check::RangeConstrained,
+    label::region_name,
+    less_than::field_cmp,
+    offset::{enable_at, OffsetError},
+    witness::WitnessInput,
+};
+
+mod nonzero;
+mod sentinel;
+
+struct Sizes {
+    RANGE: usize,
+    NUM_BITS: usize,
+    LOOKUP_RANGE: usize,
+}
+#[derive(Clone, Copy, Debug)]
+
+pub(crate) struct RangeCheckConfig<F: PrimeField, const RANGE: usize> {
+    value: Column<Advice>,
+    q_range_check: Selector,
+    sum: Option<SumConfig>,
+    // Whether q_range_check also checks the value on the row below, see configure_paired
+    paired: bool,
+    _marker: PhantomData<F>,
+}
+
+// A running sum of the range-checked values, in a column next to them
+#[derive(Clone, Copy, Debug)]
+struct SumConfig {
+    sum: Column<Advice>,
+    q_sum: Selector,
+    q_sum_start: Selector,
+}
+
+impl<F: PrimeField, const RANGE: usize> RangeCheckConfig<F, RANGE> {
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_range_check: Selector,
+        value: Column<Advice>,
+    ) -> Self {
+        let config = Self {
+            value,
+            q_range_check,
+            sum: None,
+            paired: false,
+            _marker: PhantomData,
+        };
+
+        meta.create_gate("range check", |meta| {
+            let q_range_check = meta.query_selector(q_range_check);
+            let value = meta.query_advice(value, Rotation::cur());
+            Constraints::with_selector(
+                q_range_check,
+                [("range check", range_check_expr(RANGE, value))],
+            )
+        });
+        config
+    }
+
+    // Same as configure, but each enabled row checks the value on the row below as well, so a pair of values
+    // needs the selector on one row rather than two:
+    //
+    //     value   | q_range_check
+    //    -------------------------
+    //      v_0    |       1
+    //      v_1    |       0
+    //
+    // The two checks are separate constraints of the one gate rather than a product, so the degree stays at
+    // RANGE + 1 for the product and the selector, the same as configure. Multiplying them together would
+    // double it. The cost is a second query of the value column, at Rotation::next().
+    fn configure_paired(
+        meta: &mut ConstraintSystem<F>,
+        q_range_check: Selector,
+        value: Column<Advice>,
+    ) -> Self {
+        meta.create_gate("paired range check", |meta| {
+            let q_range_check = meta.query_selector(q_range_check);
+            let cur = meta.query_advice(value, Rotation::cur());
+            let next = meta.query_advice(value, Rotation::next());
+            Constraints::with_selector(
+                q_range_check,
+                [
+                    ("range check cur", range_check_expr(RANGE, cur)),
+                    ("range check next", range_check_expr(RANGE, next)),
+                ],
+            )
+        });
+        Self {
+            value,
+            q_range_check,
+            sum: None,
+            paired: true,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure_sum(self, meta: &mut ConstraintSystem<F>) -> Self {
+        let sum = meta.advice_column();
+        let q_sum = meta.selector();
+        let q_sum_start = meta.selector();
+        meta.enable_equality(sum);
+
+        //     value   |    sum    | q_range_check |  q_sum  | q_sum_start
+        //    ---------------------------------------------------------------
+        //      v_0    |     0     |       1       |    1    |      1
+        //      v_1    |    v_0    |       1       |    1    |      0
+        //      ...    |    ...    |      ...      |   ...   |     ...
+        //             | v_0 + ... |       0       |    0    |      0
+        meta.create_gate("sum start", |meta| {
+            let q = meta.query_selector(q_sum_start);
+            let sum = meta.query_advice(sum, Rotation::cur());
+            Constraints::with_selector(q, [("sum_0 = 0", sum)])
+        });
+
+        meta.create_gate("running sum", |meta| {
+            let q = meta.query_selector(q_sum);
+            let value = meta.query_advice(self.value, Rotation::cur());
+            let sum_cur = meta.query_advice(sum, Rotation::cur());
+            let sum_next = meta.query_advice(sum, Rotation::next());
+            Constraints::with_selector(
+                q,
+                [("sum_next = sum_cur + value", sum_next - (sum_cur + value))],
+            )
+        });
+
+        Self {
+            sum: Some(SumConfig {
+                sum,
+                q_sum,
+                q_sum_start,
+            }),
+            ..self
+        }
+    }
+}
+
+// An expression which is zero exactly when `value` is in 0..range
+fn range_check_expr<F: PrimeField>(range: usize, value: Expression<F>) -> Expression<F> {
+    (0..range).fold(
+        value.clone(),
+        // We do value.clone() above to initialize the types correctly. Since we want it to check 0 equality, it doesn't really matter what it is
+        |acc: halo2_proofs::plonk::Expression<F>, i| {
+            acc * (value.clone()
+                - halo2_proofs::plonk::Expression::Constant(F::from_u128(i as u128)))
+        },
+    )
+}
+
+type CellsAndSum<F> = (Vec<AssignedCell<F, F>>, AssignedCell<F, F>);
+
+#[derive(Clone, Copy, Debug)]
+
+struct RangeCheckChip<F: PrimeField, const RANGE: usize> {
+    config: RangeCheckConfig<F, RANGE>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const RANGE: usize> RangeCheckChip<F, RANGE> {
+    fn construct(config: RangeCheckConfig<F, RANGE>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> RangeCheckConfig<F, RANGE> {
+        let value = meta.advice_column();
+        let q_range_check = meta.selector();
+        let config = RangeCheckConfig::configure(meta, q_range_check, value);
+        config
+    }
+
+    // Same as configure, but with a complex selector so that q_range_check can also gate lookup arguments,
+    // which simple selectors are not allowed in. The tradeoff is that keygen can only combine simple
+    // selectors into shared fixed columns, so each complex selector costs a fixed column of its own.
+    fn configure_complex(meta: &mut ConstraintSystem<F>) -> RangeCheckConfig<F, RANGE> {
+        let value = meta.advice_column();
+        let q_range_check = meta.complex_selector();
+        RangeCheckConfig::configure(meta, q_range_check, value)
+    }
+
+    // Same as configure, but values can be copied into the value column, as assign_instance_range needs.
+    // The instance column must have equality enabled too.
+    fn configure_for_instances(meta: &mut ConstraintSystem<F>) -> RangeCheckConfig<F, RANGE> {
+        let config = Self::configure(meta);
+        meta.enable_equality(config.value);
+        config
+    }
+
+    // Same as configure, with an extra column accumulating the sum of the checked values
+    fn configure_with_sum(meta: &mut ConstraintSystem<F>) -> RangeCheckConfig<F, RANGE> {
+        Self::configure(meta).configure_sum(meta)
+    }
+
+    // Same as configure, but the values are checked in pairs by assign_pair, with one selector per pair
+    fn configure_paired(meta: &mut ConstraintSystem<F>) -> RangeCheckConfig<F, RANGE> {
+        let value = meta.advice_column();
+        let q_range_check = meta.selector();
+        RangeCheckConfig::configure_paired(meta, q_range_check, value)
+    }
+
+    // The product gate only depends on the selector, so `value` may be `Value::unknown()`
+    // during keygen: the region shape and enabled rows are identical either way.
+    // Returns the checked cell as RangeConstrained, like the decompose chip's windows. `label`, if given,
+    // prefixes the region name, see label::region_name.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        label: Option<&str>,
+        value: Value<F>,
+    ) -> Result<RangeConstrained<F>, halo2_proofs::plonk::Error> {
+        let name = region_name(label, "Range chip brute force");
+        layouter
+            .assign_region(
+                || name.clone(),
+                |mut region| Ok(self.assign_at(&mut region, &name, value, 0)?),
+            )
+            .map(RangeConstrained)
+    }
+
+    // Same as assign, but into a row of a region the caller already has open, so the range check can share a
+    // region with other rows of the value column. The caller picks an `offset` that isn't otherwise used, and
+    // passes the region's name so an offset past the end of the circuit is reported against it.
+    fn assign_at(
+        &self,
+        region: &mut Region<'_, F>,
+        region_name: &str,
+        value: Value<F>,
+        offset: usize,
+    ) -> Result<AssignedCell<F, F>, OffsetError> {
+        #[cfg(debug_assertions)]
+        Self::warn_if_out_of_range(value);
+        enable_at(&self.config.q_range_check, region, region_name, offset)?;
+        region
+            .assign_advice(|| "value", self.config.value, offset, || value)
+            .map_err(OffsetError::Other)
+    }
+
+    // Range-checks two values on consecutive rows, enabling the selector only on the first.
+    // Needs a config from configure_paired.
+    fn assign_pair(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: [Value<F>; 2],
+    ) -> Result<[AssignedCell<F, F>; 2], Error> {
+        if !self.config.paired {
+            return Err(Error::Synthesis);
+        }
+        layouter.assign_region(
+            || "Range chip brute force pair",
+            |mut region| {
+                self.config.q_range_check.enable(&mut region, 0)?;
+                let cur = region.assign_advice(|| "cur", self.config.value, 0, || values[0])?;
+                let next = region.assign_advice(|| "next", self.config.value, 1, || values[1])?;
+                Ok([cur, next])
+            },
+        )
+    }
+
+    // Copies the public inputs in `rows` of `instance` into the value column and range-checks each one, so the
+    // proof shows they are all in 0..RANGE without the prover supplying them again as private witnesses.
+    // Needs a config from configure_for_instances. Returns the checked cells in the order of `rows`.
+    fn assign_instance_range(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        rows: std::ops::Range<usize>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "Range check instances",
+            |mut region| {
+                rows.clone()
+                    .enumerate()
+                    .map(|(offset, row)| {
+                        self.config.q_range_check.enable(&mut region, offset)?;
+                        region.assign_advice_from_instance(
+                            || format!("instance {}", row),
+                            instance,
+                            row,
+                            self.config.value,
+                            offset,
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    // An out-of-range value is a legitimate witness (it just fails verification, as in the wrong-value test),
+    // so rather than panicking this only warns, to point at the mistake before running the prover.
+    // Returns whether it warned.
+    #[cfg(debug_assertions)]
+    fn warn_if_out_of_range(value: Value<F>) -> bool {
+        let out_of_range = value
+            .error_if_known_and(|value| field_cmp(value, &F::from(RANGE as u64)) != Ordering::Less)
+            .is_err();
+        if out_of_range {
+            eprintln!(
+                "warning: assigning {:?} to a range check of 0..{}, which will fail verification",
+                value, RANGE
+            );
+        }
+        out_of_range
+    }
+
+    // Range-checks each value and returns the checked cells along with their sum.
+    // Needs a config from configure_with_sum.
+    fn range_check_and_sum(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Value<F>],
+    ) -> Result<CellsAndSum<F>, Error> {
+        let config = self.config;
+        let sum_config = config.sum.ok_or(Error::Synthesis)?;
+        layouter.assign_region(
+            || "Range check and sum",
+            |mut region| {
+                sum_config.q_sum_start.enable(&mut region, 0)?;
+                let mut sum = region.assign_advice(
+                    || "sum_0",
+                    sum_config.sum,
+                    0,
+                    || Value::known(F::ZERO),
+                )?;
+                let mut cells = vec![];
+                for (offset, value) in values.iter().enumerate() {
+                    config.q_range_check.enable(&mut region, offset)?;
+                    sum_config.q_sum.enable(&mut region, offset)?;
+                    let cell = region.assign_advice(|| "value", config.value, offset, || *value)?;
+                    let next = sum.value().copied() + value;
+                    sum = region.assign_advice(
+                        || format!("sum_{}", offset + 1),
+                        sum_config.sum,
+                        offset + 1,
+                        || next,
+                    )?;
+                    cells.push(cell);
+                }
+                Ok((cells, sum))
+            },
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RangeCheckCircuit<F: PrimeField, const RANGE: usize> {
+    value: Value<F>,
+}
+
+impl<F: PrimeField, const RANGE: usize> RangeCheckCircuit<F, RANGE> {
+    fn builder() -> RangeCheckCircuitBuilder<F, RANGE> {
+        RangeCheckCircuitBuilder {
+            value: Value::unknown(),
+        }
+    }
+
+    /// The circuit witnessing `n`.
+    pub(crate) fn known(n: u64) -> Self {
+        Self::builder().value(F::from(n)).build()
+    }
+
+    /// The keygen circuit, with no witness.
+    fn unknown() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Builds a RangeCheckCircuit, e.g. `RangeCheckCircuit::<F, 10>::builder().value(x).build()`.
+/// The range is part of the circuit's type rather than a setter, since it shapes the gate.
+/// Without a value, the built circuit is the keygen circuit with no witness.
+#[derive(Debug)]
+struct RangeCheckCircuitBuilder<F: PrimeField, const RANGE: usize> {
+    value: Value<F>,
+}
+
+impl<F: PrimeField, const RANGE: usize> RangeCheckCircuitBuilder<F, RANGE> {
+    fn value(self, value: F) -> Self {
+        Self {
+            value: Value::known(value),
+        }
+    }
+
+    fn build(self) -> RangeCheckCircuit<F, RANGE> {
+        RangeCheckCircuit { value: self.value }
+    }
+}
+
+impl<F: PrimeField, const RANGE: usize> Circuit<F> for RangeCheckCircuit<F, RANGE> {
+    type Config = RangeCheckConfig<F, RANGE>;
+    type FloorPlanner = V1;
+
+    // Circuit without witnesses, called only during key generation
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    // Has the arrangement of columns. Called only during keygen, and will just call chip config most of the time
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeCheckChip::configure(meta)
+    }
+
+    // Take the output of configure and floorplanner type to make the actual circuit
+    // Called both at key generation time, and proving time with a specific witness
+    // Will call all of the copy constraints
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        Self::assign_witness(&config, layouter.namespace(|| "value_check"), self.value)?;
+        Ok(())
+    }
+}
+
+impl<F: PrimeField, const RANGE: usize> WitnessInput<F> for RangeCheckCircuit<F, RANGE> {
+    fn from_witness(value: Value<F>) -> Result<Self, Error> {
+        Ok(Self { value })
+    }
+
+    fn assign_witness(
+        config: &Self::Config,
+        layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(RangeCheckChip::construct(*config)
+            .assign(layouter, None, value)?
+            .0)
+    }
+}
+
+/// The degree of the constraint system for the range check to 0..RANGE. The product gate's degree grows with
+/// RANGE, and sets the size of the extended domain keygen has to work over.
+pub(crate) fn range_check_degree<const RANGE: usize>() -> usize {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    RangeCheckCircuit::<Fp, RANGE>::configure(&mut cs);
+    cs.degree()
+}
+
+/// The smallest `k` that keygen accepts for the range check to 0..RANGE.
+pub(crate) fn range_check_keygen_k<const RANGE: usize>() -> u32 {
+    (1..=17)
+        .find(|k| {
+            let params: Params<EqAffine> = Params::new(*k);
+            keygen_vk(&params, &RangeCheckCircuit::<Fp, RANGE>::unknown()).is_ok()
+        })
+        .expect("range check doesn't fit in 2^17 rows")
+}
+
+/// Generates the verifying and proving keys for the range check to 0..RANGE.
+pub(crate) fn keygen_range_check<const RANGE: usize>(
+    params: &Params<EqAffine>,
+) -> Result<ProvingKey<EqAffine>, Error> {
+    let circuit = RangeCheckCircuit::<Fp, RANGE>::unknown();
+    let vk = keygen_vk(params, &circuit)?;
+    keygen_pk(params, vk, &circuit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompose_range_check::{plan_decomposition, DecomposeRangeCheckConfig};
+    use crate::table::RangeTableConfig;
+    use crate::testing::{
+        assert_witness_range, cell_to_u128, compare_planners, run_mock_auto, verify_ok,
+    };
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::{Any, Circuit},
+    };
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_range_check_correct() {
+        let k: u32 = 9; // log_2 of row count
+        let value = Fp::from(5);
+        const RANGE: usize = 10;
+        let circuit = RangeCheckCircuit::<Fp, RANGE> {
+            value: Value::known(value.into()),
+        };
+        // This prover is faster and 'fake', but is mostly a devtool for debugging
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        // This function will pretty-print on errors
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_range_check_wrong() {
+        let k: u32 = 9; // log_2 of row count
+        let value = Fp::from(11);
+        const RANGE: usize = 10;
+        let circuit = RangeCheckCircuit::<Fp, RANGE> {
+            value: Value::known(value.into()),
+        };
+        // This prover is faster and 'fake', but is mostly a devtool for debugging
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        // This function will pretty-print on errors
+        match prover.verify() {
+            Err(e) => {
+                println!("Error successfully achieved!");
+            }
+            _ => assert_eq!(1, 0),
+        }
+    }
+
+    #[test]
+    fn test_range_check_verify_ok() {
+        assert!(verify_ok(9, &RangeCheckCircuit::<Fp, 10>::known(5), vec![]));
+        assert!(!verify_ok(
+            9,
+            &RangeCheckCircuit::<Fp, 10>::known(11),
+            vec![]
+        ));
+    }
+
+    #[test]
+    fn test_range_check_circuit_builder() {
+        let k: u32 = 9;
+        let circuit = RangeCheckCircuit::<Fp, 10>::builder()
+            .value(Fp::from(5))
+            .build();
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        // The same value is out of a smaller range
+        let circuit = RangeCheckCircuit::<Fp, 5>::builder()
+            .value(Fp::from(5))
+            .build();
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // No value is the keygen circuit
+        let circuit = RangeCheckCircuit::<Fp, 5>::builder().build();
+        assert_eq!(
+            format!("{:?}", circuit),
+            format!("{:?}", circuit.without_witnesses())
+        );
+    }
+
+    #[test]
+    fn test_range_check_known_unknown() {
+        use halo2_proofs::{pasta::EqAffine, plonk::keygen_vk, poly::commitment::Params};
+
+        assert!(verify_ok(9, &RangeCheckCircuit::<Fp, 10>::known(9), vec![]));
+        assert!(!verify_ok(
+            9,
+            &RangeCheckCircuit::<Fp, 10>::known(10),
+            vec![]
+        ));
+
+        // unknown() is what keygen sees, and gives the same key as a circuit with a witness
+        let params: Params<EqAffine> = Params::new(9);
+        let vk = keygen_vk(&params, &RangeCheckCircuit::<Fp, 10>::unknown()).unwrap();
+        let vk_known = keygen_vk(&params, &RangeCheckCircuit::<Fp, 10>::known(5)).unwrap();
+        assert_eq!(
+            format!("{:?}", vk.pinned()),
+            format!("{:?}", vk_known.pinned())
+        );
+        let circuit = RangeCheckCircuit::<Fp, 10>::known(5);
+        assert_eq!(
+            format!("{:?}", RangeCheckCircuit::<Fp, 10>::unknown()),
+            format!("{:?}", circuit.without_witnesses())
+        );
+    }
+
+    #[test]
+    fn test_range_check_witness_input() {
+        let values = |values: &[u64]| values.iter().map(|v| Fp::from(*v)).collect::<Vec<_>>();
+        let mut out_of_range = values(&[10, 11]);
+        out_of_range.push(-Fp::ONE);
+        assert_witness_range::<RangeCheckCircuit<Fp, 10>>(&values(&[0, 5, 9]), &out_of_range);
+    }
+
+    // Fills the first rows of the value column with unchecked data, then range-checks at `offset` of the same
+    // region, recording the error from assign_at if there is one
+    #[derive(Default)]
+    struct AssignAtCircuit<F: PrimeField, const RANGE: usize> {
+        value: Value<F>,
+        offset: usize,
+        error: RefCell<Option<String>>,
+    }
+
+    impl<F: PrimeField, const RANGE: usize> Circuit<F> for AssignAtCircuit<F, RANGE> {
+        type Config = RangeCheckConfig<F, RANGE>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            RangeCheckChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            layouter.assign_region(
+                || "Shared region",
+                |mut region| {
+                    // Way out of range, but not checked
+                    for offset in 0..3 {
+                        region.assign_advice(
+                            || "other",
+                            config.value,
+                            offset,
+                            || Value::known(F::from(100 + offset as u64)),
+                        )?;
+                    }
+                    chip.assign_at(&mut region, "Shared region", self.value, self.offset)
+                        .map_err(|e| {
+                            *self.error.borrow_mut() = Some(e.to_string());
+                            e.into()
+                        })
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_assign_at() {
+        let k: u32 = 5;
+        const RANGE: usize = 10;
+        let circuit = |value| AssignAtCircuit::<Fp, RANGE> {
+            value: Value::known(Fp::from(value)),
+            offset: 3,
+            ..Default::default()
+        };
+        let prover = MockProver::run(k, &circuit(5), vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit(11), vec![]).unwrap();
+        match &prover.verify().unwrap_err()[..] {
+            [VerifyFailure::ConstraintNotSatisfied { location, .. }] => assert_eq!(
+                *location,
+                FailureLocation::InRegion {
+                    region: (0, "Shared region").into(),
+                    offset: 3,
+                }
+            ),
+            failures => panic!("Unexpected failures {:?}", failures),
+        }
+    }
+
+    #[test]
+    fn test_range_check_assign_at_out_of_bounds() {
+        // 2^4 rows, less the blinding rows, don't reach offset 20
+        let circuit = AssignAtCircuit::<Fp, 10> {
+            value: Value::known(Fp::from(5)),
+            offset: 20,
+            ..Default::default()
+        };
+        assert!(matches!(
+            MockProver::run(4, &circuit, vec![]),
+            Err(Error::NotEnoughRowsAvailable { current_k: 4 })
+        ));
+        assert_eq!(
+            circuit.error.borrow().as_deref(),
+            Some("Offset 20 of region \"Shared region\" is past the usable rows of a 2^4 row circuit")
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_range_check_out_of_range_warning() {
+        const RANGE: usize = 10;
+        type Chip = RangeCheckChip<Fp, RANGE>;
+        for value in [0, 5, RANGE as u64 - 1] {
+            assert!(!Chip::warn_if_out_of_range(Value::known(Fp::from(value))));
+        }
+        for value in [Fp::from(RANGE as u64), Fp::from(11), -Fp::ONE] {
+            assert!(Chip::warn_if_out_of_range(Value::known(value)));
+        }
+        // Nothing to check during keygen
+        assert!(!Chip::warn_if_out_of_range(Value::unknown()));
+    }
+
+    #[test]
+    fn test_range_check_keygen_without_witnesses() {
+        use halo2_proofs::{
+            pasta::EqAffine,
+            plonk::{keygen_pk, keygen_vk},
+            poly::commitment::Params,
+        };
+
+        let k: u32 = 9;
+        const RANGE: usize = 10;
+        let params: Params<EqAffine> = Params::new(k);
+
+        let circuit = RangeCheckCircuit::<Fp, RANGE> {
+            value: Value::known(Fp::from(5)),
+        };
+        let empty_circuit = circuit.without_witnesses();
+
+        // Keygen must go through with no witness at all
+        let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
+        let pk = keygen_pk(&params, vk, &empty_circuit).expect("keygen_pk should not fail");
+
+        // And the keys must not depend on which value ends up being witnessed
+        let vk_known = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+        assert_eq!(
+            format!("{:?}", pk.get_vk().pinned()),
+            format!("{:?}", vk_known.pinned())
+        );
+    }
+
+    // Range checks `value` to 0..RANGE with the product gate, and additionally looks up the same
+    // q_range_check * value in a 0..LOOKUP_RANGE table, which only works with a complex selector.
+    #[derive(Default)]
+    struct ComplexRangeCheckCircuit<F: PrimeField, const RANGE: usize> {
+        value: Value<F>,
+    }
+
+    const LOOKUP_RANGE: usize = 8;
+
+    impl<F: PrimeField, const RANGE: usize> Circuit<F> for ComplexRangeCheckCircuit<F, RANGE> {
+        type Config = (
+            RangeCheckConfig<F, RANGE>,
+            RangeTableConfig<F, LOOKUP_RANGE>,
+        );
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let config = RangeCheckChip::configure_complex(meta);
+            let table = RangeTableConfig::configure(meta);
+            meta.lookup(|meta| {
+                let q = meta.query_selector(config.q_range_check);
+                let value = meta.query_advice(config.value, Rotation::cur());
+                vec![(q * value, table.value)]
+            });
+            (config, table)
+        }
+
+        fn synthesize(
+            &self,
+            (config, table): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            table.load(&mut layouter)?;
+            let chip = RangeCheckChip::construct(config);
+            chip.assign(layouter.namespace(|| "value_check"), None, self.value)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_complex_selector_with_lookup() {
+        let k: u32 = 9;
+        const RANGE: usize = 10;
+
+        // In range for both the product gate and the lookup
+        for value in 0..LOOKUP_RANGE as u64 {
+            let circuit = ComplexRangeCheckCircuit::<Fp, RANGE> {
+                value: Value::known(Fp::from(value)),
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // 9 passes the product gate but not the lookup, 10 fails both
+        for value in [9, 10] {
+            let circuit = ComplexRangeCheckCircuit::<Fp, RANGE> {
+                value: Value::known(Fp::from(value)),
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[derive(Default)]
+    struct RangeCheckAndSumCircuit<F: PrimeField, const RANGE: usize> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: PrimeField, const RANGE: usize> Circuit<F> for RangeCheckAndSumCircuit<F, RANGE> {
+        type Config = (RangeCheckConfig<F, RANGE>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (RangeCheckChip::configure_with_sum(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            let (_, sum) =
+                chip.range_check_and_sum(layouter.namespace(|| "values"), &self.values)?;
+            layouter.constrain_instance(sum.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_range_check_and_sum() {
+        let k: u32 = 5;
+        const RANGE: usize = 8;
+        let circuit = |values: &[u64]| RangeCheckAndSumCircuit::<Fp, RANGE> {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        };
+
+        let values = [3, 7, 0, 5, 1];
+        let prover = MockProver::run(k, &circuit(&values), vec![vec![Fp::from(16)]]).unwrap();
+        prover.assert_satisfied();
+
+        // No values sum to zero
+        let prover = MockProver::run(k, &circuit(&[]), vec![vec![Fp::ZERO]]).unwrap();
+        prover.assert_satisfied();
+
+        // The wrong total
+        let prover = MockProver::run(k, &circuit(&values), vec![vec![Fp::from(15)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // 8 is out of range, even though the sum is right
+        let prover = MockProver::run(k, &circuit(&[3, 8, 5]), vec![vec![Fp::from(16)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[derive(Default)]
+    struct PairedRangeCheckCircuit<F: PrimeField, const RANGE: usize> {
+        values: [Value<F>; 2],
+    }
+
+    impl<F: PrimeField, const RANGE: usize> Circuit<F> for PairedRangeCheckCircuit<F, RANGE> {
+        type Config = RangeCheckConfig<F, RANGE>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            RangeCheckChip::configure_paired(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            chip.assign_pair(layouter.namespace(|| "pair"), self.values)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_paired() {
+        let k: u32 = 5;
+        const RANGE: usize = 10;
+        let circuit = |cur: u64, next: u64| PairedRangeCheckCircuit::<Fp, RANGE> {
+            values: [Value::known(Fp::from(cur)), Value::known(Fp::from(next))],
+        };
+        for (cur, next) in [(0, 0), (5, 9), (9, 0)] {
+            let prover = MockProver::run(k, &circuit(cur, next), vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // Either value out of range fails its own constraint, both on the row the selector is enabled on
+        for (cur, next, constraint) in [
+            (10, 5, (0, "range check cur")),
+            (5, 10, (1, "range check next")),
+        ] {
+            let prover = MockProver::run(k, &circuit(cur, next), vec![]).unwrap();
+            match &prover.verify().unwrap_err()[..] {
+                [VerifyFailure::ConstraintNotSatisfied {
+                    constraint: failed,
+                    location,
+                    ..
+                }] => {
+                    assert_eq!(
+                        *failed,
+                        ((0, "paired range check").into(), constraint.0, constraint.1).into()
+                    );
+                    assert_eq!(
+                        *location,
+                        FailureLocation::InRegion {
+                            region: (0, "Range chip brute force pair").into(),
+                            offset: 0,
+                        }
+                    );
+                }
+                failures => panic!("Unexpected failures {:?}", failures),
+            }
+        }
+        let prover = MockProver::run(k, &circuit(10, 11), vec![]).unwrap();
+        assert_eq!(prover.verify().unwrap_err().len(), 2);
+    }
+
+    #[test]
+    fn test_range_check_pair_needs_paired_config() {
+        // The plain gate only checks the row its selector is on, so it can't check a pair
+        struct UnpairedCircuit;
+        impl Circuit<Fp> for UnpairedCircuit {
+            type Config = RangeCheckConfig<Fp, 10>;
+            type FloorPlanner = V1;
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                RangeCheckChip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let chip = RangeCheckChip::construct(config);
+                chip.assign_pair(layouter.namespace(|| "pair"), [Value::unknown(); 2])?;
+                Ok(())
+            }
+        }
+        assert!(matches!(
+            MockProver::run(5, &UnpairedCircuit, vec![]),
+            Err(Error::Synthesis)
+        ));
+    }
+
+    // Range-checks instance rows 1..4, leaving rows 0 and 4 unchecked
+    #[derive(Default)]
+    struct InstanceRangeCircuit<F: PrimeField, const RANGE: usize> {
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: PrimeField, const RANGE: usize> Circuit<F> for InstanceRangeCircuit<F, RANGE> {
+        type Config = (RangeCheckConfig<F, RANGE>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (RangeCheckChip::configure_for_instances(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            let cells =
+                chip.assign_instance_range(layouter.namespace(|| "instances"), instance, 1..4)?;
+            assert_eq!(cells.len(), 3);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_instance_range() {
+        let k: u32 = 5;
+        const RANGE: usize = 8;
+        let circuit = InstanceRangeCircuit::<Fp, RANGE>::default();
+        let instances = |values: [u64; 5]| vec![values.map(Fp::from).to_vec()];
+
+        let prover = MockProver::run(k, &circuit, instances([100, 0, 7, 3, 100])).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, instances([0, 1, 8, 3, 0])).unwrap();
+        match &prover.verify().unwrap_err()[..] {
+            [VerifyFailure::ConstraintNotSatisfied { location, .. }] => assert_eq!(
+                *location,
+                FailureLocation::InRegion {
+                    region: (0, "Range check instances").into(),
+                    offset: 1,
+                }
+            ),
+            failures => panic!("Unexpected failures {:?}", failures),
+        }
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn print_range_chip_vanilla() {
+        use plotters::prelude::*;
+
+        let root =
+            BitMapBackend::new("range-chip-vanilla-layout.png", (1024, 3096)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root
+            .titled("Range Chip Vanilla Layout", ("sans-serif", 60))
+            .unwrap();
+
+        let circuit = RangeCheckCircuit::<Fp, 8> {
+            value: Value::unknown(),
+        };
+        halo2_proofs::dev::CircuitLayout::default()
+            .render(9, &circuit, &root)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_range_check_floor_planners() {
+        const RANGE: usize = 10;
+        // A single region, which both planners put at row 0
+        for value in [5, 11] {
+            let circuit = || RangeCheckCircuit::<Fp, RANGE> {
+                value: Value::known(Fp::from(value)),
+            };
+            let (simple_k, v1_k) = compare_planners(circuit, vec![]);
+            assert_eq!(simple_k, v1_k);
+        }
+    }
+
+    // Both the brute-force chip and the decompose chip are generic over the one `ff::PrimeField` bound, so they
+    // compose in a circuit generic over F: the value is checked to 0..RANGE, then the same cell to 0..2^3
+    #[derive(Default)]
+    struct BruteForceAndDecomposeCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for BruteForceAndDecomposeCircuit<F> {
+        type Config = (RangeCheckConfig<F, 10>, DecomposeRangeCheckConfig<F>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            // The value column needs equality for the copy into the decompose chip
+            (
+                RangeCheckChip::configure_for_instances(meta),
+                DecomposeRangeCheckConfig::configure_with_plan(meta, plan_decomposition(3, 2)),
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (range, decompose): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            decompose.load_table(&mut layouter)?;
+            let value = RangeCheckChip::construct(range).assign(
+                layouter.namespace(|| "brute force"),
+                None,
+                self.value,
+            )?;
+            decompose.assign_cell(layouter.namespace(|| "decompose"), None, &value.0)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_composes_with_decompose() {
+        let run = |value: u64| {
+            let circuit = BruteForceAndDecomposeCircuit {
+                value: Value::known(Fp::from(value)),
+            };
+            run_mock_auto(&circuit, vec![]).verify().is_ok()
+        };
+        assert!(run(0));
+        assert!(run(7));
+        // In 0..10 but not 0..8, and the other way round for neither
+        assert!(!run(9));
+        assert!(!run(11));
+    }
+
+    // Range-checks each value, and checks the cell inside the returned RangeConstrained against it
+    #[derive(Default)]
+    struct RangeConstrainedCircuit {
+        values: Vec<u64>,
+    }
+
+    impl Circuit<Fp> for RangeConstrainedCircuit {
+        type Config = RangeCheckConfig<Fp, 10>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            RangeCheckChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            for value in &self.values {
+                let checked = chip.assign(
+                    layouter.namespace(|| format!("value {}", value)),
+                    None,
+                    Value::known(Fp::from(*value)),
+                )?;
+                assert_eq!(cell_to_u128(&checked.0), Some(*value as u128));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_range_check_range_constrained() {
+        let circuit = RangeConstrainedCircuit {
+            values: vec![0, 5, 9],
+        };
+        run_mock_auto(&circuit, vec![]).assert_satisfied();
+    }
+}
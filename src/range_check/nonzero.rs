@@ -0,0 +1,156 @@
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::range_check_expr;
+
+/// Range-checks a value to 1..RANGE, for indices and identifiers that must be positive.
+/// Alongside the brute-force product, the prover witnesses the inverse of the value,
+///     $$value \cdot inv = 1$$
+/// which has no solution when the value is 0.
+#[derive(Clone, Copy, Debug)]
+struct NonZeroRangeConfig<F: PrimeField, const RANGE: usize> {
+    value: Column<Advice>,
+    inv: Column<Advice>,
+    q_range_check: Selector,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const RANGE: usize> NonZeroRangeConfig<F, RANGE> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let inv = meta.advice_column();
+        let q_range_check = meta.selector();
+
+        //     value   |   inv   | q_range_check
+        //    -------------------------------------
+        //       v     |  1 / v  |       1
+        meta.create_gate("nonzero range check", |meta| {
+            let q_range_check = meta.query_selector(q_range_check);
+            let value = meta.query_advice(value, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            Constraints::with_selector(
+                q_range_check,
+                [
+                    ("range check", range_check_expr(RANGE, value.clone())),
+                    ("value != 0", value * inv - Expression::Constant(F::ONE)),
+                ],
+            )
+        });
+
+        Self {
+            value,
+            inv,
+            q_range_check,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct NonZeroRangeChip<F: PrimeField, const RANGE: usize> {
+    config: NonZeroRangeConfig<F, RANGE>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const RANGE: usize> NonZeroRangeChip<F, RANGE> {
+    fn construct(config: NonZeroRangeConfig<F, RANGE>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> NonZeroRangeConfig<F, RANGE> {
+        NonZeroRangeConfig::configure(meta)
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "Nonzero range check",
+            |mut region| {
+                let offset = 0;
+                self.config.q_range_check.enable(&mut region, offset)?;
+                let cell = region.assign_advice(|| "value", self.config.value, offset, || value)?;
+                // The inverse is assigned even when the value is unknown, so keygen sees the same cells as proving.
+                // Zero has no inverse, so it gets 0, which fails the gate
+                region.assign_advice(
+                    || "inv",
+                    self.config.inv,
+                    offset,
+                    || value.map(|value| value.invert().unwrap_or(F::ZERO)),
+                )?;
+                Ok(cell)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::run_mock_auto;
+    use halo2_proofs::{
+        dev::MockProver,
+        pasta::{EqAffine, Fp},
+        poly::commitment::Params,
+    };
+
+    const RANGE: usize = 8;
+
+    #[derive(Default)]
+    struct NonZeroRangeCheckCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for NonZeroRangeCheckCircuit<F> {
+        type Config = NonZeroRangeConfig<F, RANGE>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            NonZeroRangeChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = NonZeroRangeChip::construct(config);
+            chip.assign(layouter.namespace(|| "value_check"), self.value)?;
+            Ok(())
+        }
+    }
+
+    fn run(value: u64) -> MockProver<Fp> {
+        let circuit = NonZeroRangeCheckCircuit {
+            value: Value::known(Fp::from(value)),
+        };
+        run_mock_auto(&circuit, vec![])
+    }
+
+    #[test]
+    fn test_nonzero_range_check() {
+        for value in 1..RANGE as u64 {
+            run(value).assert_satisfied();
+        }
+        for value in [0, RANGE as u64] {
+            assert!(run(value).verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_nonzero_range_check_keygen() {
+        let params: Params<EqAffine> = Params::new(4);
+        let circuit = NonZeroRangeCheckCircuit::<Fp>::default();
+        keygen_vk(&params, &circuit).unwrap();
+    }
+}
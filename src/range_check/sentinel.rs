@@ -0,0 +1,142 @@
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+use super::range_check_expr;
+
+/// Range-checks an optional value: it must either be in 0..RANGE, or equal a sentinel meaning "absent".
+/// The brute-force product gets one more factor for the sentinel,
+///     $$value \cdot (value - 1) \cdots (value - (RANGE - 1)) \cdot (value - sentinel) = 0$$
+/// which raises the gate degree by one.
+#[derive(Clone, Copy, Debug)]
+struct SentinelRangeCheckConfig<F: PrimeField, const RANGE: usize> {
+    value: Column<Advice>,
+    q_range_check: Selector,
+    sentinel: F,
+}
+
+impl<F: PrimeField, const RANGE: usize> SentinelRangeCheckConfig<F, RANGE> {
+    fn configure(meta: &mut ConstraintSystem<F>, sentinel: F) -> Self {
+        let value = meta.advice_column();
+        let q_range_check = meta.selector();
+
+        meta.create_gate("range check or sentinel", |meta| {
+            let q_range_check = meta.query_selector(q_range_check);
+            let value = meta.query_advice(value, Rotation::cur());
+            let sentinel_check = value.clone() - Expression::Constant(sentinel);
+            Constraints::with_selector(
+                q_range_check,
+                [(
+                    "range check or sentinel",
+                    range_check_expr(RANGE, value) * sentinel_check,
+                )],
+            )
+        });
+
+        Self {
+            value,
+            q_range_check,
+            sentinel,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SentinelRangeCheckChip<F: PrimeField, const RANGE: usize> {
+    config: SentinelRangeCheckConfig<F, RANGE>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const RANGE: usize> SentinelRangeCheckChip<F, RANGE> {
+    fn construct(config: SentinelRangeCheckConfig<F, RANGE>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    // Uses u64::MAX as the sentinel, which is far outside any RANGE the product gate can handle
+    fn configure(meta: &mut ConstraintSystem<F>) -> SentinelRangeCheckConfig<F, RANGE> {
+        SentinelRangeCheckConfig::configure(meta, F::from(u64::MAX))
+    }
+
+    // An absent value (None) is assigned as the sentinel
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<Option<F>>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let sentinel = self.config.sentinel;
+        layouter.assign_region(
+            || "Range chip with sentinel",
+            |mut region| {
+                let offset = 0;
+                self.config.q_range_check.enable(&mut region, offset)?;
+                region.assign_advice(
+                    || "value",
+                    self.config.value,
+                    offset,
+                    || value.map(|value| value.unwrap_or(sentinel)),
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::run_mock_auto;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const RANGE: usize = 8;
+
+    #[derive(Default)]
+    struct SentinelRangeCheckCircuit<F: PrimeField> {
+        value: Value<Option<F>>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for SentinelRangeCheckCircuit<F> {
+        type Config = SentinelRangeCheckConfig<F, RANGE>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            SentinelRangeCheckChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = SentinelRangeCheckChip::construct(config);
+            chip.assign(layouter.namespace(|| "value_check"), self.value)?;
+            Ok(())
+        }
+    }
+
+    fn run(value: Option<u64>) -> MockProver<Fp> {
+        let circuit = SentinelRangeCheckCircuit {
+            value: Value::known(value.map(Fp::from)),
+        };
+        run_mock_auto(&circuit, vec![])
+    }
+
+    #[test]
+    fn test_sentinel_range_check() {
+        for value in 0..RANGE as u64 {
+            run(Some(value)).assert_satisfied();
+        }
+        // Absent, and explicitly passing the sentinel value
+        run(None).assert_satisfied();
+        run(Some(u64::MAX)).assert_satisfied();
+
+        for value in [RANGE as u64, 100, u64::MAX - 1] {
+            assert!(run(Some(value)).verify().is_err());
+        }
+    }
+}
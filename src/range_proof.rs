@@ -0,0 +1,242 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
+        ConstraintSystem, Error, Instance, ProvingKey, SingleVerifier, VerifyingKey,
+    },
+    poly::commitment::Params,
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, TranscriptRead, TranscriptWrite,
+    },
+};
+use rand_core::OsRng;
+
+use crate::{
+    bits_range_check::BitsRangeCheckConfig,
+    less_than::{LessThanChip, LessThanConfig},
+};
+
+const N_BITS: usize = 32;
+// Enough rows for the two 32-bit range checks with some to spare, see test_range_proof_k
+const K: u32 = 7;
+
+/// Proves that a private `value` is below a public `range`, both u32s. The value is first range-checked
+/// to 32 bits, so that the less-than gadget can compare it against the range from the instance column.
+#[derive(Debug, Clone)]
+struct RangeProofConfig<F: PrimeField> {
+    advice: Column<Advice>,
+    instance: Column<Instance>,
+    range_check: BitsRangeCheckConfig<F>,
+    less_than: LessThanConfig<F, N_BITS>,
+}
+
+#[derive(Default)]
+struct RangeProofCircuit<F: PrimeField> {
+    value: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for RangeProofCircuit<F> {
+    type Config = RangeProofConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        RangeProofConfig {
+            advice,
+            instance,
+            range_check: BitsRangeCheckConfig::configure(meta),
+            less_than: LessThanChip::configure(meta),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (value, range) = layouter.assign_region(
+            || "Load value and range",
+            |mut region| {
+                let value = region.assign_advice(|| "value", config.advice, 0, || self.value)?;
+                let range = region.assign_advice_from_instance(
+                    || "range",
+                    config.instance,
+                    0,
+                    config.advice,
+                    1,
+                )?;
+                Ok((value, range))
+            },
+        )?;
+        let value = config.range_check.assign(
+            layouter.namespace(|| "value fits in 32 bits"),
+            &value,
+            N_BITS,
+        )?;
+        LessThanChip::construct(config.less_than).assert_less_than(
+            layouter.namespace(|| "value < range"),
+            &value,
+            &range,
+        )
+    }
+}
+
+// Keys only depend on the circuit's shape, so the prover and verifier can each derive them
+fn keygen(params: &Params<EqAffine>) -> Result<ProvingKey<EqAffine>, Error> {
+    let empty_circuit = RangeProofCircuit::<Fp>::default();
+    let vk = keygen_vk(params, &empty_circuit)?;
+    keygen_pk(params, vk, &empty_circuit)
+}
+
+/// Proves `value < range`, returning the serialized proof. The transcript is hashed with Blake2b.
+pub(crate) fn prove_range_check(value: u32, range: u32) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    prove_range_check_with_transcript(value, range, &mut transcript)?;
+    Ok(transcript.finalize())
+}
+
+/// Proves `value < range`, writing the proof to `transcript`. The transcript's hash derives the proof's
+/// challenges, so a verifier has to read it back with the same one, e.g. the hash another ecosystem's verifier
+/// expects. This version of halo2 only ships Blake2b, but any `TranscriptWrite` can be passed in.
+pub(crate) fn prove_range_check_with_transcript<E, T>(
+    value: u32,
+    range: u32,
+    transcript: &mut T,
+) -> Result<(), Error>
+where
+    E: EncodedChallenge<EqAffine>,
+    T: TranscriptWrite<EqAffine, E>,
+{
+    let params: Params<EqAffine> = Params::new(K);
+    let pk = keygen(&params)?;
+    let circuit = RangeProofCircuit {
+        value: Value::known(Fp::from(value as u64)),
+    };
+    let instance = [Fp::from(range as u64)];
+    create_proof(&params, &pk, &[circuit], &[&[&instance]], OsRng, transcript)
+}
+
+/// Verifies a proof from prove_range_check against the public `range`.
+pub(crate) fn verify_range_check(proof: &[u8], range: u32) -> Result<(), Error> {
+    let params: Params<EqAffine> = Params::new(K);
+    let vk = range_check_vk(&params)?;
+    verify_range_check_with_vk(&params, &vk, proof, range)
+}
+
+/// The verifying key for the range proof under `params`.
+///
+/// This version of halo2 can write and read `Params` but not a `VerifyingKey`, so there is no VK to ship as bytes.
+/// Instead a verifier in another process reads the params with `Params::read` and derives the same key from
+/// the circuit's shape here, since keygen is deterministic.
+pub(crate) fn range_check_vk(params: &Params<EqAffine>) -> Result<VerifyingKey<EqAffine>, Error> {
+    keygen_vk(params, &RangeProofCircuit::<Fp>::default())
+}
+
+/// Verifies a proof from prove_range_check against the public `range`, with a key from range_check_vk.
+pub(crate) fn verify_range_check_with_vk(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    range: u32,
+) -> Result<(), Error> {
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    verify_range_check_with_transcript(params, vk, &mut transcript, range)
+}
+
+/// Verifies a proof from prove_range_check_with_transcript against the public `range`, reading it from
+/// `transcript`, which must hash the same way as the prover's.
+pub(crate) fn verify_range_check_with_transcript<E, T>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    transcript: &mut T,
+    range: u32,
+) -> Result<(), Error>
+where
+    E: EncodedChallenge<EqAffine>,
+    T: TranscriptRead<EqAffine, E>,
+{
+    let instance = [Fp::from(range as u64)];
+    let strategy = SingleVerifier::new(params);
+    verify_proof(params, vk, strategy, &[&[&instance]], transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::min_k;
+
+    #[test]
+    fn test_range_proof_k() {
+        let circuit = RangeProofCircuit {
+            value: Value::known(Fp::from(5)),
+        };
+        assert!(min_k(&circuit, vec![vec![Fp::from(10)]]) <= K);
+    }
+
+    #[test]
+    fn test_range_proof() {
+        let proof = prove_range_check(5, 10).unwrap();
+        assert!(verify_range_check(&proof, 10).is_ok());
+        // The proof is bound to the public range it was made for
+        assert!(verify_range_check(&proof, 5).is_err());
+
+        for (value, range) in [(0, 1), (u32::MAX - 1, u32::MAX)] {
+            let proof = prove_range_check(value, range).unwrap();
+            assert!(verify_range_check(&proof, range).is_ok());
+        }
+
+        // Proving doesn't check the constraints, but the proof won't verify
+        for (value, range) in [(10, 10), (11, 10), (u32::MAX, 0)] {
+            let proof = prove_range_check(value, range).unwrap();
+            assert!(verify_range_check(&proof, range).is_err());
+        }
+    }
+
+    #[test]
+    fn test_range_proof_transcripts() {
+        let params: Params<EqAffine> = Params::new(K);
+        let vk = range_check_vk(&params).unwrap();
+
+        // Blake2b is the only transcript hash this version of halo2 has
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+        prove_range_check_with_transcript(5, 10, &mut transcript).unwrap();
+        let proof = transcript.finalize();
+        for (range, ok) in [(10, true), (5, false)] {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+            let verified = verify_range_check_with_transcript(&params, &vk, &mut transcript, range);
+            assert_eq!(verified.is_ok(), ok, "{}", range);
+        }
+        // It's the same proof as the default path makes
+        assert!(verify_range_check(&proof, 10).is_ok());
+    }
+
+    #[test]
+    fn test_verify_with_params_from_bytes() {
+        let params: Params<EqAffine> = Params::new(K);
+        let mut params_bytes = vec![];
+        params.write(&mut params_bytes).unwrap();
+        let proof = prove_range_check(5, 10).unwrap();
+
+        // As a separate verifier would, starting from nothing but the params bytes
+        let params = Params::<EqAffine>::read(&mut &params_bytes[..]).unwrap();
+        let vk = range_check_vk(&params).unwrap();
+        assert!(verify_range_check_with_vk(&params, &vk, &proof, 10).is_ok());
+        assert!(verify_range_check_with_vk(&params, &vk, &proof, 5).is_err());
+
+        // The derived key is the one the prover used
+        let pk = keygen(&params).unwrap();
+        assert_eq!(
+            format!("{:?}", vk.pinned()),
+            format!("{:?}", pk.get_vk().pinned())
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{ConstraintSystem, Error},
+};
+use std::marker::PhantomData;
+
+use crate::decompose_range_check::{
+    plan_decomposition, DecomposeRangeCheckConfig, DecomposedValue, NUM_BITS,
+};
+
+/// Checks that a `range_bits`-bit value is a repdigit in base $2^K$, i.e. that all of its $K$-bit windows
+/// $w_i$ are the same digit, such as `0b011_011_011`. The decompose chip splits the value into windows and
+/// range-checks it, and copy constraints then tie each window to the next:
+///     $$w_0 = w_1 = \dots = w_{n-1}$$
+/// No gate is needed, so this only adds equality constraints on top of the decomposition.
+#[derive(Debug, Clone)]
+struct RepdigitConfig<F: PrimeField> {
+    decompose: DecomposeRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> RepdigitConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, range_bits: usize) -> Self {
+        // A short top window would cap the digit below 2^K rather than check it
+        assert!(
+            range_bits.is_multiple_of(NUM_BITS),
+            "A repdigit needs whole {}-bit windows, not {} bits",
+            NUM_BITS,
+            range_bits
+        );
+        let plan = plan_decomposition(range_bits, NUM_BITS);
+        Self {
+            decompose: DecomposeRangeCheckConfig::configure_with_plan(meta, plan),
+        }
+    }
+
+    fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.decompose.load_table(layouter)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RepdigitChip<F: PrimeField> {
+    config: RepdigitConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> RepdigitChip<F> {
+    fn construct(config: RepdigitConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, range_bits: usize) -> RepdigitConfig<F> {
+        RepdigitConfig::configure(meta, range_bits)
+    }
+
+    /// Decomposes `value` and constrains its windows to all be equal, returning the decomposition and the
+    /// common digit, which is the lowest window.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<(DecomposedValue<F>, AssignedCell<F, F>), Error> {
+        let decomposed =
+            self.config
+                .decompose
+                .assign_cell(layouter.namespace(|| "decompose"), None, value)?;
+        layouter.assign_region(
+            || "repdigit",
+            |mut region| {
+                for pair in decomposed.windows.windows(2) {
+                    region.constrain_equal(pair[0].0.cell(), pair[1].0.cell())?;
+                }
+                Ok(())
+            },
+        )?;
+        let digit = decomposed.windows[0].0.clone();
+        Ok((decomposed, digit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    // Three 3-bit windows
+    const RANGE_BITS: usize = 9;
+
+    // Checks the private value is a repdigit, and exposes its digit at instance row 0
+    #[derive(Default)]
+    struct RepdigitCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for RepdigitCircuit<F> {
+        type Config = (RepdigitConfig<F>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (RepdigitChip::configure(meta, RANGE_BITS), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_table(&mut layouter)?;
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            let (_, digit) = RepdigitChip::construct(config)
+                .assign(layouter.namespace(|| "repdigit"), &value)?;
+            layouter.constrain_instance(digit.cell(), instance, 0)
+        }
+    }
+
+    fn run(value: u64, digit: u64) -> bool {
+        let circuit = RepdigitCircuit {
+            value: Value::known(Fp::from(value)),
+        };
+        run_mock_auto(&circuit, vec![vec![Fp::from(digit)]])
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_repdigit() {
+        for (value, digit) in [(0b011_011_011, 3), (0, 0), (0b001_001_001, 1), (0o777, 7)] {
+            assert!(run(value, digit), "{:#o}", value);
+            assert!(!run(value, digit + 1), "{:#o}", value);
+        }
+    }
+
+    #[test]
+    fn test_not_repdigit() {
+        // One window off, whichever digit is claimed, and a value with a leading zero window
+        for value in [0b011_011_010, 0b111_011_011, 0b000_011_011, 0o123] {
+            for digit in 0..8 {
+                assert!(!run(value, digit), "{:#o}", value);
+            }
+        }
+        // 0o7777 repeats its digit, but doesn't fit in 9 bits
+        assert!(!run(0o7777, 7));
+    }
+
+    #[test]
+    #[should_panic(expected = "A repdigit needs whole 3-bit windows")]
+    fn test_repdigit_short_window() {
+        RepdigitChip::<Fp>::configure(&mut ConstraintSystem::default(), 8);
+    }
+}
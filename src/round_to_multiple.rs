@@ -0,0 +1,227 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::mod_reduce::{ModReduceChip, ModReduceConfig, QuotientAndRemainder};
+
+/// Rounds `value` down to a multiple of `m`, for fixed-point rounding. The mod-reduce chip witnesses
+///     $$value = q \cdot m + r, \quad r < m$$
+/// and the rounded value is then constrained to $value - r$, which is $q \cdot m$, the largest multiple of $m$
+/// not above the value. As in mod reduce, $m$ and the quotient must fit in N_BITS bits.
+#[derive(Debug, Clone)]
+struct RoundToMultipleConfig<F: PrimeField, const N_BITS: usize> {
+    value: Column<Advice>,
+    r: Column<Advice>,
+    rounded: Column<Advice>,
+    q_round: Selector,
+    mod_reduce: ModReduceConfig<F, N_BITS>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> RoundToMultipleConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let r = meta.advice_column();
+        let rounded = meta.advice_column();
+        let q_round = meta.selector();
+        let mod_reduce = ModReduceChip::configure(meta);
+        for column in [value, r, rounded] {
+            meta.enable_equality(column);
+        }
+
+        //     value   |    r    |   rounded   | q_round
+        //    ----------------------------------------------
+        //       v     |    r    |    v - r    |    1
+        meta.create_gate("round down", |meta| {
+            let q = meta.query_selector(q_round);
+            let value = meta.query_advice(value, Rotation::cur());
+            let r = meta.query_advice(r, Rotation::cur());
+            let rounded = meta.query_advice(rounded, Rotation::cur());
+            Constraints::with_selector(q, [("rounded = value - r", rounded - (value - r))])
+        });
+
+        Self {
+            value,
+            r,
+            rounded,
+            q_round,
+            mod_reduce,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RoundToMultipleChip<F: PrimeField, const N_BITS: usize> {
+    config: RoundToMultipleConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> RoundToMultipleChip<F, N_BITS> {
+    fn construct(config: RoundToMultipleConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> RoundToMultipleConfig<F, N_BITS> {
+        RoundToMultipleConfig::configure(meta)
+    }
+
+    /// Returns the constrained `(q, r)` such that `value = q * multiple + r` and `r < multiple`.
+    /// `r` is zero exactly when `value` is already a multiple.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        multiple: &AssignedCell<F, F>,
+    ) -> Result<QuotientAndRemainder<F>, Error> {
+        ModReduceChip::construct(self.config.mod_reduce.clone()).assign(layouter, value, multiple)
+    }
+
+    /// Returns a cell constrained to `value - r`, i.e. `value` rounded down to a multiple, given the remainder
+    /// `r` that assign returned for it.
+    fn round_down(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        r: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "round down",
+            |mut region| {
+                let offset = 0;
+                config.q_round.enable(&mut region, offset)?;
+                let value = value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                let r = r.copy_advice(|| "r", &mut region, config.r, offset)?;
+                let rounded = value.value().zip(r.value()).map(|(value, r)| *value - *r);
+                region.assign_advice(|| "rounded", config.rounded, offset, || rounded)
+            },
+        )
+    }
+
+    // Like assign, but with the given witness for (q, r) in place of the honest division
+    fn assign_with_remainder(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        multiple: &AssignedCell<F, F>,
+        q_and_r: Value<(F, F)>,
+    ) -> Result<QuotientAndRemainder<F>, Error> {
+        ModReduceChip::construct(self.config.mod_reduce.clone())
+            .assign_q_and_r(layouter, value, multiple, q_and_r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 8;
+
+    // Rounds `value` to a multiple of `multiple`, exposing q, r and the rounded value at instance rows 0, 1
+    // and 2. The witness for q and r can be forged.
+    #[derive(Default)]
+    struct RoundToMultipleCircuit<F: PrimeField> {
+        value: Value<F>,
+        multiple: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for RoundToMultipleCircuit<F> {
+        type Config = (
+            RoundToMultipleConfig<F, N_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type Witness = Value<(F, F)>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (RoundToMultipleChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            q_and_r: Option<&Value<(F, F)>>,
+        ) -> Result<(), Error> {
+            let (value, multiple) = layouter.assign_region(
+                || "Load value and multiple",
+                |mut region| {
+                    let value = region.assign_advice(|| "value", input, 0, || self.value)?;
+                    let multiple =
+                        region.assign_advice(|| "multiple", input, 1, || self.multiple)?;
+                    Ok((value, multiple))
+                },
+            )?;
+            let chip = RoundToMultipleChip::construct(config);
+            let (q, r) = match q_and_r {
+                Some(q_and_r) => chip.assign_with_remainder(
+                    layouter.namespace(|| "reduce"),
+                    &value,
+                    &multiple,
+                    *q_and_r,
+                ),
+                None => chip.assign(layouter.namespace(|| "reduce"), &value, &multiple),
+            }?;
+            let rounded = chip.round_down(layouter.namespace(|| "round down"), &value, &r)?;
+            for (row, cell) in [q, r, rounded].iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(value: u64, multiple: u64) -> Forged<Fp, RoundToMultipleCircuit<Fp>> {
+        Forged::honest(RoundToMultipleCircuit {
+            value: Value::known(Fp::from(value)),
+            multiple: Value::known(Fp::from(multiple)),
+        })
+    }
+
+    fn public_inputs(q: u64, r: u64, rounded: u64) -> [Fp; 3] {
+        [q, r, rounded].map(Fp::from)
+    }
+
+    #[test]
+    fn test_round_exact_multiples() {
+        for (value, multiple) in [(0, 5), (40, 8), (250, 10), (255, 255), (7, 1)] {
+            let q = value / multiple;
+            assert!(run(&circuit(value, multiple), &public_inputs(q, 0, value)));
+        }
+    }
+
+    #[test]
+    fn test_round_non_multiples() {
+        for (value, multiple) in [(17, 5), (3, 10), (255, 16), (1001, 100)] {
+            let (q, r) = (value / multiple, value % multiple);
+            let circuit = circuit(value, multiple);
+            assert!(run(&circuit, &public_inputs(q, r, value - r)));
+            // Rounding up, or not rounding at all, is rejected
+            for (q, r, rounded) in [(q + 1, 0, value - r + multiple), (q, 0, value)] {
+                assert!(!run(&circuit, &public_inputs(q, r, rounded)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_malicious_remainder() {
+        // 17 = 2 * 5 + 7 satisfies value = q * m + r, and would round 17 down to 10, but r >= m
+        let malicious = circuit(17, 5).forge(Value::known((Fp::from(2), Fp::from(7))));
+        assert!(!run(&malicious, &public_inputs(2, 7, 10)));
+
+        let honest = circuit(17, 5).forge(Value::known((Fp::from(3), Fp::from(2))));
+        assert!(run(&honest, &public_inputs(3, 2, 15)));
+    }
+}
@@ -0,0 +1,285 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::less_than::{field_cmp, LessThanChip, LessThanConfig};
+
+/// Proves the running maximum of a sequence $v_0, \dots, v_{n-1}$, i.e. $m_i = \max(v_0, \dots, v_i)$.
+/// The first maximum is $v_0$ itself, and each later one selects between the previous maximum and the new
+/// value on the less-than chip's $lt_i = (m_{i-1} < v_i)$:
+///     $$m_i = lt_i \cdot v_i + (1 - lt_i) \cdot m_{i-1}$$
+/// $lt_i$ is copied from the comparison, so the prover can't pick the wrong side.
+///
+/// As with the less-than gadget, the values must already be known to fit in N_BITS bits.
+#[derive(Debug, Clone)]
+struct RunningMaxConfig<F: PrimeField, const N_BITS: usize> {
+    prev_max: Column<Advice>,
+    value: Column<Advice>,
+    lt: Column<Advice>,
+    max: Column<Advice>,
+    q_max: Selector,
+    less_than: LessThanConfig<F, N_BITS>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> RunningMaxConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let prev_max = meta.advice_column();
+        let value = meta.advice_column();
+        let lt = meta.advice_column();
+        let max = meta.advice_column();
+        let q_max = meta.selector();
+        let less_than = LessThanChip::configure(meta);
+        for column in [prev_max, value, lt, max] {
+            meta.enable_equality(column);
+        }
+
+        //    prev_max  |  value  |   lt   |   max   | q_max
+        //    ------------------------------------------------
+        //     m_{i-1}  |   v_i   |  lt_i  |   m_i   |   1
+        meta.create_gate("running max", |meta| {
+            let q = meta.query_selector(q_max);
+            let prev_max = meta.query_advice(prev_max, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let max = meta.query_advice(max, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                q,
+                [(
+                    "max = lt * value + (1 - lt) * prev_max",
+                    max - (lt.clone() * value + (one - lt) * prev_max),
+                )],
+            )
+        });
+
+        Self {
+            prev_max,
+            value,
+            lt,
+            max,
+            q_max,
+            less_than,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RunningMaxChip<F: PrimeField, const N_BITS: usize> {
+    config: RunningMaxConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> RunningMaxChip<F, N_BITS> {
+    fn construct(config: RunningMaxConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> RunningMaxConfig<F, N_BITS> {
+        RunningMaxConfig::configure(meta)
+    }
+
+    /// Returns a cell per value, constrained to the maximum of the values up to and including it. The first is
+    /// the first value's own cell.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut max = Value::unknown();
+        let maxes = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let value = value.value().copied();
+                max = if i == 0 {
+                    value
+                } else {
+                    max.zip(value).map(|(max, value)| {
+                        if field_cmp(&max, &value).is_lt() {
+                            value
+                        } else {
+                            max
+                        }
+                    })
+                };
+                max
+            })
+            .collect();
+        self.assign_maxes(layouter, values, maxes)
+    }
+
+    // Like assign, but with the given witness for each maximum after the first
+    fn assign_maxes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+        maxes: Vec<Value<F>>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        let less_than = LessThanChip::construct(config.less_than.clone());
+        let Some(first) = values.first() else {
+            return Ok(vec![]);
+        };
+        let mut out = vec![first.clone()];
+        for (i, (value, max)) in values.iter().zip(maxes).enumerate().skip(1) {
+            let prev_max = &out[i - 1];
+            let lt = less_than.assign(
+                layouter.namespace(|| format!("m_{} < v_{}", i - 1, i)),
+                prev_max,
+                value,
+            )?;
+            let max = layouter.assign_region(
+                || "running max",
+                |mut region| {
+                    let offset = 0;
+                    config.q_max.enable(&mut region, offset)?;
+                    prev_max.copy_advice(|| "prev_max", &mut region, config.prev_max, offset)?;
+                    value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                    lt.copy_advice(|| "lt", &mut region, config.lt, offset)?;
+                    region.assign_advice(|| format!("m_{}", i), config.max, offset, || max)
+                },
+            )?;
+            out.push(max);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    const N_BITS: usize = 8;
+
+    // Takes the running maximum of the private values, exposing the i-th maximum at instance row i. The witness
+    // for the maxima can be forged.
+    #[derive(Default)]
+    struct RunningMaxCircuit<F: PrimeField> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for RunningMaxCircuit<F> {
+        type Config = (
+            RunningMaxConfig<F, N_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type Witness = Vec<Value<F>>;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (RunningMaxChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            maxes: Option<&Vec<Value<F>>>,
+        ) -> Result<(), Error> {
+            let values = layouter.assign_region(
+                || "Load values",
+                |mut region| {
+                    self.values
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, value)| {
+                            region.assign_advice(|| "value", input, offset, || *value)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()
+                },
+            )?;
+            let chip = RunningMaxChip::construct(config);
+            let maxes = match maxes {
+                Some(maxes) => {
+                    chip.assign_maxes(layouter.namespace(|| "running max"), &values, maxes.clone())
+                }
+                None => chip.assign(layouter.namespace(|| "running max"), &values),
+            }?;
+            for (row, max) in maxes.iter().enumerate() {
+                layouter.constrain_instance(max.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(values: &[u64]) -> Forged<Fp, RunningMaxCircuit<Fp>> {
+        Forged::honest(RunningMaxCircuit {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        })
+    }
+
+    // The maxima as the public inputs
+    fn public(maxes: &[u64]) -> Vec<Fp> {
+        maxes.iter().map(|max| Fp::from(*max)).collect()
+    }
+
+    // The host reference
+    fn running_max(values: &[u64]) -> Vec<u64> {
+        values
+            .iter()
+            .scan(0, |max, value| {
+                *max = (*max).max(*value);
+                Some(*max)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_running_max() {
+        for values in [
+            vec![],
+            vec![7],
+            vec![1, 2, 3, 10],
+            vec![10, 3, 2, 1],
+            vec![4, 4, 9, 9, 0, 255, 3],
+            // A longer run with the maximum climbing in uneven steps
+            vec![93, 17, 130, 130, 44, 0, 201, 12, 254, 7, 255, 88],
+        ] {
+            let maxes = running_max(&values);
+            assert!(run(&circuit(&values), &public(&maxes)), "{:?}", values);
+        }
+    }
+
+    #[test]
+    fn test_running_max_wrong_output() {
+        let values = [3, 8, 5, 200, 1];
+        assert_eq!(running_max(&values), [3, 8, 8, 200, 200]);
+        // The running minimum, the values themselves, and a maximum that lags one step behind
+        for wrong in [[3, 3, 3, 3, 1], values, [3, 3, 8, 8, 200]] {
+            assert!(!run(&circuit(&values), &public(&wrong)), "{:?}", wrong);
+        }
+    }
+
+    #[test]
+    fn test_running_max_forged() {
+        // Claiming 5 is still the maximum after 8, witnessed consistently with the public output
+        let values = [3, 5, 8, 1];
+        let forged = [3, 5, 5, 5];
+        let lagging =
+            circuit(&values).forge(public(&forged).into_iter().map(Value::known).collect());
+        assert!(!run(&lagging, &public(&forged)));
+        // A maximum above every value fails just the same
+        let inflated = circuit(&values).forge(vec![Value::known(Fp::from(9)); values.len()]);
+        assert!(!run(&inflated, &public(&[3, 9, 9, 9])));
+    }
+}
@@ -0,0 +1,347 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{decompose_range_check::field_to_u128, table::RangeTableConfig};
+
+/// Range-checks a value to $W \cdot K$ bits with a running sum $z$ in a single advice column, where $K$ is WINDOW.
+///     $$z_0 = \alpha, \quad z_{i+1} = (z_i - k_i) / 2^K, \quad z_W = 0$$
+/// Each window $k_i = z_i - 2^K z_{i+1}$ is looked up in a $0..2^K$ table, so
+///     $$\alpha = k_0 + 2^K k_1 + ... + 2^{(W-1)K} k_{W-1}$$
+/// with no separate column for the windows. This is the layout the decompose module's docs describe,
+/// taking $W + 1$ rows of one column rather than $W$ rows of two.
+///
+/// configure_base replaces $2^K$ with any base $B$, such as 10, so that the windows are the digits of
+///     $$\alpha = d_0 + B d_1 + ... + B^{W-1} d_{W-1}$$
+/// each looked up in a $0..B$ table, and the value is checked to be in $0..B^W$. WINDOW is unused then.
+#[derive(Debug, Clone)]
+pub(crate) struct RunningSumRangeCheckConfig<F: PrimeField, const WINDOW: usize> {
+    z: Column<Advice>,
+    q_range_check: Selector,
+    q_zero: Selector,
+    // The table size depends on the base, which can't be spelled as a const generic, so it is filled from entries
+    table: RangeTableConfig<F, 0>,
+    base: u64,
+}
+
+impl<F: PrimeField, const WINDOW: usize> RunningSumRangeCheckConfig<F, WINDOW> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self::configure_base(meta, 1 << WINDOW)
+    }
+
+    fn configure_base(meta: &mut ConstraintSystem<F>, base: u64) -> Self {
+        assert!(
+            base >= 2,
+            "A base {} running sum never shifts a digit out",
+            base
+        );
+        let z = meta.advice_column();
+        let q_range_check = meta.complex_selector();
+        let q_zero = meta.selector();
+        // Tables are a power of two long, so other bases repeat the zero digit to fill it out
+        let mut digits: Vec<F> = (0..base).map(F::from).collect();
+        digits.resize(digits.len().next_power_of_two(), F::ZERO);
+        let table = RangeTableConfig::with_entries(meta, digits);
+        // Needed to copy the checked cell into z_0
+        meta.enable_equality(z);
+
+        //        z       | q_range_check |   q_zero
+        //       ---------------------------------------
+        //       z_0      |       1       |     0
+        //       z_1      |       1       |     0
+        //       ...      |      ...      |    ...
+        //       z_W      |       0       |     1
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_range_check);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let window = z_cur - z_next * Expression::Constant(F::from(base));
+            vec![(q * window, table.value)]
+        });
+
+        meta.create_gate("z_W = 0", |meta| {
+            let q = meta.query_selector(q_zero);
+            let z = meta.query_advice(z, Rotation::cur());
+            Constraints::with_selector(q, [("z_W = 0", z)])
+        });
+
+        Self {
+            z,
+            q_range_check,
+            q_zero,
+            table,
+            base,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RunningSumRangeCheck<F: PrimeField, const WINDOW: usize> {
+    config: RunningSumRangeCheckConfig<F, WINDOW>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const WINDOW: usize> RunningSumRangeCheck<F, WINDOW> {
+    pub(crate) fn construct(config: RunningSumRangeCheckConfig<F, WINDOW>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> RunningSumRangeCheckConfig<F, WINDOW> {
+        RunningSumRangeCheckConfig::configure(meta)
+    }
+
+    pub(crate) fn configure_base(
+        meta: &mut ConstraintSystem<F>,
+        base: u64,
+    ) -> RunningSumRangeCheckConfig<F, WINDOW> {
+        RunningSumRangeCheckConfig::configure_base(meta, base)
+    }
+
+    /// The number of rows a check of `num_windows` windows takes up.
+    fn rows_for(num_windows: usize) -> usize {
+        num_windows + 1
+    }
+
+    pub(crate) fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.config.table.load(layouter)
+    }
+
+    /// Copies `value` into z_0 and constrains it to `0..B^num_windows`, i.e. `0..2^(num_windows * WINDOW)`
+    /// unless configured with another base.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_windows: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.assign_z(layouter, value, num_windows)?.swap_remove(0))
+    }
+
+    /// Like assign, but returns all of $z_0, \dots, z_W$. In base $B$, $z_i$ is the value with its lowest $i$
+    /// digits shifted out, so it's zero from the first digit above the highest nonzero one.
+    pub(crate) fn assign_z(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_windows: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let base = self.config.base as u128;
+        // An out-of-range value never reaches zero, so the honest witness still fails the z_W = 0 gate
+        let digits = value
+            .value()
+            .map(|value| {
+                let mut rest = field_to_u128(*value);
+                (0..num_windows)
+                    .map(|_| {
+                        let digit = rest % base;
+                        rest /= base;
+                        F::from_u128(digit)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .transpose_vec(num_windows);
+        self.assign_z_digits(layouter, value, digits)
+    }
+
+    // Like assign, but with the given witness for the digits, least significant first
+    fn assign_digits(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        digits: Vec<Value<F>>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self
+            .assign_z_digits(layouter, value, digits)?
+            .swap_remove(0))
+    }
+
+    fn assign_z_digits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        digits: Vec<Value<F>>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        let base_inv = F::from(config.base).invert().unwrap();
+        layouter.assign_region(
+            || format!("running sum {} windows", digits.len()),
+            |mut region| {
+                let mut zs = vec![value.copy_advice(|| "z_0", &mut region, config.z, 0)?];
+
+                // Shift one digit out of the running sum per row
+                let mut z = zs[0].value().copied();
+                for (i, digit) in digits.iter().enumerate() {
+                    config.q_range_check.enable(&mut region, i)?;
+                    z = (z - digit).map(|z| z * base_inv);
+                    zs.push(region.assign_advice(
+                        || format!("z_{}", i + 1),
+                        config.z,
+                        i + 1,
+                        || z,
+                    )?);
+                }
+                config.q_zero.enable(&mut region, digits.len())?;
+
+                Ok(zs)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+    use crate::{
+        decompose_range_check::{plan_decomposition, DecomposeRangeCheckConfig},
+        testing::{run_mock_auto, ForgeableCircuit, Forged},
+    };
+
+    const WINDOW: usize = 3;
+    const NUM_WINDOWS: usize = 3;
+
+    #[derive(Default)]
+    struct RunningSumCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for RunningSumCircuit<F> {
+        type Config = (RunningSumRangeCheckConfig<F, WINDOW>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (RunningSumRangeCheck::configure(meta), input)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RunningSumRangeCheck::construct(config);
+            chip.load_table(&mut layouter)?;
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            chip.assign(layouter.namespace(|| "range check"), &value, NUM_WINDOWS)?;
+            Ok(())
+        }
+    }
+
+    fn circuit(value: Fp) -> RunningSumCircuit<Fp> {
+        RunningSumCircuit {
+            value: Value::known(value),
+        }
+    }
+
+    #[test]
+    fn test_running_sum_range_check() {
+        for value in [0, 1, 7, 8, 100, 365, 511] {
+            run_mock_auto(&circuit(Fp::from(value)), vec![]).assert_satisfied();
+        }
+        for value in [Fp::from(512), Fp::from(1000), -Fp::ONE] {
+            assert!(run_mock_auto(&circuit(value), vec![]).verify().is_err());
+        }
+    }
+
+    // Checks the private value has at most 4 decimal digits. The witness for the digits can be forged.
+    #[derive(Default)]
+    struct DecimalCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for DecimalCircuit<F> {
+        type Config = (RunningSumRangeCheckConfig<F, 0>, Column<Advice>);
+        type Witness = Vec<Value<F>>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (RunningSumRangeCheck::configure_base(meta, 10), input)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input): Self::Config,
+            mut layouter: impl Layouter<F>,
+            digits: Option<&Vec<Value<F>>>,
+        ) -> Result<(), Error> {
+            let chip = RunningSumRangeCheck::construct(config);
+            chip.load_table(&mut layouter)?;
+            let value = layouter.assign_region(
+                || "Load value",
+                |mut region| region.assign_advice(|| "value", input, 0, || self.value),
+            )?;
+            match digits {
+                Some(digits) => chip.assign_digits(
+                    layouter.namespace(|| "decimal digits"),
+                    &value,
+                    digits.clone(),
+                ),
+                None => chip.assign(layouter.namespace(|| "decimal digits"), &value, 4),
+            }?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_running_sum_base_10() {
+        let circuit = |value: Fp| {
+            Forged::honest(DecimalCircuit {
+                value: Value::known(value),
+            })
+        };
+        for value in [0, 7, 10, 1234, 9009, 9999] {
+            run_mock_auto(&circuit(Fp::from(value)), vec![]).assert_satisfied();
+        }
+        for value in [Fp::from(10_000), Fp::from(65_536), -Fp::ONE] {
+            assert!(run_mock_auto(&circuit(value), vec![]).verify().is_err());
+        }
+
+        // 14 + 10 * (2 + 10 * (2 + 10 * 1)) is 1234 too, and reaches zero in 4 digits, but 14 isn't a digit
+        let digits = |digits: [u64; 4]| digits.map(|d| Value::known(Fp::from(d))).to_vec();
+        let honest = circuit(Fp::from(1234)).forge(digits([4, 3, 2, 1]));
+        run_mock_auto(&honest, vec![]).assert_satisfied();
+        let malicious = circuit(Fp::from(1234)).forge(digits([14, 2, 2, 1]));
+        assert!(run_mock_auto(&malicious, vec![]).verify().is_err());
+    }
+
+    #[test]
+    fn test_running_sum_layout() {
+        let plan = plan_decomposition(WINDOW * NUM_WINDOWS, WINDOW);
+
+        // The running sum needs one advice column where the two-column decomposition needs a value and a window column
+        let mut meta = ConstraintSystem::<Fp>::default();
+        RunningSumRangeCheck::<Fp, WINDOW>::configure(&mut meta);
+        assert_eq!(meta.num_advice_columns(), 1);
+        let mut meta = ConstraintSystem::<Fp>::default();
+        DecomposeRangeCheckConfig::configure_with_plan(&mut meta, plan.clone());
+        assert_eq!(meta.num_advice_columns(), 2);
+
+        // In exchange it takes one extra row, for z_W
+        assert_eq!(
+            RunningSumRangeCheck::<Fp, WINDOW>::rows_for(NUM_WINDOWS),
+            plan.num_windows() + 1
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+/// Proves that a vector of cells is sorted in non-decreasing order, by witnessing each adjacent difference
+///     $$d_i = v_{i+1} - v_i$$
+/// and range-checking it to N_BITS bits, so that $d_i$ is a small non-negative integer.
+///
+/// As with the less-than gadget, the values themselves must already be known to fit in N_BITS bits,
+/// otherwise a field-negative value can sit right below a small one.
+#[derive(Debug, Clone)]
+struct SortedConfig<F: PrimeField, const N_BITS: usize> {
+    value: Column<Advice>,
+    diff: Column<Advice>,
+    q_sorted: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> SortedConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let diff = meta.advice_column();
+        let q_sorted = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        meta.enable_equality(value);
+        meta.enable_equality(diff);
+
+        //     value   |   diff    | q_sorted
+        //    ------------------------------------
+        //      v_0    |    d_0    |    1
+        //      v_1    |    d_1    |    1
+        //      ...    |    ...    |   ...
+        //    v_{n-1}  |           |    0
+        meta.create_gate("sorted", |meta| {
+            let q = meta.query_selector(q_sorted);
+            let cur = meta.query_advice(value, Rotation::cur());
+            let next = meta.query_advice(value, Rotation::next());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            Constraints::with_selector(q, [("d = next - cur", diff - (next - cur))])
+        });
+
+        Self {
+            value,
+            diff,
+            q_sorted,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SortedChip<F: PrimeField, const N_BITS: usize> {
+    config: SortedConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> SortedChip<F, N_BITS> {
+    fn construct(config: SortedConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SortedConfig<F, N_BITS> {
+        SortedConfig::configure(meta)
+    }
+
+    /// Constrains `values` to be in non-decreasing order. Equal neighbours are allowed.
+    fn assert_sorted(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let diffs = layouter.assign_region(
+            || "sorted",
+            |mut region| {
+                let values = values
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, value)| {
+                        value.copy_advice(
+                            || format!("v_{}", offset),
+                            &mut region,
+                            config.value,
+                            offset,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+
+                values
+                    .windows(2)
+                    .enumerate()
+                    .map(|(offset, pair)| {
+                        config.q_sorted.enable(&mut region, offset)?;
+                        let diff = pair[1].value().copied() - pair[0].value();
+                        region.assign_advice(
+                            || format!("d_{}", offset),
+                            config.diff,
+                            offset,
+                            || diff,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+
+        for (i, diff) in diffs.iter().enumerate() {
+            config.range.assign(
+                layouter.namespace(|| format!("range check d_{}", i)),
+                diff,
+                N_BITS,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::{FailureLocation, VerifyFailure},
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const N_BITS: usize = 8;
+
+    #[derive(Default)]
+    struct SortedCircuit<F: PrimeField> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for SortedCircuit<F> {
+        type Config = (SortedConfig<F, N_BITS>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (SortedChip::configure(meta), input)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let values = layouter.assign_region(
+                || "Load values",
+                |mut region| {
+                    self.values
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, value)| {
+                            region.assign_advice(|| "value", input, offset, || *value)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()
+                },
+            )?;
+            SortedChip::construct(config).assert_sorted(layouter.namespace(|| "sorted"), &values)
+        }
+    }
+
+    fn circuit(values: &[u64]) -> SortedCircuit<Fp> {
+        SortedCircuit {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_sorted() {
+        // Including equal neighbours, and the largest step that fits in N_BITS
+        for values in [
+            vec![],
+            vec![7],
+            vec![1, 2, 3, 10],
+            vec![4, 4, 4, 9, 9],
+            vec![0, 255],
+        ] {
+            run_mock_auto(&circuit(&values), vec![]).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_unsorted() {
+        // A step down wraps d around the field, and a step of 256 doesn't fit in N_BITS.
+        // Either way only the range check of that pair's difference fails.
+        for (values, index) in [
+            (vec![1, 2, 3, 2, 5], 2),
+            (vec![255, 0], 0),
+            (vec![3, 3, 4, 4, 3], 3),
+            (vec![0, 10, 266], 1),
+        ] {
+            let failures = run_mock_auto(&circuit(&values), vec![])
+                .verify()
+                .unwrap_err();
+            match &failures[..] {
+                [VerifyFailure::ConstraintNotSatisfied { location, .. }] => assert_eq!(
+                    *location,
+                    FailureLocation::InRegion {
+                        // After the "Load values" and "sorted" regions
+                        region: (2 + index, "range check 8 bits").into(),
+                        offset: N_BITS,
+                    }
+                ),
+                _ => panic!("Unexpected failures {:?}", failures),
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+/// Checks that `value` lies in the open interval between the cells `lo` and `hi`, $lo < value < hi$.
+/// The gaps to either end are witnessed as
+///     $$d_{lo} = value - lo - 1, \quad d_{hi} = hi - value - 1$$
+/// and range-checked to N_BITS bits, so each is non-negative. A value on or past either end makes its gap
+/// wrap around the field, far out of range. The three inputs are range-checked to N_BITS bits too, since a
+/// field-negative `lo` could otherwise sit just below any value.
+#[derive(Debug, Clone)]
+struct StrictlyBetweenConfig<F: PrimeField, const N_BITS: usize> {
+    lo: Column<Advice>,
+    value: Column<Advice>,
+    hi: Column<Advice>,
+    above_lo: Column<Advice>,
+    below_hi: Column<Advice>,
+    q_between: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> StrictlyBetweenConfig<F, N_BITS> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let lo = meta.advice_column();
+        let value = meta.advice_column();
+        let hi = meta.advice_column();
+        let above_lo = meta.advice_column();
+        let below_hi = meta.advice_column();
+        let q_between = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        for column in [lo, value, hi, above_lo, below_hi] {
+            meta.enable_equality(column);
+        }
+
+        //     lo    |  value  |   hi    |     above_lo     |     below_hi     | q_between
+        //    ---------------------------------------------------------------------------------
+        //     lo    |    v    |   hi    |    v - lo - 1    |    hi - v - 1    |     1
+        meta.create_gate("strictly between", |meta| {
+            let q = meta.query_selector(q_between);
+            let lo = meta.query_advice(lo, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+            let hi = meta.query_advice(hi, Rotation::cur());
+            let above_lo = meta.query_advice(above_lo, Rotation::cur());
+            let below_hi = meta.query_advice(below_hi, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "above_lo = value - lo - 1",
+                        above_lo - (value.clone() - lo - one.clone()),
+                    ),
+                    ("below_hi = hi - value - 1", below_hi - (hi - value - one)),
+                ],
+            )
+        });
+
+        Self {
+            lo,
+            value,
+            hi,
+            above_lo,
+            below_hi,
+            q_between,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StrictlyBetweenChip<F: PrimeField, const N_BITS: usize> {
+    config: StrictlyBetweenConfig<F, N_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_BITS: usize> StrictlyBetweenChip<F, N_BITS> {
+    fn construct(config: StrictlyBetweenConfig<F, N_BITS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> StrictlyBetweenConfig<F, N_BITS> {
+        StrictlyBetweenConfig::configure(meta)
+    }
+
+    /// Constrains `lo < value < hi`, returning the range-checked copy of `value`.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        lo: &AssignedCell<F, F>,
+        value: &AssignedCell<F, F>,
+        hi: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let (value, above_lo, below_hi) = layouter.assign_region(
+            || "strictly between",
+            |mut region| {
+                let offset = 0;
+                config.q_between.enable(&mut region, offset)?;
+                let lo = lo.copy_advice(|| "lo", &mut region, config.lo, offset)?;
+                let value = value.copy_advice(|| "value", &mut region, config.value, offset)?;
+                let hi = hi.copy_advice(|| "hi", &mut region, config.hi, offset)?;
+                let above_lo = value
+                    .value()
+                    .zip(lo.value())
+                    .map(|(value, lo)| *value - *lo - F::ONE);
+                let below_hi = hi
+                    .value()
+                    .zip(value.value())
+                    .map(|(hi, value)| *hi - *value - F::ONE);
+                let above_lo =
+                    region.assign_advice(|| "above_lo", config.above_lo, offset, || above_lo)?;
+                let below_hi =
+                    region.assign_advice(|| "below_hi", config.below_hi, offset, || below_hi)?;
+                Ok((value, above_lo, below_hi))
+            },
+        )?;
+
+        for (name, cell) in [
+            ("lo", lo),
+            ("hi", hi),
+            ("above_lo", &above_lo),
+            ("below_hi", &below_hi),
+        ] {
+            config.range.assign(
+                layouter.namespace(|| format!("range check {}", name)),
+                cell,
+                N_BITS,
+            )?;
+        }
+        config
+            .range
+            .assign(layouter.namespace(|| "range check value"), &value, N_BITS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::testing::run_mock_auto;
+
+    const N_BITS: usize = 8;
+
+    // Reads `lo` and `hi` from instance rows 0 and 1, and checks the private value lies strictly between them
+    #[derive(Default)]
+    struct StrictlyBetweenCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for StrictlyBetweenCircuit<F> {
+        type Config = (
+            StrictlyBetweenConfig<F, N_BITS>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (StrictlyBetweenChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (lo, value, hi) = layouter.assign_region(
+                || "Load bounds and value",
+                |mut region| {
+                    let lo = region.assign_advice_from_instance(|| "lo", instance, 0, input, 0)?;
+                    let value = region.assign_advice(|| "value", input, 1, || self.value)?;
+                    let hi = region.assign_advice_from_instance(|| "hi", instance, 1, input, 2)?;
+                    Ok((lo, value, hi))
+                },
+            )?;
+            StrictlyBetweenChip::construct(config).assign(
+                layouter.namespace(|| "lo < value < hi"),
+                &lo,
+                &value,
+                &hi,
+            )?;
+            Ok(())
+        }
+    }
+
+    fn run(lo: Fp, value: u64, hi: u64) -> bool {
+        let circuit = StrictlyBetweenCircuit {
+            value: Value::known(Fp::from(value)),
+        };
+        run_mock_auto(&circuit, vec![vec![lo, Fp::from(hi)]])
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_strictly_between() {
+        for (lo, value, hi) in [
+            (10, 11, 20),
+            (10, 15, 20),
+            (10, 19, 20),
+            (0, 1, 2),
+            (0, 254, 255),
+        ] {
+            assert!(run(Fp::from(lo), value, hi), "{} < {} < {}", lo, value, hi);
+        }
+    }
+
+    #[test]
+    fn test_strict_boundaries() {
+        for (lo, value, hi) in [
+            // On either end of the interval
+            (10, 10, 20),
+            (10, 20, 20),
+            // Outside it
+            (10, 5, 20),
+            (10, 25, 20),
+            // Intervals with nothing strictly inside
+            (10, 10, 11),
+            (10, 11, 11),
+            (10, 10, 10),
+        ] {
+            assert!(!run(Fp::from(lo), value, hi), "{} < {} < {}", lo, value, hi);
+        }
+        // -1 is just below 0 in the field, but it isn't an 8-bit bound
+        assert!(!run(-Fp::from(1), 0, 20));
+    }
+}
@@ -12,11 +12,13 @@ struct Sizes {
     LOOKUP_RANGE: usize,
 }
 
-/// A lookup table of values from 0..RANGE.
+/// A lookup table of values from 0..RANGE, each tagged with its bit length in `num_bits`, or of explicit entries
+/// passed to `with_entries`.
 #[derive(Debug, Clone)]
 pub(super) struct RangeTableConfig<F: PrimeField, const RANGE: usize> {
     pub(super) value: TableColumn,
     pub(super) num_bits: TableColumn,
+    entries: Option<Vec<F>>,
     _marker: PhantomData<F>,
 }
 
@@ -28,45 +30,64 @@ impl<F: PrimeField, const RANGE: usize> RangeTableConfig<F, RANGE> {
         Self {
             value,
             num_bits,
+            entries: None,
             _marker: PhantomData,
         }
     }
 
+    /// A table holding exactly `entries`, e.g. an S-box computed elsewhere, instead of 0..RANGE.
+    /// The length must be a power of two. Only the value column is filled, num_bits is left unassigned.
+    pub(super) fn with_entries(meta: &mut ConstraintSystem<F>, entries: Vec<F>) -> Self {
+        assert!(
+            entries.len().is_power_of_two(),
+            "Table has {} entries, which is not a power of two",
+            entries.len()
+        );
+        Self {
+            entries: Some(entries),
+            ..Self::configure(meta)
+        }
+    }
+
     pub(super) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        if let Some(entries) = &self.entries {
+            return layouter.assign_table(
+                || "load table entries",
+                |mut table| {
+                    for (offset, entry) in entries.iter().enumerate() {
+                        table.assign_cell(
+                            || "value",
+                            self.value,
+                            offset,
+                            || Value::known(*entry),
+                        )?;
+                    }
+                    Ok(())
+                },
+            );
+        }
+
         layouter.assign_table(
             || "load range-check table",
             |mut table| {
                 // Assign the values
-                let mut offset = 0;
-                for value in 0..RANGE {
+                for (offset, value) in (0..RANGE).enumerate() {
                     table.assign_cell(
                         || "value",
                         self.value,
                         offset,
                         || Value::known(F::from_u128(value as u128)),
                     )?;
-                    offset += 1;
                 }
 
-                // Assign the number of bits per value
-                let mut bitCount = 0;
-                let mut currPow2 = 1;
-                let mut currentBits = 0;
-                offset = 0;
-                for value in 0..RANGE {
+                // Assign the number of bits per value, i.e. its bit length, with 0 taking no bits
+                for (offset, value) in (0..RANGE).enumerate() {
                     table.assign_cell(
                         || "num_bits",
                         self.num_bits,
                         offset,
-                        || Value::known(F::from(currentBits as u64)),
+                        || Value::known(F::from((usize::BITS - value.leading_zeros()) as u64)),
                     )?;
-                    offset += 1;
-                    bitCount += 1;
-                    if (bitCount >= currPow2) {
-                        bitCount = 0;
-                        currentBits += 1;
-                        currPow2 *= 2;
-                    }
                 }
 
                 Ok(())
@@ -74,3 +95,99 @@ impl<F: PrimeField, const RANGE: usize> RangeTableConfig<F, RANGE> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, Selector},
+        poly::Rotation,
+    };
+
+    use super::*;
+
+    // A 16-entry table of squares mod 31, standing in for a table computed outside the circuit
+    fn squares<F: PrimeField>() -> Vec<F> {
+        (0..16u64).map(|i| F::from(i * i % 31)).collect()
+    }
+
+    #[derive(Default)]
+    struct EntriesCircuit<F: PrimeField> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for EntriesCircuit<F> {
+        type Config = (RangeTableConfig<F, 0>, Column<Advice>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let table = RangeTableConfig::with_entries(meta, squares());
+            let value = meta.advice_column();
+            let q_lookup = meta.complex_selector();
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q_lookup);
+                let value = meta.query_advice(value, Rotation::cur());
+                vec![(q * value, table.value)]
+            });
+            (table, value, q_lookup)
+        }
+
+        fn synthesize(
+            &self,
+            (table, value, q_lookup): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            table.load(&mut layouter)?;
+            layouter.assign_region(
+                || "Assign value",
+                |mut region| {
+                    q_lookup.enable(&mut region, 0)?;
+                    region.assign_advice(|| "value", value, 0, || self.value)
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_with_entries() {
+        let k = 5;
+        for entry in squares::<Fp>() {
+            let circuit = EntriesCircuit {
+                value: Value::known(entry),
+            };
+            MockProver::run(k, &circuit, vec![])
+                .unwrap()
+                .assert_satisfied();
+        }
+
+        // 3 is in 0..16 but isn't a square mod 31
+        let circuit = EntriesCircuit {
+            value: Value::known(Fp::from(3)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert_eq!(
+            prover.verify(),
+            Err(vec![VerifyFailure::Lookup {
+                lookup_index: 0,
+                location: FailureLocation::InRegion {
+                    region: (1, "Assign value").into(),
+                    offset: 0,
+                },
+            }])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Table has 15 entries, which is not a power of two")]
+    fn test_with_entries_wrong_length() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        RangeTableConfig::<Fp, 0>::with_entries(&mut meta, squares()[1..].to_vec());
+    }
+}
@@ -0,0 +1,345 @@
+use ff::{Field, FromUniformBytes, PrimeField};
+use halo2_proofs::{
+    circuit::{floor_planner::V1, AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem, Error, Selector},
+};
+use std::{collections::BTreeSet, marker::PhantomData};
+
+use crate::{
+    decompose_range_check::field_to_u128_exact, planner::WithFloorPlanner, trace::selector_rows,
+    witness::WitnessInput,
+};
+
+/// The largest `k` the helpers below will try before giving up.
+const MAX_K: u32 = 20;
+
+/// Runs the MockProver at the smallest `k` that has enough rows for `circuit`,
+/// returning both. Only running out of rows moves on to the next `k`, whether it's the circuit's rows or the
+/// instance column's, that `InstanceTooLarge` reports. Any other error, such as `Error::Synthesis` from a bad
+/// witness, is returned as it is.
+fn run_at_min_k<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+) -> Result<(u32, MockProver<F>), Error> {
+    for k in 1..=MAX_K {
+        match MockProver::run(k, circuit, instances.clone()) {
+            Err(Error::NotEnoughRowsAvailable { .. } | Error::InstanceTooLarge) => continue,
+            result => return result.map(|prover| (k, prover)),
+        }
+    }
+    panic!("circuit doesn't fit in 2^{} rows", MAX_K)
+}
+
+/// The smallest `k` such that `circuit` with `instances` can be synthesized in `2^k` rows.
+/// This only accounts for rows, the circuit may still fail to verify.
+pub(crate) fn min_k<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+) -> u32 {
+    expect_synthesized(run_at_min_k(circuit, instances)).0
+}
+
+/// Same as run_mock_auto, but returns the error if the circuit fails to synthesize, for tests that expect it to.
+pub(crate) fn try_run_mock_auto<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+) -> Result<MockProver<F>, Error> {
+    run_at_min_k(circuit, instances).map(|(_, prover)| prover)
+}
+
+/// Runs the MockProver at `min_k`, so tests don't need to hardcode a `k` that goes stale
+/// whenever the circuit's parameters change.
+pub(crate) fn run_mock_auto<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+) -> MockProver<F> {
+    expect_synthesized(try_run_mock_auto(circuit, instances))
+}
+
+fn expect_synthesized<T>(result: Result<T, Error>) -> T {
+    result.unwrap_or_else(|e| panic!("circuit failed to synthesize: {:?}", e))
+}
+
+/// Runs the MockProver at `k` and returns whether `circuit` verifies, for callers that branch on the result
+/// rather than panicking like `assert_satisfied`. A circuit that doesn't fit in `2^k` rows, or fails to
+/// synthesize at all, doesn't verify either.
+pub(crate) fn verify_ok<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+) -> bool {
+    MockProver::run(k, circuit, instances).is_ok_and(|prover| prover.verify().is_ok())
+}
+
+/// Runs the MockProver at `min_k` with `public` as the circuit's one instance column, and returns whether it
+/// verifies, for tests that try several claimed outputs against the same circuit.
+pub(crate) fn run<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(circuit: &C, public: &[F]) -> bool {
+    run_mock_auto(circuit, vec![public.to_vec()])
+        .verify()
+        .is_ok()
+}
+
+/// A test circuit around a chip that can take the witness for some of its cells from the caller, rather than
+/// computing it, e.g. the product of MulRangeChip::assign_product. Wrapped in Forged, it's laid out either
+/// with the chip's honest witness or with a forged one.
+pub(crate) trait ForgeableCircuit<F: Field>: Default {
+    type Config: Clone;
+    /// What a malicious prover supplies in place of the chip's honest witness.
+    type Witness: Clone;
+
+    /// The circuit without its private inputs, for keygen. Only circuits with a shape to keep override this.
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config;
+
+    /// Lays the circuit out, with `forged` in place of the chip's honest witness if it's given.
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>,
+        forged: Option<&Self::Witness>,
+    ) -> Result<(), Error>;
+}
+
+/// Lays out `C` under SimpleFloorPlanner, with a forged witness if one was set with `forge`, to model a
+/// malicious prover. Unlike a Corrupted cell, a forged witness goes through the chip like the honest one, so
+/// the cells computed from it and copied from it agree with it, and a test sees what the chip's own
+/// constraints make of it.
+pub(crate) struct Forged<F: Field, C: ForgeableCircuit<F>> {
+    circuit: C,
+    witness: Option<C::Witness>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, C: ForgeableCircuit<F>> Forged<F, C> {
+    /// `circuit` with the chip's honest witness.
+    pub(crate) fn honest(circuit: C) -> Self {
+        Self {
+            circuit,
+            witness: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The same circuit, with `witness` in place of the chip's honest one.
+    pub(crate) fn forge(self, witness: C::Witness) -> Self {
+        Self {
+            witness: Some(witness),
+            ..self
+        }
+    }
+}
+
+impl<F: Field, C: ForgeableCircuit<F>> Circuit<F> for Forged<F, C> {
+    type Config = C::Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::honest(self.circuit.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit
+            .synthesize(config, layouter, self.witness.as_ref())
+    }
+}
+
+/// Verifies many witnesses of the same circuit type, each with its own public inputs, the way one keygen is
+/// reused to prove many statements. Every circuit of type `C` configures the same columns and gates, and only
+/// the witness changes, so `k` is found once from the first circuit and reused for the rest, rather than
+/// searched for again on every run. Returns each circuit's verification result, in order.
+pub(crate) fn run_many<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(
+    circuits: &[(C, Vec<Vec<F>>)],
+) -> Vec<Result<(), Vec<VerifyFailure>>> {
+    let Some((first, instances)) = circuits.first() else {
+        return vec![];
+    };
+    let k = min_k(first, instances.clone());
+    circuits
+        .iter()
+        .map(|(circuit, instances)| {
+            MockProver::run(k, circuit, instances.clone())
+                .unwrap_or_else(|e| panic!("circuit doesn't fit in 2^{} rows: {:?}", k, e))
+                .verify()
+        })
+        .collect()
+}
+
+/// Runs `circuit` under both SimpleFloorPlanner and V1, each at its own `min_k`, and asserts that it either
+/// verifies under both or fails under both. Returns the two `min_k`s, `(simple, v1)`.
+pub(crate) fn compare_planners<F: FromUniformBytes<64> + Ord, C: Circuit<F>>(
+    circuit: impl Fn() -> C,
+    instances: Vec<Vec<F>>,
+) -> (u32, u32) {
+    let simple = WithFloorPlanner::<_, SimpleFloorPlanner>::new(circuit());
+    let (simple_k, simple) = expect_synthesized(run_at_min_k(&simple, instances.clone()));
+    let v1 = WithFloorPlanner::<_, V1>::new(circuit());
+    let (v1_k, v1) = expect_synthesized(run_at_min_k(&v1, instances));
+    assert_eq!(
+        simple.verify().is_ok(),
+        v1.verify().is_ok(),
+        "SimpleFloorPlanner and V1 disagree on whether the circuit verifies"
+    );
+    (simple_k, v1_k)
+}
+
+/// Asserts that once `circuit` is synthesized, the selector picked out of its config by `selector` is enabled on
+/// exactly the `expected` rows, counting from the top of the circuit. This catches a gate that misses its
+/// first or last row, which otherwise only shows up when a wrong witness happens to slip through.
+pub(crate) fn assert_selector_rows<F: PrimeField, C: Circuit<F>>(
+    circuit: &C,
+    instances: &[Vec<F>],
+    selector: impl FnOnce(&C::Config) -> Selector,
+    expected: impl IntoIterator<Item = usize>,
+) {
+    let rows = selector_rows(circuit, instances, selector).expect("circuit failed to synthesize");
+    let expected: BTreeSet<usize> = expected.into_iter().collect();
+    assert_eq!(
+        rows, expected,
+        "selector enabled on rows {:?}, expected {:?}",
+        rows, expected
+    );
+}
+
+/// Builds the range check circuit `C` around each value through WitnessInput, and asserts that the values in
+/// `in_range` verify and the ones in `out_of_range` don't. Any circuit implementing WitnessInput can be driven
+/// this way, whatever type it keeps its witness as. A value the circuit can't hold as its witness counts as
+/// rejected.
+pub(crate) fn assert_witness_range<C: WitnessInput<Fp>>(in_range: &[Fp], out_of_range: &[Fp]) {
+    for value in in_range {
+        let circuit = C::from_witness(Value::known(*value))
+            .unwrap_or_else(|e| panic!("{:?} can't be witnessed: {:?}", value, e));
+        run_mock_auto(&circuit, vec![]).assert_satisfied();
+    }
+    for value in out_of_range {
+        if let Ok(circuit) = C::from_witness(Value::known(*value)) {
+            assert!(
+                run_mock_auto(&circuit, vec![]).verify().is_err(),
+                "{:?} passed the range check",
+                value
+            );
+        }
+    }
+}
+
+/// The integer behind `cell`, read from its little-endian repr, for checking assigned cells while debugging.
+/// None if the value is unknown, as it is during keygen, or if it doesn't fit in a u128.
+pub(crate) fn cell_to_u128(cell: &AssignedCell<Fp, Fp>) -> Option<u128> {
+    let mut value = None;
+    cell.value().map(|v| value = Some(*v));
+    value.and_then(field_to_u128_exact)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Advice, Column, ConstraintSystem, Error},
+    };
+
+    use super::*;
+
+    // Assigns `rows` cells to a single advice column
+    #[derive(Default)]
+    struct RowsCircuit {
+        rows: usize,
+    }
+
+    impl Circuit<Fp> for RowsCircuit {
+        type Config = Column<Advice>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { rows: self.rows }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            meta.advice_column()
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "Assign rows",
+                |mut region| {
+                    for offset in 0..self.rows {
+                        region.assign_advice(
+                            || "row",
+                            config,
+                            offset,
+                            || Value::known(Fp::from(offset as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_run_mock_auto() {
+        for rows in [1, 10, 100, 300] {
+            let circuit = RowsCircuit { rows };
+            let k = min_k(&circuit, vec![]);
+            run_mock_auto(&circuit, vec![]).assert_satisfied();
+            MockProver::run(k, &circuit, vec![])
+                .unwrap()
+                .assert_satisfied();
+            assert!(MockProver::run(k - 1, &circuit, vec![]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_ok_too_few_rows() {
+        let circuit = RowsCircuit { rows: 100 };
+        let k = min_k(&circuit, vec![]);
+        assert!(verify_ok(k, &circuit, vec![]));
+        // Where run_mock_auto would panic, this just doesn't verify
+        assert!(!verify_ok(k - 1, &circuit, vec![]));
+    }
+
+    // Fails to synthesize whatever k it's given
+    #[derive(Default)]
+    struct SynthesisErrorCircuit;
+
+    impl Circuit<Fp> for SynthesisErrorCircuit {
+        type Config = ();
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(_: &mut ConstraintSystem<Fp>) -> Self::Config {}
+
+        fn synthesize(&self, _: Self::Config, _: impl Layouter<Fp>) -> Result<(), Error> {
+            Err(Error::Synthesis)
+        }
+    }
+
+    #[test]
+    fn test_run_mock_auto_synthesis_error() {
+        // The error comes straight back, rather than every k up to MAX_K being tried
+        assert!(matches!(
+            try_run_mock_auto(&SynthesisErrorCircuit, vec![]),
+            Err(Error::Synthesis)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "circuit failed to synthesize: Synthesis")]
+    fn test_run_mock_auto_synthesis_error_panics() {
+        run_mock_auto(&SynthesisErrorCircuit, vec![]);
+    }
+}
@@ -0,0 +1,256 @@
+use ff::{Field, PrimeField};
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Renders every advice, fixed and selector cell the circuit assigns as a table, one line per row.
+/// This is meant for small teaching circuits, where it's easier to read than the layout image.
+///
+/// This version of the MockProver keeps its cell matrices private, so rather than reading them back after
+/// `MockProver::run`, we run the circuit's floor planner again against an `Assignment` that records each cell.
+/// The cells are the same ones the MockProver sees, so call this next to `MockProver::run` with the same inputs.
+#[cfg(feature = "trace")]
+pub(crate) fn dump_assignment<F: PrimeField, C: Circuit<F>>(
+    circuit: &C,
+    instances: &[Vec<F>],
+) -> Result<String, Error> {
+    record(circuit, instances).map(|(trace, _)| trace.render())
+}
+
+/// The rows `selector` is enabled on once `circuit` is synthesized, counting from the top of the circuit.
+/// `selector` picks it out of the circuit's config, which is the same on every call to `configure`.
+pub(crate) fn selector_rows<F: PrimeField, C: Circuit<F>>(
+    circuit: &C,
+    instances: &[Vec<F>],
+    selector: impl FnOnce(&C::Config) -> Selector,
+) -> Result<BTreeSet<usize>, Error> {
+    let (trace, config) = record(circuit, instances)?;
+    let selector = selector(&config);
+    Ok(trace
+        .selectors
+        .into_iter()
+        .filter(|(s, _)| *s == selector)
+        .flat_map(|(_, rows)| rows)
+        .collect())
+}
+
+// Synthesizes the circuit into a Trace, returning the config it was synthesized with too
+fn record<'a, F: PrimeField, C: Circuit<F>>(
+    circuit: &C,
+    instances: &'a [Vec<F>],
+) -> Result<(Trace<'a, F>, C::Config), Error> {
+    let mut meta = ConstraintSystem::default();
+    let config = C::configure(&mut meta);
+    let mut trace = Trace {
+        instances,
+        advice: vec![BTreeMap::new(); meta.num_advice_columns()],
+        fixed: vec![BTreeMap::new(); meta.num_fixed_columns()],
+        selectors: vec![],
+    };
+    C::FloorPlanner::synthesize(
+        &mut trace,
+        circuit,
+        config.clone(),
+        meta.constants().clone(),
+    )?;
+    Ok((trace, config))
+}
+
+/// A cell that was assigned, but whose value isn't known, as happens during keygen.
+#[cfg(feature = "trace")]
+const UNKNOWN: &str = "?";
+/// A cell that was never assigned.
+#[cfg(feature = "trace")]
+const UNASSIGNED: &str = ".";
+
+struct Trace<'a, F: Field> {
+    instances: &'a [Vec<F>],
+    advice: Vec<BTreeMap<usize, Option<F>>>,
+    fixed: Vec<BTreeMap<usize, Option<F>>>,
+    // Selectors in the order they're first enabled, since they don't expose their index
+    selectors: Vec<(Selector, Vec<usize>)>,
+}
+
+impl<F: PrimeField> Trace<'_, F> {
+    #[cfg(feature = "trace")]
+    fn render(&self) -> String {
+        use std::fmt::Write;
+
+        let num_rows = self
+            .advice
+            .iter()
+            .chain(&self.fixed)
+            .filter_map(|column| column.keys().next_back())
+            .chain(self.selectors.iter().flat_map(|(_, rows)| rows.iter()))
+            .max()
+            .map_or(0, |row| row + 1);
+
+        let header = ["row".to_string()]
+            .into_iter()
+            .chain((0..self.advice.len()).map(|i| format!("advice_{}", i)))
+            .chain((0..self.fixed.len()).map(|i| format!("fixed_{}", i)))
+            .chain((0..self.selectors.len()).map(|i| format!("selector_{}", i)));
+        let rows = (0..num_rows).map(|row| {
+            let cell = |column: &BTreeMap<usize, Option<F>>| match column.get(&row) {
+                Some(Some(value)) => format_value(value),
+                Some(None) => UNKNOWN.to_string(),
+                None => UNASSIGNED.to_string(),
+            };
+            [row.to_string()]
+                .into_iter()
+                .chain(self.advice.iter().map(cell))
+                .chain(self.fixed.iter().map(cell))
+                .chain(
+                    self.selectors
+                        .iter()
+                        .map(|(_, rows)| (rows.contains(&row) as u8).to_string()),
+                )
+                .collect::<Vec<_>>()
+        });
+        let table: Vec<Vec<String>> = std::iter::once(header.collect()).chain(rows).collect();
+
+        let widths: Vec<usize> = (0..table[0].len())
+            .map(|i| table.iter().map(|line| line[i].len()).max().unwrap_or(0))
+            .collect();
+        let mut out = String::new();
+        for line in &table {
+            let cells: Vec<String> = line
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect();
+            writeln!(out, "{}", cells.join(" | ")).unwrap();
+        }
+        out
+    }
+}
+
+/// Small values print in decimal, and so do small negative ones like `-1`. Anything else prints as hex.
+#[cfg(feature = "trace")]
+fn format_value<F: PrimeField>(value: &F) -> String {
+    let small = |value: &F| {
+        let repr = value.to_repr();
+        let (low, high) = repr.as_ref().split_at(8);
+        high.iter()
+            .all(|byte| *byte == 0)
+            .then(|| u64::from_le_bytes(low.try_into().unwrap()))
+    };
+    if let Some(value) = small(value) {
+        value.to_string()
+    } else if let Some(value) = small(&-*value) {
+        format!("-{}", value)
+    } else {
+        let repr = value.to_repr();
+        let hex: String = repr
+            .as_ref()
+            .iter()
+            .rev()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("0x{}", hex)
+    }
+}
+
+fn record_cell<F: Field>(
+    column: &mut BTreeMap<usize, Option<F>>,
+    row: usize,
+    value: Value<Assigned<F>>,
+) {
+    let mut known = None;
+    value.map(|value| known = Some(value.evaluate()));
+    column.insert(row, known);
+}
+
+impl<F: PrimeField> Assignment<F> for Trace<'_, F> {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        match self.selectors.iter_mut().find(|(s, _)| s == selector) {
+            Some((_, rows)) => rows.push(row),
+            None => self.selectors.push((*selector, vec![row])),
+        }
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.instances
+            .get(column.index())
+            .and_then(|column| column.get(row))
+            .map(|value| Value::known(*value))
+            .ok_or(Error::BoundsFailure)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        record_cell(&mut self.advice[column.index()], row, to().map(Into::into));
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        record_cell(&mut self.fixed[column.index()], row, to().map(Into::into));
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        // Only the row it starts from is shown, since the fill runs to the end of the usable rows
+        record_cell(&mut self.fixed[column.index()], row, to);
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
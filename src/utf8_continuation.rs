@@ -0,0 +1,231 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::bits_range_check::BitsRangeCheckConfig;
+
+// The bits under the mask 0xC0, and the value they must take, 0x80
+const PAYLOAD_BITS: usize = 6;
+
+/// Checks that a byte is a UTF-8 continuation byte, `(byte & 0xC0) == 0x80`, i.e. of the form `10xxxxxx`, and
+/// returns its 6-bit payload. The byte is split into its top two bits and the rest:
+///     $$byte = p + 2^6 \cdot b_6 + 2^7 \cdot b_7$$
+/// with $b_6, b_7$ boolean and $p$ range-checked to 6 bits, which also bounds the byte to 8 bits. The mask is then
+/// applied with equality constraints pinning $b_7 = 1$ and $b_6 = 0$ to constants.
+#[derive(Debug, Clone)]
+struct Utf8ContinuationConfig<F: PrimeField> {
+    byte: Column<Advice>,
+    payload: Column<Advice>,
+    bit_6: Column<Advice>,
+    bit_7: Column<Advice>,
+    q_split: Selector,
+    range: BitsRangeCheckConfig<F>,
+}
+
+impl<F: PrimeField> Utf8ContinuationConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let byte = meta.advice_column();
+        let payload = meta.advice_column();
+        let bit_6 = meta.advice_column();
+        let bit_7 = meta.advice_column();
+        let q_split = meta.selector();
+        let range = BitsRangeCheckConfig::configure(meta);
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+        for column in [byte, payload, bit_6, bit_7] {
+            meta.enable_equality(column);
+        }
+
+        //     byte   |  payload  |  bit_6  |  bit_7  | q_split
+        //    -------------------------------------------------------
+        //     byte   |     p     |    0    |    1    |    1
+        meta.create_gate("split top bits", |meta| {
+            let q = meta.query_selector(q_split);
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let payload = meta.query_advice(payload, Rotation::cur());
+            let bit_6 = meta.query_advice(bit_6, Rotation::cur());
+            let bit_7 = meta.query_advice(bit_7, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            let boolean = |bit: Expression<F>| bit.clone() * (one.clone() - bit);
+            Constraints::with_selector(
+                q,
+                [
+                    ("bit_6 is boolean", boolean(bit_6.clone())),
+                    ("bit_7 is boolean", boolean(bit_7.clone())),
+                    (
+                        "byte = p + 2^6 * bit_6 + 2^7 * bit_7",
+                        byte - (payload
+                            + Expression::Constant(F::from(1 << 6)) * bit_6
+                            + Expression::Constant(F::from(1 << 7)) * bit_7),
+                    ),
+                ],
+            )
+        });
+
+        Self {
+            byte,
+            payload,
+            bit_6,
+            bit_7,
+            q_split,
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Utf8ContinuationChip<F: PrimeField> {
+    config: Utf8ContinuationConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> Utf8ContinuationChip<F> {
+    fn construct(config: Utf8ContinuationConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Utf8ContinuationConfig<F> {
+        Utf8ContinuationConfig::configure(meta)
+    }
+
+    /// Returns a cell constrained to the low 6 bits of `byte`, which must be a UTF-8 continuation byte.
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        byte: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let top_bits = byte.value().map(|byte| {
+            let byte = byte.to_repr().as_ref()[0];
+            (byte >> 6 & 1 == 1, byte >> 7 & 1 == 1)
+        });
+        self.assign_top_bits(layouter, byte, top_bits)
+    }
+
+    // Like assign, but with the given witness for bits 6 and 7, the payload taking up the rest of the byte
+    fn assign_top_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        byte: &AssignedCell<F, F>,
+        top_bits: Value<(bool, bool)>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let payload = layouter.assign_region(
+            || "utf-8 continuation",
+            |mut region| {
+                let offset = 0;
+                config.q_split.enable(&mut region, offset)?;
+                let byte = byte.copy_advice(|| "byte", &mut region, config.byte, offset)?;
+                let bit_6 = top_bits.map(|(bit_6, _)| F::from(bit_6 as u64));
+                let bit_7 = top_bits.map(|(_, bit_7)| F::from(bit_7 as u64));
+                let payload = byte.value().copied()
+                    - bit_6 * Value::known(F::from(1 << 6))
+                    - bit_7 * Value::known(F::from(1 << 7));
+                let bit_6 = region.assign_advice(|| "bit_6", config.bit_6, offset, || bit_6)?;
+                let bit_7 = region.assign_advice(|| "bit_7", config.bit_7, offset, || bit_7)?;
+                // The mask: (byte & 0xC0) == 0x80
+                region.constrain_constant(bit_6.cell(), F::ZERO)?;
+                region.constrain_constant(bit_7.cell(), F::ONE)?;
+                region.assign_advice(|| "payload", config.payload, offset, || payload)
+            },
+        )?;
+        config.range.assign(
+            layouter.namespace(|| "range check payload"),
+            &payload,
+            PAYLOAD_BITS,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, plonk::Instance};
+
+    use super::*;
+    use crate::testing::{run, ForgeableCircuit, Forged};
+
+    // Checks the private byte, and exposes its payload at instance row 0. The witness for the top two bits can
+    // be forged.
+    #[derive(Default)]
+    struct Utf8ContinuationCircuit<F: PrimeField> {
+        byte: Value<F>,
+    }
+
+    impl<F: PrimeField> ForgeableCircuit<F> for Utf8ContinuationCircuit<F> {
+        type Config = (Utf8ContinuationConfig<F>, Column<Advice>, Column<Instance>);
+        type Witness = Value<(bool, bool)>;
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (Utf8ContinuationChip::configure(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+            top_bits: Option<&Value<(bool, bool)>>,
+        ) -> Result<(), Error> {
+            let byte = layouter.assign_region(
+                || "Load byte",
+                |mut region| region.assign_advice(|| "byte", input, 0, || self.byte),
+            )?;
+            let chip = Utf8ContinuationChip::construct(config);
+            let payload = match top_bits {
+                Some(top_bits) => {
+                    chip.assign_top_bits(layouter.namespace(|| "check byte"), &byte, *top_bits)
+                }
+                None => chip.assign(layouter.namespace(|| "check byte"), &byte),
+            }?;
+            layouter.constrain_instance(payload.cell(), instance, 0)
+        }
+    }
+
+    fn circuit(byte: u64) -> Forged<Fp, Utf8ContinuationCircuit<Fp>> {
+        Forged::honest(Utf8ContinuationCircuit {
+            byte: Value::known(Fp::from(byte)),
+        })
+    }
+
+    #[test]
+    fn test_continuation_bytes() {
+        // The payload of the continuation bytes 0x80..=0xBF is everything below the top two bits
+        for byte in 0x80..=0xbf {
+            let payload = Fp::from(byte & 0x3f);
+            assert!(run(&circuit(byte), &[payload]), "{:#x}", byte);
+            assert!(
+                !run(&circuit(byte), &[payload + Fp::from(1)]),
+                "{:#x}",
+                byte
+            );
+        }
+    }
+
+    #[test]
+    fn test_non_continuation_bytes() {
+        // ASCII, the lead bytes of multi-byte sequences, and 0x140, which would be 0x80 + 0xC0 past 8 bits
+        for byte in [0x00, 0x41, 0x7f, 0xc0, 0xc3, 0xe2, 0xf0, 0xff, 0x140] {
+            assert!(
+                !run(&circuit(byte), &[Fp::from(byte & 0x3f)]),
+                "{:#x}",
+                byte
+            );
+        }
+    }
+
+    #[test]
+    fn test_forged_top_bits() {
+        // Claiming 0xC5 is 10xxxxxx leaves a payload of 0x45, which doesn't fit in 6 bits
+        let forged = circuit(0xc5).forge(Value::known((false, true)));
+        assert!(!run(&forged, &[Fp::from(0x45)]));
+    }
+}
@@ -0,0 +1,9 @@
+use wasm_bindgen::prelude::*;
+
+use crate::range_proof::prove_range_check;
+
+/// Proves `value < range` in the browser, returning the serialized proof.
+#[wasm_bindgen]
+pub fn wasm_prove_range_check(value: u32, range: u32) -> Vec<u8> {
+    prove_range_check(value, range).expect("Synthesis should not fail for a u32 value and range")
+}
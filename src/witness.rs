@@ -0,0 +1,24 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Circuit, Error},
+};
+
+/// A circuit which range-checks a single witnessed value, whatever type it keeps the witness as internally
+/// (e.g. `Value<F>` for RangeCheckCircuit, `u128` for DecomposeRangeCheckCircuit), so a generic harness can
+/// build and drive any of them with a `Value<F>`.
+pub(crate) trait WitnessInput<F: PrimeField>: Circuit<F> {
+    /// The circuit with `value` as its witness. An unknown value gives the keygen circuit. Returns
+    /// `Error::Synthesis` if the circuit's witness type can't hold `value` exactly.
+    fn from_witness(value: Value<F>) -> Result<Self, Error>
+    where
+        Self: Sized;
+
+    /// Assigns and range-checks `value` with the circuit's config, converting it to whatever the config takes.
+    /// The circuit's `synthesize` goes through this too. Returns the checked cell.
+    fn assign_witness(
+        config: &Self::Config,
+        layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error>;
+}